@@ -0,0 +1,30 @@
+use network::{request, Type};
+
+use crate::{
+    commands::CommandError,
+    types::{Lobby, SessionToken},
+};
+
+pub fn answer_takeback_cmd(
+    token: &SessionToken,
+    accept: bool,
+    active_lobby: &Option<Lobby>,
+) -> Result<(), CommandError> {
+    if active_lobby.is_none() {
+        return Err(CommandError::NotConnected);
+    }
+
+    request::<_, _, ()>(
+        active_lobby.as_ref().unwrap().addr,
+        Type::AnswerTakeback,
+        &(token, accept),
+    )?;
+
+    if accept {
+        println!("accepted the takeback");
+    } else {
+        println!("declined the takeback");
+    }
+
+    Ok(())
+}
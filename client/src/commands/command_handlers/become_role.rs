@@ -2,11 +2,11 @@ use network::{request, Type};
 
 use crate::{
     commands::CommandError,
-    types::{Lobby, UserId, UserType},
+    types::{Lobby, SessionToken, UserType},
 };
 
 pub fn become_role_cmd(
-    user_id: &UserId,
+    token: &SessionToken,
     user_type: UserType,
     active_lobby: &Option<Lobby>,
 ) -> Result<(), CommandError> {
@@ -17,7 +17,7 @@ pub fn become_role_cmd(
     request(
         active_lobby.as_ref().unwrap().addr,
         Type::BecomeRole,
-        &(*user_id, user_type),
+        &(token, user_type),
     )?;
 
     println!("will become {:?}", user_type);
@@ -0,0 +1,26 @@
+use network::{request, Type};
+
+use crate::{
+    commands::CommandError,
+    types::{GameSettings, Lobby, SessionToken},
+};
+
+pub fn change_settings_cmd(
+    token: &SessionToken,
+    settings: GameSettings,
+    active_lobby: &Option<Lobby>,
+) -> Result<(), CommandError> {
+    if active_lobby.is_none() {
+        return Err(CommandError::NotConnected);
+    }
+
+    request::<_, _, ()>(
+        active_lobby.as_ref().unwrap().addr,
+        Type::ChangeSettings,
+        &(token, &settings),
+    )?;
+
+    println!("settings changed to {settings:?}");
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use network::{request, Type};
+
+use crate::{
+    commands::CommandError,
+    types::{ChatMessage, Lobby, SessionToken},
+};
+
+pub fn get_chat_history_cmd(
+    token: &SessionToken,
+    before: Option<u32>,
+    offset: u32,
+    active_lobby: &Option<Lobby>,
+) -> Result<Vec<ChatMessage>, CommandError> {
+    if active_lobby.is_none() {
+        return Err(CommandError::NotConnected);
+    }
+
+    let messages: Vec<ChatMessage> = request(
+        active_lobby.as_ref().unwrap().addr,
+        Type::GetChatHistory,
+        &(token, before, offset),
+    )?;
+
+    for message in messages.iter() {
+        println!("{message}");
+    }
+
+    Ok(messages)
+}
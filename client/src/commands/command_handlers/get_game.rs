@@ -0,0 +1,15 @@
+use network::{request, Type};
+
+use crate::{
+    commands::CommandError,
+    types::{GameRecord, SessionToken},
+    SERVER_ADDR,
+};
+
+pub fn get_game_cmd(token: &SessionToken, game_id: u32) -> Result<GameRecord, CommandError> {
+    let game: GameRecord = request(SERVER_ADDR, Type::GetGame, &(token, game_id))?;
+
+    println!("received: {game:?}");
+
+    Ok(game)
+}
@@ -0,0 +1,26 @@
+use network::{request, Type};
+
+use crate::{
+    commands::CommandError,
+    types::{GameSummary, SessionToken, UserId},
+    SERVER_ADDR,
+};
+
+pub fn get_game_history_cmd(
+    token: &SessionToken,
+    user_id: UserId,
+    start: u32,
+    offset: u32,
+) -> Result<Vec<GameSummary>, CommandError> {
+    let games: Vec<GameSummary> = request(
+        SERVER_ADDR,
+        Type::GetGameHistory,
+        &(token, user_id, start, offset),
+    )?;
+
+    for game in games.iter() {
+        println!("{game:?}");
+    }
+
+    Ok(games)
+}
@@ -0,0 +1,31 @@
+use network::{request, Type};
+
+use crate::{
+    commands::CommandError,
+    types::{Leaderboard, Role, SessionToken},
+    SERVER_ADDR,
+};
+
+pub fn get_leaderboard_cmd(
+    token: &SessionToken,
+    role: Role,
+    start: u32,
+    offset: u32,
+) -> Result<Leaderboard, CommandError> {
+    let leaderboard: Leaderboard = request(
+        SERVER_ADDR,
+        Type::GetLeaderboard,
+        &(token, role, start, offset),
+    )?;
+
+    for entry in leaderboard.entries.iter() {
+        println!("{:3}. {} ({})", entry.rank, entry.name, entry.rating);
+    }
+
+    match leaderboard.user.as_ref() {
+        Some(user) => println!("your rank: {} ({})", user.rank, user.rating),
+        None => println!("you are not rated yet"),
+    }
+
+    Ok(leaderboard)
+}
@@ -2,17 +2,17 @@ use network::{request, Type};
 
 use crate::{
     commands::CommandError,
-    types::{LobbyAddrVec, UserId},
+    types::{LobbyAddrVec, SessionToken},
     SERVER_ADDR,
 };
 
 pub fn get_lobbies_cmd(
-    user_id: &UserId,
+    token: &SessionToken,
     start: u32,
     offset: u32,
 ) -> Result<LobbyAddrVec, CommandError> {
     let new_lobbies: LobbyAddrVec =
-        request(SERVER_ADDR, Type::GetLobbies, &(*user_id, start, offset))?;
+        request(SERVER_ADDR, Type::GetLobbies, &(token, start, offset))?;
 
     println!("received: {new_lobbies:?}");
 
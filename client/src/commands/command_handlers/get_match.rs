@@ -0,0 +1,15 @@
+use network::{request, Type};
+
+use crate::{
+    commands::CommandError,
+    types::{MatchRecord, SessionToken},
+    SERVER_ADDR,
+};
+
+pub fn get_match_cmd(token: &SessionToken, match_id: u32) -> Result<MatchRecord, CommandError> {
+    let series: MatchRecord = request(SERVER_ADDR, Type::GetMatch, &(token, match_id))?;
+
+    println!("received: {series:?}");
+
+    Ok(series)
+}
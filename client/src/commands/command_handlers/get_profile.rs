@@ -0,0 +1,15 @@
+use network::{request, Type};
+
+use crate::{
+    commands::CommandError,
+    types::{Profile, SessionToken, UserId},
+    SERVER_ADDR,
+};
+
+pub fn get_profile_cmd(token: &SessionToken, user_id: UserId) -> Result<Profile, CommandError> {
+    let profile: Profile = request(SERVER_ADDR, Type::GetProfile, &(token, user_id))?;
+
+    println!("received: {profile:?}");
+
+    Ok(profile)
+}
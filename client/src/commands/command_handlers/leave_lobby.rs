@@ -2,11 +2,11 @@ use network::{request, Type};
 
 use crate::{
     commands::CommandError,
-    types::{Lobby, UserId},
+    types::{Lobby, SessionToken},
 };
 
 pub fn leave_lobby_cmd(
-    user_id: &UserId,
+    token: &SessionToken,
     active_lobby: &mut Option<Lobby>,
 ) -> Result<(), CommandError> {
     if active_lobby.is_none() {
@@ -16,7 +16,7 @@ pub fn leave_lobby_cmd(
     request(
         active_lobby.as_ref().unwrap().addr,
         Type::LeaveLobby,
-        &user_id,
+        token,
     )?;
 
     *active_lobby = None;
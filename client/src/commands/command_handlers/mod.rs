@@ -11,9 +11,23 @@ mod get_lobby_state;
 mod join_lobby;
 mod leave_lobby;
 
+mod accept_draw;
+mod answer_takeback;
 mod become_role;
 mod change_name;
+mod change_settings;
+mod get_chat_history;
 mod make_host;
+mod offer_draw;
+mod rematch;
+mod request_takeback;
+mod resign;
+
+mod get_game;
+mod get_game_history;
+mod get_leaderboard;
+mod get_match;
+mod get_profile;
 
 pub use clear::clear_cmd;
 pub use ping::ping_cmd;
@@ -28,6 +42,20 @@ pub use get_lobby_state::get_lobby_state;
 pub use join_lobby::join_lobby_cmd;
 pub use leave_lobby::leave_lobby_cmd;
 
+pub use accept_draw::accept_draw_cmd;
+pub use answer_takeback::answer_takeback_cmd;
 pub use become_role::become_role_cmd;
 pub use change_name::change_name_cmd;
+pub use change_settings::change_settings_cmd;
+pub use get_chat_history::get_chat_history_cmd;
 pub use make_host::make_host_cmd;
+pub use offer_draw::offer_draw_cmd;
+pub use rematch::rematch_cmd;
+pub use request_takeback::request_takeback_cmd;
+pub use resign::resign_cmd;
+
+pub use get_game::get_game_cmd;
+pub use get_game_history::get_game_history_cmd;
+pub use get_leaderboard::get_leaderboard_cmd;
+pub use get_match::get_match_cmd;
+pub use get_profile::get_profile_cmd;
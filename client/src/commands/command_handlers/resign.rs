@@ -0,0 +1,18 @@
+use network::{request, Type};
+
+use crate::{
+    commands::CommandError,
+    types::{Lobby, SessionToken},
+};
+
+pub fn resign_cmd(token: &SessionToken, active_lobby: &Option<Lobby>) -> Result<(), CommandError> {
+    if active_lobby.is_none() {
+        return Err(CommandError::NotConnected);
+    }
+
+    request::<_, _, ()>(active_lobby.as_ref().unwrap().addr, Type::Resign, token)?;
+
+    println!("resigned");
+
+    Ok(())
+}
@@ -12,8 +12,10 @@ use network::{SendRecv, Type};
 use crate::types::{BoolMutex, EventQueue, EventQueueItem};
 
 use super::{
-    Event, GameStartedEvent, GameUpdatedEvent, LobbyClosingEvent, NetworkEvent, PlayerJoinedEvent,
-    PlayerLeftEvent, PlayerUpdatedEvent,
+    DrawOfferedEvent, Event, GameEndedEvent, GameRewoundEvent, GameStartedEvent, GameUpdatedEvent,
+    LobbyClosingEvent, MatchUpdatedEvent, NetworkEvent, PhaseChangedEvent, PlayerJoinedEvent,
+    PlayerLeftEvent, PlayerUpdatedEvent, RematchVotedEvent, SettingsChangedEvent,
+    TakebackDeclinedEvent, TakebackRequestedEvent,
 };
 
 pub struct EventLoop {
@@ -128,10 +130,52 @@ pub fn network_event_loop_thread(running: BoolMutex, events: EventQueue, server:
                         Ok(buf) => Some(NetworkEvent::GameUpdated(GameUpdatedEvent::new(buf))),
                         Err(_) => None,
                     },
+                    Type::PhaseChanged => match bincode::deserialize(&buf) {
+                        Ok(buf) => Some(NetworkEvent::PhaseChanged(PhaseChangedEvent::new(buf))),
+                        Err(_) => None,
+                    },
+                    Type::DrawOffered => match bincode::deserialize(&buf) {
+                        Ok(buf) => Some(NetworkEvent::DrawOffered(DrawOfferedEvent::new(buf))),
+                        Err(_) => None,
+                    },
+                    Type::GameEnded => match bincode::deserialize(&buf) {
+                        Ok(buf) => Some(NetworkEvent::GameEnded(GameEndedEvent::new(buf))),
+                        Err(_) => None,
+                    },
+                    Type::TakebackRequested => match bincode::deserialize(&buf) {
+                        Ok(buf) => Some(NetworkEvent::TakebackRequested(
+                            TakebackRequestedEvent::new(buf),
+                        )),
+                        Err(_) => None,
+                    },
+                    Type::TakebackDeclined => match bincode::deserialize(&buf) {
+                        Ok(buf) => Some(NetworkEvent::TakebackDeclined(
+                            TakebackDeclinedEvent::new(buf),
+                        )),
+                        Err(_) => None,
+                    },
+                    Type::GameRewound => match bincode::deserialize(&buf) {
+                        Ok(buf) => Some(NetworkEvent::GameRewound(GameRewoundEvent::new(buf))),
+                        Err(_) => None,
+                    },
+                    Type::RematchVoted => match bincode::deserialize(&buf) {
+                        Ok(buf) => Some(NetworkEvent::RematchVoted(RematchVotedEvent::new(buf))),
+                        Err(_) => None,
+                    },
+                    Type::MatchUpdated => match bincode::deserialize(&buf) {
+                        Ok(buf) => Some(NetworkEvent::MatchUpdated(MatchUpdatedEvent::new(buf))),
+                        Err(_) => None,
+                    },
                     Type::LobbyClosing => match bincode::deserialize(&buf) {
                         Ok(buf) => Some(NetworkEvent::LobbyClosing(LobbyClosingEvent::new(buf))),
                         Err(_) => None,
                     },
+                    Type::SettingsChanged => match bincode::deserialize(&buf) {
+                        Ok(buf) => Some(NetworkEvent::SettingsChanged(SettingsChangedEvent::new(
+                            buf,
+                        ))),
+                        Err(_) => None,
+                    },
                     _ => None,
                 };
 
@@ -0,0 +1,12 @@
+use crate::types::Role;
+
+#[derive(Clone, Debug)]
+pub struct DrawOfferedEvent {
+    pub role: Role, // the player that offered the draw
+}
+
+impl DrawOfferedEvent {
+    pub fn new(role: Role) -> DrawOfferedEvent {
+        DrawOfferedEvent { role }
+    }
+}
@@ -0,0 +1,12 @@
+use rules::GameResult;
+
+#[derive(Clone, Debug)]
+pub struct GameEndedEvent {
+    pub result: GameResult,
+}
+
+impl GameEndedEvent {
+    pub fn new(result: GameResult) -> GameEndedEvent {
+        GameEndedEvent { result }
+    }
+}
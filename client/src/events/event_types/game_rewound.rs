@@ -0,0 +1,12 @@
+use rules::GameState;
+
+#[derive(Clone, Debug)]
+pub struct GameRewoundEvent {
+    pub game: GameState, // the game as it was before the turn that was taken back
+}
+
+impl GameRewoundEvent {
+    pub fn new(game: GameState) -> GameRewoundEvent {
+        GameRewoundEvent { game }
+    }
+}
@@ -1,23 +1,41 @@
 mod lobby_closing;
+mod phase_changed;
 mod player_joined;
 mod player_left;
 mod player_updated;
+mod rematch_voted;
+mod settings_changed;
+mod takeback_declined;
+mod takeback_requested;
 
+mod draw_offered;
+mod game_ended;
 mod game_move;
+mod game_rewound;
 mod game_started;
 mod game_updated;
+mod match_updated;
 
 mod lobby_card;
 mod player_card;
 
 pub use lobby_closing::LobbyClosingEvent;
+pub use phase_changed::PhaseChangedEvent;
 pub use player_joined::PlayerJoinedEvent;
 pub use player_left::PlayerLeftEvent;
 pub use player_updated::PlayerUpdatedEvent;
+pub use rematch_voted::RematchVotedEvent;
+pub use settings_changed::SettingsChangedEvent;
+pub use takeback_declined::TakebackDeclinedEvent;
+pub use takeback_requested::TakebackRequestedEvent;
 
+pub use draw_offered::DrawOfferedEvent;
+pub use game_ended::GameEndedEvent;
 pub use game_move::GameMoveEventData;
+pub use game_rewound::GameRewoundEvent;
 pub use game_started::GameStartedEvent;
 pub use game_updated::GameUpdatedEvent;
+pub use match_updated::MatchUpdatedEvent;
 
 pub use lobby_card::LobbyCardEventData;
 pub use player_card::PlayerCardEventData;
@@ -0,0 +1,12 @@
+use crate::types::GameSettings;
+
+#[derive(Clone, Debug)]
+pub struct SettingsChangedEvent {
+    pub settings: GameSettings,
+}
+
+impl SettingsChangedEvent {
+    pub fn new(settings: GameSettings) -> SettingsChangedEvent {
+        SettingsChangedEvent { settings }
+    }
+}
@@ -0,0 +1,12 @@
+use crate::types::Role;
+
+#[derive(Clone, Debug)]
+pub struct TakebackRequestedEvent {
+    pub role: Role, // the player that wants to take back their turn
+}
+
+impl TakebackRequestedEvent {
+    pub fn new(role: Role) -> TakebackRequestedEvent {
+        TakebackRequestedEvent { role }
+    }
+}
@@ -19,7 +19,16 @@ pub enum NetworkEvent {
     PlayerLeft(PlayerLeftEvent),
     GameStarted(GameStartedEvent),
     GameUpdated(GameUpdatedEvent),
+    PhaseChanged(PhaseChangedEvent),
+    DrawOffered(DrawOfferedEvent),
+    GameEnded(GameEndedEvent),
+    TakebackRequested(TakebackRequestedEvent),
+    TakebackDeclined(TakebackDeclinedEvent),
+    GameRewound(GameRewoundEvent),
+    RematchVoted(RematchVotedEvent),
+    MatchUpdated(MatchUpdatedEvent),
     LobbyClosing(LobbyClosingEvent),
+    SettingsChanged(SettingsChangedEvent),
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -6,12 +6,17 @@ use std::net::{Ipv4Addr, SocketAddr};
 use std::{cell::RefCell, rc::Rc};
 
 use commands::{
-    become_role_cmd, change_name_cmd, check_error, clear_cmd, close_lobby_cmd, connect_cmd,
-    create_lobby_cmd, disconnect_cmd, get_lobbies_cmd, get_lobby_state, join_lobby_cmd,
-    leave_lobby_cmd, make_host_cmd, ping_cmd,
+    accept_draw_cmd, answer_takeback_cmd, become_role_cmd, change_name_cmd, change_settings_cmd,
+    check_error, clear_cmd, close_lobby_cmd, connect_cmd, create_lobby_cmd, disconnect_cmd,
+    get_chat_history_cmd, get_game_cmd, get_game_history_cmd, get_leaderboard_cmd, get_lobbies_cmd,
+    get_lobby_state, get_match_cmd, get_profile_cmd, join_lobby_cmd, leave_lobby_cmd,
+    make_host_cmd, offer_draw_cmd, ping_cmd, rematch_cmd, request_takeback_cmd, resign_cmd,
 };
 use events::EventLoop;
-use types::{GameState, GameStateShared, LobbyShort, LobbyVec, UserType};
+use types::{
+    AngelRule, ComputerLevel, Difficulty, Exits, GameState, GameStateShared, LobbyShort, LobbyVec,
+    Outline, Role, RoleAssignment, TimeControl, Topology, UserType,
+};
 
 const SERVER_ADDR: SocketAddr =
     SocketAddr::new(std::net::IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 20000);
@@ -22,6 +27,7 @@ fn main() {
 
     let game_state: GameStateShared = rc_cell!(GameState {
         id: 0, // invalid id, doesn't matter because we connect before using the id
+        token: String::new(),
         name: String::from(DEFAULT_NAME),
         lobby: None,
         selected_lobby: None,
@@ -45,11 +51,14 @@ fn main() {
             }
         } else if buf == "connect" {
             match connect_cmd(state.name.clone(), event_loop.addr) {
-                Ok(id) => state.id = id,
+                Ok((id, token)) => {
+                    state.id = id;
+                    state.token = token;
+                }
                 Err(e) => check_error(e),
             }
         } else if buf == "disconnect" {
-            match disconnect_cmd(&state.id) {
+            match disconnect_cmd(&state.token) {
                 Ok(_) => {}
                 Err(e) => check_error(e),
             }
@@ -63,7 +72,7 @@ fn main() {
                 .unwrap();
             let new_name = String::from(&buf[new_name + 1..]);
 
-            match change_name_cmd(&state.id, new_name.clone(), &state.lobby) {
+            match change_name_cmd(&state.token, new_name.clone()) {
                 Ok(_) => state.name = new_name,
                 Err(e) => check_error(e),
             }
@@ -86,7 +95,7 @@ fn main() {
                 .unwrap();
             let lobby_name = String::from(&buf[lobby_name + 1..]);
 
-            match create_lobby_cmd(&state.id, lobby_name) {
+            match create_lobby_cmd(&state.token, lobby_name) {
                 Ok(lobby) => match get_lobby_state(lobby.clone()) {
                     Ok(lobby_state) => lobbies.push(LobbyShort {
                         id: lobby.id,
@@ -106,7 +115,7 @@ fn main() {
             let start = buf.split(' ').nth(2).unwrap().parse::<u32>().unwrap();
             let offset = buf.split(' ').nth(3).unwrap().parse::<u32>().unwrap();
 
-            match get_lobbies_cmd(&state.id, start, offset) {
+            match get_lobbies_cmd(&state.token, start, offset) {
                 Ok(new_lobbies) => {
                     for lobby in new_lobbies {
                         match get_lobby_state(lobby.clone()) {
@@ -134,7 +143,7 @@ fn main() {
             let index = buf.split(' ').nth(2).unwrap().parse::<usize>().unwrap();
 
             if index < lobbies.len() {
-                match join_lobby_cmd(&state.id, lobbies[index].addr, &state.lobby) {
+                match join_lobby_cmd(&state.token, lobbies[index].addr, &state.lobby) {
                     Ok(lobby_state) => {
                         let mut user_type = UserType::Spectator;
                         for player in &lobby_state.players {
@@ -148,25 +157,61 @@ fn main() {
                             name: lobby_state.name,
                             players: lobby_state.players,
                             user_type,
+                            settings: lobby_state.settings,
+                            phase: lobby_state.phase,
+                            game: lobby_state.game,
+                            series: lobby_state.series,
+                            result: None,
+                            rematch_votes: vec![],
                         });
                     }
                     Err(e) => check_error(e),
                 }
             }
         } else if buf == "leave lobby" {
-            match leave_lobby_cmd(&state.id.clone(), &mut state.lobby) {
+            match leave_lobby_cmd(&state.token.clone(), &mut state.lobby) {
                 Ok(_) => {}
                 Err(e) => check_error(e),
             }
         } else if buf == "close lobby" {
-            match close_lobby_cmd(&state.id.clone(), &mut state.lobby) {
+            match close_lobby_cmd(&state.token.clone(), &mut state.lobby) {
                 Ok(id) => lobbies.retain(|a| a.id != id),
                 Err(e) => check_error(e),
             }
         } else if buf.starts_with("make host") {
             let id = buf.split(' ').nth(2).unwrap().parse::<u32>().unwrap();
 
-            match make_host_cmd(&state.id, id, &state.lobby) {
+            match make_host_cmd(&state.token, id, &state.lobby) {
+                Ok(_) => {}
+                Err(e) => check_error(e),
+            }
+        } else if buf == "resign" {
+            match resign_cmd(&state.token, &state.lobby) {
+                Ok(_) => {}
+                Err(e) => check_error(e),
+            }
+        } else if buf == "offer draw" {
+            match offer_draw_cmd(&state.token, &state.lobby) {
+                Ok(_) => {}
+                Err(e) => check_error(e),
+            }
+        } else if buf == "accept draw" {
+            match accept_draw_cmd(&state.token, &state.lobby) {
+                Ok(_) => {}
+                Err(e) => check_error(e),
+            }
+        } else if buf == "rematch" {
+            match rematch_cmd(&state.token, &state.lobby) {
+                Ok(_) => {}
+                Err(e) => check_error(e),
+            }
+        } else if buf == "takeback" {
+            match request_takeback_cmd(&state.token, &state.lobby) {
+                Ok(_) => {}
+                Err(e) => check_error(e),
+            }
+        } else if buf == "accept takeback" || buf == "decline takeback" {
+            match answer_takeback_cmd(&state.token, buf.starts_with("accept"), &state.lobby) {
                 Ok(_) => {}
                 Err(e) => check_error(e),
             }
@@ -179,7 +224,130 @@ fn main() {
             } else {
                 continue;
             };
-            match become_role_cmd(&state.id, role, &state.lobby) {
+            match become_role_cmd(&state.token, role, &state.lobby) {
+                Ok(_) => {}
+                Err(e) => check_error(e),
+            }
+        } else if buf.starts_with("settings") {
+            // settings <name> <value>, changes one setting and keeps the rest of the lobby's
+            let mut settings = match state.lobby.as_ref() {
+                Some(lobby) => lobby.settings.clone(),
+                None => continue,
+            };
+            let name = buf.split(' ').nth(1).unwrap();
+            let value = buf.split(' ').nth(2).unwrap();
+
+            match name {
+                "size" => settings.grid_size = value.parse::<usize>().unwrap(),
+                "density" => settings.wall_density = value.parse::<u32>().unwrap(),
+                // without a seed every game gets a random board
+                "seed" if value == "random" => settings.seed = None,
+                "seed" => settings.seed = Some(value.parse::<u64>().unwrap()),
+                "power" => settings.angel_power = value.parse::<u32>().unwrap(),
+                "rule" => match AngelRule::parse(value) {
+                    Some(rule) => settings.angel_rule = rule,
+                    None => continue,
+                },
+                "walls" => settings.walls_per_turn = value.parse::<u32>().unwrap(),
+                "budget" if value == "none" => settings.wall_budget = None,
+                "budget" => settings.wall_budget = Some(value.parse::<u32>().unwrap()),
+                "adjacent" => settings.adjacent_walls = value == "yes",
+                "topology" => match Topology::parse(value) {
+                    Some(topology) => settings.topology = topology,
+                    None => continue,
+                },
+                "outline" => match Outline::parse(value) {
+                    Some(outline) => settings.outline = outline,
+                    None => continue,
+                },
+                // one letter for each open edge, e.g. tb for top and bottom
+                "exits" => match Exits::parse(value) {
+                    Some(exits) => settings.exits = exits,
+                    None => continue,
+                },
+                // unlimited, 300+5 for five minutes and five more after every turn, or 30/move
+                "time" => match TimeControl::parse(value) {
+                    Some(time_control) => settings.time_control = time_control,
+                    None => continue,
+                },
+                // host-devil, host-angel or coin-flip, the computer plays the other side alone
+                "roles" => match RoleAssignment::parse(value) {
+                    Some(roles) => settings.roles = roles,
+                    None => continue,
+                },
+                "swap" => settings.swap_roles = value == "yes",
+                // best of n, 1 plays single games
+                "match" => settings.match_games = value.parse::<u32>().unwrap(),
+                "match-rated" => settings.match_rated = value == "yes",
+                // any, easy, normal or hard, any scatters the walls without rating the board
+                "difficulty" => match Difficulty::parse(value) {
+                    Some(difficulty) => settings.difficulty = difficulty,
+                    None => continue,
+                },
+                // easy, normal or hard, how well the computer plays the devil
+                "computer" => match ComputerLevel::parse(value) {
+                    Some(level) => settings.computer_level = level,
+                    None => continue,
+                },
+                _ => continue,
+            }
+
+            match change_settings_cmd(&state.token, settings, &state.lobby) {
+                Ok(_) => {}
+                Err(e) => check_error(e),
+            }
+        } else if buf.starts_with("chat history") {
+            // the latest messages, or the ones sent before the given message id
+            let offset = buf.split(' ').nth(2).unwrap().parse::<u32>().unwrap();
+            let before = buf.split(' ').nth(3).map(|id| id.parse::<u32>().unwrap());
+
+            match get_chat_history_cmd(&state.token, before, offset, &state.lobby) {
+                Ok(_) => {}
+                Err(e) => check_error(e),
+            }
+        } else if buf.starts_with("game history") {
+            let user_id = buf.split(' ').nth(2).unwrap().parse::<u32>().unwrap();
+            let start = buf.split(' ').nth(3).unwrap().parse::<u32>().unwrap();
+            let offset = buf.split(' ').nth(4).unwrap().parse::<u32>().unwrap();
+
+            match get_game_history_cmd(&state.token, user_id, start, offset) {
+                Ok(_) => {}
+                Err(e) => check_error(e),
+            }
+        } else if buf.starts_with("get game") {
+            let id = buf.split(' ').nth(2).unwrap().parse::<u32>().unwrap();
+
+            match get_game_cmd(&state.token, id) {
+                Ok(_) => {}
+                Err(e) => check_error(e),
+            }
+        } else if buf.starts_with("get match") {
+            let id = buf.split(' ').nth(2).unwrap().parse::<u32>().unwrap();
+
+            match get_match_cmd(&state.token, id) {
+                Ok(_) => {}
+                Err(e) => check_error(e),
+            }
+        } else if buf.starts_with("leaderboard") {
+            let role = buf.split(' ').nth(1).unwrap();
+            let role = if role == "angel" {
+                Role::Angel
+            } else if role == "devil" {
+                Role::Devil
+            } else {
+                continue;
+            };
+            let start = buf.split(' ').nth(2).unwrap().parse::<u32>().unwrap();
+            let offset = buf.split(' ').nth(3).unwrap().parse::<u32>().unwrap();
+
+            match get_leaderboard_cmd(&state.token, role, start, offset) {
+                Ok(_) => {}
+                Err(e) => check_error(e),
+            }
+        } else if buf.starts_with("profile") {
+            let id = buf.split(' ').nth(1).unwrap().parse::<u32>().unwrap();
+
+            match get_profile_cmd(&state.token, id) {
                 Ok(_) => {}
                 Err(e) => check_error(e),
             }
@@ -214,13 +382,72 @@ fn main() {
                         active_lobby.players.retain(|p| p.id != e.user_id);
                     }
                 }
+                events::Event::Network(events::NetworkEvent::SettingsChanged(e)) => {
+                    if let Some(active_lobby) = state.lobby.as_mut() {
+                        active_lobby.settings = e.settings;
+                    }
+                }
+                events::Event::Network(events::NetworkEvent::PhaseChanged(e)) => {
+                    if let Some(active_lobby) = state.lobby.as_mut() {
+                        active_lobby.phase = e.change.to;
+                    }
+                }
+                events::Event::Network(events::NetworkEvent::GameStarted(e)) => {
+                    if let Some(active_lobby) = state.lobby.as_mut() {
+                        active_lobby.game = Some(e.game);
+                        active_lobby.rematch_votes.clear();
+                    }
+                }
+                events::Event::Network(events::NetworkEvent::GameUpdated(e)) => {
+                    if let Some(game) = state.lobby.as_mut().and_then(|l| l.game.as_mut()) {
+                        game.apply_move(e.update.user_move);
+                    }
+                }
+                events::Event::Network(events::NetworkEvent::DrawOffered(e)) => {
+                    if let Some(game) = state.lobby.as_mut().and_then(|l| l.game.as_mut()) {
+                        game.draw_offer = Some(e.role);
+                    }
+                }
+                events::Event::Network(events::NetworkEvent::TakebackRequested(e)) => {
+                    if let Some(game) = state.lobby.as_mut().and_then(|l| l.game.as_mut()) {
+                        game.takeback_request = Some(e.role);
+                    }
+                }
+                events::Event::Network(events::NetworkEvent::TakebackDeclined(e)) => {
+                    if let Some(game) = state.lobby.as_mut().and_then(|l| l.game.as_mut()) {
+                        // the opponent of the one that declined asked for it
+                        if game.takeback_request == Some(e.role.opponent()) {
+                            game.takeback_request = None;
+                        }
+                    }
+                }
+                events::Event::Network(events::NetworkEvent::GameRewound(e)) => {
+                    if let Some(active_lobby) = state.lobby.as_mut() {
+                        active_lobby.game = Some(e.game);
+                    }
+                }
+                events::Event::Network(events::NetworkEvent::GameEnded(e)) => {
+                    if let Some(active_lobby) = state.lobby.as_mut() {
+                        active_lobby.game = None;
+                        active_lobby.result = Some(e.result);
+                    }
+                }
+                events::Event::Network(events::NetworkEvent::RematchVoted(e)) => {
+                    if let Some(active_lobby) = state.lobby.as_mut() {
+                        active_lobby.rematch_votes.push(e.user_id);
+                    }
+                }
+                events::Event::Network(events::NetworkEvent::MatchUpdated(e)) => {
+                    if let Some(active_lobby) = state.lobby.as_mut() {
+                        active_lobby.series = Some(e.series);
+                    }
+                }
                 events::Event::Network(events::NetworkEvent::LobbyClosing(_)) => {
                     if let Some(active_lobby) = state.lobby.as_mut() {
                         lobbies.retain(|a| a.id != active_lobby.id);
                     }
                     state.lobby = None;
                 }
-                _ => {}
             }
         }
     }
@@ -9,11 +9,18 @@ use std::{
 use crate::events::Event;
 use serde_derive::{Deserialize, Serialize};
 
+// the game rules are shared with the server
+pub use rules::{
+    AngelRule, ComputerLevel, Difficulty, EndReason, Exits, GameMove, GameSettings, MatchState,
+    Outline, Role, RoleAssignment, TimeControl, Topology,
+};
+
 pub type BoolMutex = Arc<Mutex<bool>>;
 
 pub type EventQueue = Arc<Mutex<VecDeque<EventQueueItem>>>;
 pub type EventQueueItem = Event;
 pub type UserId = u32;
+pub type SessionToken = String;
 pub type UserName = String;
 
 #[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
@@ -49,6 +56,27 @@ pub struct LobbyAddr {
 pub struct LobbyState {
     pub name: String,
     pub players: Vec<Player>,
+    pub chat: Vec<ChatMessage>, // the latest messages, oldest first
+    pub settings: GameSettings,
+    pub game: Option<rules::GameState>, // the game being played, if any
+    pub series: Option<MatchState>,     // the match being played, if any
+    pub phase: Phase,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum Phase {
+    Waiting,
+    Countdown, // the game begins once it's over
+    InGame,
+    Finished,
+}
+
+// bincode decodes fields by position so from stays even though only to is used
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct PhaseChange {
+    pub from: Phase,
+    pub to: Phase,
 }
 
 #[derive(Debug, Deserialize)]
@@ -57,6 +85,23 @@ pub struct LobbyStateShort {
     pub players: u32,
 }
 
+// the sender's id and the send time are part of the server's message, only shown by Debug
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+pub struct ChatMessage {
+    pub id: u32,
+    pub user_id: UserId,
+    pub name: UserName,
+    pub text: String,
+    pub sent_at: u64,
+}
+
+impl Display for ChatMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format!("#{} {}: {}", self.id, self.name, self.text))
+    }
+}
+
 #[derive(Debug)]
 pub struct Lobby {
     pub id: u16,
@@ -64,18 +109,55 @@ pub struct Lobby {
     pub name: String,
     pub players: Vec<Player>,
     pub user_type: UserType, // current user's type
+    pub settings: GameSettings,
+    pub phase: Phase,
+    pub game: Option<rules::GameState>, // the game being played, kept up to date with its moves
+    pub series: Option<MatchState>,     // the match being played, if any
+    pub result: Option<rules::GameResult>, // how the last game ended
+    pub rematch_votes: Vec<u32>,        // the players that want to play it again
 }
 
 impl Display for Lobby {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut display = String::new();
 
-        display += &format!("id: {}\naddr: {:?}\nplayers:\n", self.id, self.addr);
+        display += &format!(
+            "id: {}\naddr: {:?}\nphase: {:?}\nsettings: {:?}\nplayers:\n",
+            self.id, self.addr, self.phase, self.settings
+        );
 
         for player in self.players.iter() {
             display += &format!("  {}\n", player);
         }
 
+        if let Some(game) = &self.game {
+            let turn = if game.turn { Role::Devil } else { Role::Angel };
+            display += &format!("angel: {:?}, {} to move\n", game.angel_pos, turn.as_str());
+
+            if let Some(role) = game.draw_offer {
+                display += &format!("the {} offers a draw\n", role.as_str());
+            }
+            if let Some(role) = game.takeback_request {
+                display += &format!("the {} asks to take back their turn\n", role.as_str());
+            }
+        }
+
+        if let Some(result) = &self.result {
+            let winner = result.winner.map_or("nobody", |role| role.as_str());
+            display += &format!("last game: {} won, {:?}\n", winner, result.reason);
+        }
+
+        if !self.rematch_votes.is_empty() {
+            display += &format!("rematch wanted by: {:?}\n", self.rematch_votes);
+        }
+
+        if let Some(series) = &self.series {
+            display += &format!(
+                "match: {} - {}, {} draws\n",
+                series.wins.0, series.wins.1, series.draws
+            );
+        }
+
         f.write_str(&display)
     }
 }
@@ -88,6 +170,123 @@ pub struct LobbyShort {
     pub players: u32,
 }
 
+// the records below mirror the server's replies field for field since bincode decodes them
+// in order, the client only prints them with Debug so most fields are never read
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+pub struct GameSummary {
+    pub id: u32,
+    pub angel: u32,
+    pub angel_name: String,
+    pub devil: u32,
+    pub devil_name: String,
+    pub winner: Option<Role>,
+    pub reason: EndReason,
+    pub rated: bool,
+    pub match_id: Option<u32>,
+    pub moves: u32,
+    pub started_at: u64,
+    pub ended_at: u64,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+pub struct GameRecord {
+    pub id: u32,
+    pub lobby_id: u16,
+    pub lobby_name: String,
+    pub angel: u32,
+    pub angel_name: String,
+    pub devil: u32,
+    pub devil_name: String,
+    pub grid: Vec<Vec<bool>>,
+    pub angel_start: (i32, i32),
+    pub wall_density: Option<u32>,
+    pub seed: Option<u64>,
+    pub difficulty: Difficulty,
+    pub angel_power: u32,
+    pub angel_rule: AngelRule,
+    pub walls_per_turn: u32,
+    pub wall_budget: Option<u32>,
+    pub adjacent_walls: bool,
+    pub topology: Topology,
+    pub outline: Outline,
+    pub exits: Exits,
+    pub time_control: TimeControl,
+    pub winner: Option<Role>,
+    pub reason: EndReason,
+    pub rated: bool,
+    pub match_id: Option<u32>,
+    pub moves: Vec<GameMove>,
+    pub started_at: u64,
+    pub ended_at: u64,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+pub struct MatchRecord {
+    pub id: u32,
+    pub lobby_id: u16,
+    pub lobby_name: String,
+    pub players: (u32, u32),
+    pub names: (String, String),
+    pub games: u32,
+    pub wins: (u32, u32),
+    pub draws: u32,
+    pub winner: Option<u32>,
+    pub forfeit: Option<u32>,
+    pub abandoned: bool,
+    pub rated: bool,
+    pub game_ids: Vec<u32>,
+    pub started_at: u64,
+    pub ended_at: u64,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+pub struct LeaderboardEntry {
+    pub rank: u32,
+    pub id: u32,
+    pub name: String,
+    pub rating: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Leaderboard {
+    pub entries: Vec<LeaderboardEntry>,
+    pub user: Option<LeaderboardEntry>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+pub struct RoleStats {
+    pub games: u32,
+    pub wins: u32,
+    pub losses: u32,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+pub struct ProfileStats {
+    pub games: u32,
+    pub angel: RoleStats,
+    pub devil: RoleStats,
+    pub average_moves: f32,
+    pub longest_survival: u32,
+    pub recent: Vec<GameSummary>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+pub struct Profile {
+    pub id: u32,
+    pub name: String,
+    pub guest: bool,
+    pub angel_rating: Option<u32>,
+    pub devil_rating: Option<u32>,
+    pub stats: ProfileStats,
+}
+
 pub type LobbyVec = Vec<LobbyShort>;
 pub type LobbyAddrVec = Vec<LobbyAddr>;
 
@@ -95,14 +294,13 @@ pub type LobbyAddrVec = Vec<LobbyAddr>;
 /// Option is used because initially there is no state, throughout the code unwrap will be unused because we know for sure that the values exist because in order to get to window X part Y of state must be initialized
 pub struct GameState {
     pub id: UserId,
+    pub token: SessionToken, // sent with every request instead of the id
     pub name: UserName,
     pub lobby: Option<Lobby>,
     pub selected_lobby: Option<LobbyShort>,
 }
 pub type GameStateShared = RcCell<GameState>;
 
-pub const GRID_SIZE: usize = 11;
-
 pub type RcCell<T> = Rc<RefCell<T>>;
 
 #[macro_export]
@@ -1,25 +1,14 @@
 use network::{request, Type};
 
-use crate::{
-    commands::CommandError,
-    types::{Lobby, UserId},
-    SERVER_ADDR,
-};
+use crate::{commands::CommandError, types::SessionToken, SERVER_ADDR};
 
-pub fn change_name_cmd(
-    user_id: &UserId,
-    name: String,
-    active_lobby: &Option<Lobby>,
-) -> Result<(), CommandError> {
+pub fn change_name_cmd(token: &SessionToken, name: String) -> Result<(), CommandError> {
     if name.is_empty() {
         return Err(CommandError::EmptyString);
     }
 
-    request(SERVER_ADDR.with(|&a| a), Type::ChangeName, &(*user_id, name))?;
-
-    if let Some(active_lobby) = active_lobby {
-        request(active_lobby.addr, Type::ChangedName, user_id)?;
-    }
+    // the server updates the lobby the user is in as well
+    request(SERVER_ADDR.with(|&a| a), Type::ChangeName, &(token, name))?;
 
     Ok(())
 }
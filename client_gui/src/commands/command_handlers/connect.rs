@@ -2,12 +2,20 @@ use std::net::SocketAddr;
 
 use network::{request, Type};
 
-use crate::{commands::CommandError, types::UserId, SERVER_ADDR};
+use crate::{
+    commands::CommandError,
+    types::{SessionToken, UserId},
+    SERVER_ADDR,
+};
 
-pub fn connect_cmd(name: String, addr: SocketAddr) -> Result<UserId, CommandError> {
-    let res: u32 = request(SERVER_ADDR.with(|&a| a), Type::Connect, &(name.clone(), addr))?;
+pub fn connect_cmd(name: String, addr: SocketAddr) -> Result<(UserId, SessionToken), CommandError> {
+    let res: (UserId, SessionToken) = request(
+        SERVER_ADDR.with(|&a| a),
+        Type::Connect,
+        &(name.clone(), addr),
+    )?;
 
-    println!("received: {res}");
+    println!("received: {}", res.0);
 
     Ok(res)
 }
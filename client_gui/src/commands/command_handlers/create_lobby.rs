@@ -2,12 +2,12 @@ use network::{request, Type};
 
 use crate::{
     commands::CommandError,
-    types::{LobbyAddr, UserId},
+    types::{LobbyAddr, SessionToken},
     SERVER_ADDR,
 };
 
-pub fn create_lobby_cmd(user_id: &UserId, name: String) -> Result<LobbyAddr, CommandError> {
-    let lobby: LobbyAddr = request(SERVER_ADDR.with(|&a| a), Type::CreateLobby, &(*user_id, name))?;
+pub fn create_lobby_cmd(token: &SessionToken, name: String) -> Result<LobbyAddr, CommandError> {
+    let lobby: LobbyAddr = request(SERVER_ADDR.with(|&a| a), Type::CreateLobby, &(token, name))?;
 
     println!("received: {lobby:?}");
 
@@ -1,9 +1,9 @@
 use network::{request, Type};
 
-use crate::{commands::CommandError, types::UserId, SERVER_ADDR};
+use crate::{commands::CommandError, types::SessionToken, SERVER_ADDR};
 
-pub fn disconnect_cmd(user_id: &UserId) -> Result<(), CommandError> {
-    request(SERVER_ADDR.with(|&a| a), Type::Disconnect, user_id)?;
+pub fn disconnect_cmd(token: &SessionToken) -> Result<(), CommandError> {
+    request(SERVER_ADDR.with(|&a| a), Type::Disconnect, token)?;
 
     println!("disconnected");
 
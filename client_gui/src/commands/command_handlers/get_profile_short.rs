@@ -0,0 +1,22 @@
+use network::{request, Type};
+
+use crate::{
+    commands::CommandError,
+    types::{ProfileShort, SessionToken, UserId},
+    SERVER_ADDR,
+};
+
+pub fn get_profile_short_cmd(
+    token: &SessionToken,
+    user_id: UserId,
+) -> Result<ProfileShort, CommandError> {
+    let profile: ProfileShort = request(
+        SERVER_ADDR.with(|&a| a),
+        Type::GetProfileShort,
+        &(token, user_id),
+    )?;
+
+    println!("received: {profile:?}");
+
+    Ok(profile)
+}
@@ -4,11 +4,11 @@ use network::{request, Type};
 
 use crate::{
     commands::CommandError,
-    types::{Lobby, LobbyState, UserId},
+    types::{Lobby, LobbyState, SessionToken},
 };
 
 pub fn join_lobby_cmd(
-    user_id: &UserId,
+    token: &SessionToken,
     lobby_addr: SocketAddr,
     active_lobby: &Option<Lobby>,
 ) -> Result<LobbyState, CommandError> {
@@ -16,7 +16,7 @@ pub fn join_lobby_cmd(
         return Err(CommandError::AlreadyConnected);
     }
 
-    let res: LobbyState = request(lobby_addr, Type::JoinLobby, user_id)?;
+    let res: LobbyState = request(lobby_addr, Type::JoinLobby, token)?;
 
     println!("joined lobby");
 
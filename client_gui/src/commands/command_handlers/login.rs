@@ -0,0 +1,29 @@
+use std::net::SocketAddr;
+
+use network::{request, Type};
+
+use crate::{
+    commands::CommandError,
+    types::{SessionToken, UserId},
+    SERVER_ADDR,
+};
+
+pub fn login_cmd(
+    name: String,
+    password: String,
+    addr: SocketAddr,
+) -> Result<(UserId, SessionToken), CommandError> {
+    if name.is_empty() || password.is_empty() {
+        return Err(CommandError::EmptyString);
+    }
+
+    let res: (UserId, SessionToken) = request(
+        SERVER_ADDR.with(|&a| a),
+        Type::Login,
+        &(name, password, addr),
+    )?;
+
+    println!("received: {}", res.0);
+
+    Ok(res)
+}
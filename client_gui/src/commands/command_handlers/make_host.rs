@@ -2,11 +2,11 @@ use network::{request, Type};
 
 use crate::{
     commands::CommandError,
-    types::{Lobby, UserId},
+    types::{Lobby, SessionToken},
 };
 
 pub fn make_host_cmd(
-    user_id: &UserId,
+    token: &SessionToken,
     new_host_id: u32,
     active_lobby: &Option<Lobby>,
 ) -> Result<(), CommandError> {
@@ -17,7 +17,7 @@ pub fn make_host_cmd(
     request(
         active_lobby.as_ref().unwrap().addr,
         Type::MakeHost,
-        &(*user_id, new_host_id),
+        &(token, new_host_id),
     )?;
 
     println!("user {} will be host", new_host_id);
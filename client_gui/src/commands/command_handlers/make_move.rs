@@ -2,11 +2,11 @@ use network::{request, Type};
 
 use crate::{
     commands::CommandError,
-    types::{Lobby, UserId},
+    types::{Lobby, SessionToken},
 };
 
 pub fn make_move_cmd(
-    user_id: &UserId,
+    token: &SessionToken,
     user_move: (i32, i32),
     active_lobby: &Option<Lobby>,
 ) -> Result<(), CommandError> {
@@ -17,7 +17,7 @@ pub fn make_move_cmd(
     request(
         active_lobby.as_ref().unwrap().addr,
         Type::MakeMove,
-        &(*user_id, user_move),
+        &(token, user_move),
     )?;
 
     println!("made move");
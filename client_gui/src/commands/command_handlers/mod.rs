@@ -2,6 +2,8 @@
 
 mod connect;
 mod disconnect;
+mod login;
+mod register;
 
 mod close_lobby;
 mod create_lobby;
@@ -12,16 +14,25 @@ mod leave_lobby;
 
 mod become_role;
 mod change_name;
+mod get_profile_short;
 mod make_host;
 mod send_message;
 
+mod accept_draw;
+mod answer_takeback;
 mod make_move;
+mod offer_draw;
+mod rematch;
+mod request_takeback;
+mod resign;
 mod start_game;
 
 // pub use ping::ping_cmd;
 
 pub use connect::connect_cmd;
 pub use disconnect::disconnect_cmd;
+pub use login::login_cmd;
+pub use register::register_cmd;
 
 pub use close_lobby::close_lobby_cmd;
 pub use create_lobby::create_lobby_cmd;
@@ -32,8 +43,15 @@ pub use leave_lobby::leave_lobby_cmd;
 
 pub use become_role::become_role_cmd;
 pub use change_name::change_name_cmd;
+pub use get_profile_short::get_profile_short_cmd;
 pub use make_host::make_host_cmd;
 pub use send_message::send_message_cmd;
 
+pub use accept_draw::accept_draw_cmd;
+pub use answer_takeback::answer_takeback_cmd;
 pub use make_move::make_move_cmd;
+pub use offer_draw::offer_draw_cmd;
+pub use rematch::rematch_cmd;
+pub use request_takeback::request_takeback_cmd;
+pub use resign::resign_cmd;
 pub use start_game::start_game_cmd;
@@ -2,11 +2,11 @@ use network::{request, Type};
 
 use crate::{
     commands::CommandError,
-    types::{Lobby, UserId},
+    types::{Lobby, SessionToken},
 };
 
 pub fn send_message_cmd(
-    user_id: &UserId,
+    token: &SessionToken,
     text: String,
     active_lobby: &Option<Lobby>,
 ) -> Result<(), CommandError> {
@@ -21,7 +21,7 @@ pub fn send_message_cmd(
     request(
         active_lobby.as_ref().unwrap().addr,
         Type::SendMessage,
-        &(*user_id, text),
+        &(token, text),
     )?;
 
     println!("message sent");
@@ -2,10 +2,10 @@ use network::{request, Type};
 
 use crate::{
     commands::CommandError,
-    types::{Lobby, UserId},
+    types::{Lobby, SessionToken},
 };
 
-pub fn start_game_cmd(user_id: &UserId, active_lobby: &Option<Lobby>) -> Result<(), CommandError> {
+pub fn start_game_cmd(token: &SessionToken, active_lobby: &Option<Lobby>) -> Result<(), CommandError> {
     if active_lobby.is_none() {
         return Err(CommandError::NotConnected);
     }
@@ -13,7 +13,7 @@ pub fn start_game_cmd(user_id: &UserId, active_lobby: &Option<Lobby>) -> Result<
     request(
         active_lobby.as_ref().unwrap().addr,
         Type::StartGame,
-        user_id,
+        token,
     )?;
 
     println!("game starting");
@@ -1,14 +1,12 @@
-use serde_derive::Deserialize;
+use rules::GameUpdate;
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug)]
 pub struct GameUpdatedEvent {
-    pub win: (bool, bool), // (devil won, angel won)
-    pub turn: bool,
-    pub user_move: (i32, i32),
+    pub update: GameUpdate,
 }
 
 impl GameUpdatedEvent {
-    pub fn new(update: GameUpdatedEvent) -> GameUpdatedEvent {
-        update
+    pub fn new(update: GameUpdate) -> GameUpdatedEvent {
+        GameUpdatedEvent { update }
     }
 }
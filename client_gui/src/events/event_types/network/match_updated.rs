@@ -0,0 +1,12 @@
+use rules::MatchState;
+
+#[derive(Clone, Debug)]
+pub struct MatchUpdatedEvent {
+    pub series: MatchState,
+}
+
+impl MatchUpdatedEvent {
+    pub fn new(series: MatchState) -> MatchUpdatedEvent {
+        MatchUpdatedEvent { series }
+    }
+}
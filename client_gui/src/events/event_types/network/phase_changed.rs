@@ -0,0 +1,12 @@
+use crate::types::PhaseChange;
+
+#[derive(Clone, Debug)]
+pub struct PhaseChangedEvent {
+    pub change: PhaseChange,
+}
+
+impl PhaseChangedEvent {
+    pub fn new(change: PhaseChange) -> PhaseChangedEvent {
+        PhaseChangedEvent { change }
+    }
+}
@@ -0,0 +1,10 @@
+#[derive(Clone, Debug)]
+pub struct RematchVotedEvent {
+    pub user_id: u32, // the player that wants to play again
+}
+
+impl RematchVotedEvent {
+    pub fn new(user_id: u32) -> RematchVotedEvent {
+        RematchVotedEvent { user_id }
+    }
+}
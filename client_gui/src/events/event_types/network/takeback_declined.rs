@@ -0,0 +1,12 @@
+use rules::Role;
+
+#[derive(Clone, Debug)]
+pub struct TakebackDeclinedEvent {
+    pub role: Role, // the player that declined
+}
+
+impl TakebackDeclinedEvent {
+    pub fn new(role: Role) -> TakebackDeclinedEvent {
+        TakebackDeclinedEvent { role }
+    }
+}
@@ -19,6 +19,14 @@ pub enum NetworkEvent {
     PlayerLeft(PlayerLeftEvent),
     GameStarted(GameStartedEvent),
     GameUpdated(GameUpdatedEvent),
+    PhaseChanged(PhaseChangedEvent),
+    DrawOffered(DrawOfferedEvent),
+    GameEnded(GameEndedEvent),
+    TakebackRequested(TakebackRequestedEvent),
+    TakebackDeclined(TakebackDeclinedEvent),
+    GameRewound(GameRewoundEvent),
+    RematchVoted(RematchVotedEvent),
+    MatchUpdated(MatchUpdatedEvent),
     LobbyClosing(LobbyClosingEvent),
     Message(MessageEvent),
 }
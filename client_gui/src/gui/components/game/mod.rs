@@ -2,15 +2,19 @@ mod tile;
 
 use std::sync::mpsc;
 
+use rules::{GameState, Topology};
 use sfml::{
-    graphics::{Color, Drawable, FloatRect, RectangleShape, Shape, Sprite, Texture, Transformable},
+    graphics::{
+        Color, ConvexShape, Drawable, FloatRect, RectangleShape, Shape, Sprite, Texture,
+        Transformable,
+    },
     system::Vector2f,
     SfBox,
 };
 
-use crate::{
-    events::{EventData, GameMoveEventData, GameStartedEvent, GameUpdatedEvent, UIEvent, Window},
-    types::GRID_SIZE,
+use crate::events::{
+    EventData, GameMoveEventData, GameRewoundEvent, GameStartedEvent, GameUpdatedEvent, UIEvent,
+    Window,
 };
 
 use tile::Tile;
@@ -24,8 +28,12 @@ pub struct Game {
     wall_texture: SfBox<Texture>,
     player_texture: SfBox<Texture>,
 
-    grid: [[Tile; GRID_SIZE]; GRID_SIZE],
+    grid: Vec<Vec<Tile>>,
+    size: usize,
+    topology: Topology,
     player_pos: (usize, usize),
+    // the game as the server sees it, the moves it sends are replayed on it
+    game: Option<GameState>,
     ratio: f32,
     sender: mpsc::Sender<UIEvent>,
     pub began: bool,
@@ -33,35 +41,10 @@ pub struct Game {
 
 impl Game {
     const REAL_WIDTH: f32 = 64.0;
-
+    // shown until the first game starts, the board takes the size of each game
+    const DEFAULT_SIZE: usize = 11;
     pub fn new(id: u32, window: Window, bounds: FloatRect, sender: mpsc::Sender<UIEvent>) -> Game {
-        let tile_width = bounds.width / (2 * GRID_SIZE + 1) as f32;
-        let ratio = tile_width / Game::REAL_WIDTH;
-
-        let off = [
-            Vector2f::new(0.0, -tile_width),
-            Vector2f::new(tile_width, -(tile_width / 2.0)),
-            Vector2f::new(tile_width, tile_width / 2.0),
-            Vector2f::new(0.0, tile_width),
-            Vector2f::new(-tile_width, tile_width / 2.0),
-            Vector2f::new(-tile_width, -(tile_width / 2.0)),
-        ];
-
-        let mut grid = [[Default::default(); GRID_SIZE]; GRID_SIZE];
-
-        for (i, line) in grid.iter_mut().enumerate() {
-            for (j, item) in line.iter_mut().enumerate() {
-                let origin = Vector2f::new(
-                    bounds.left
-                        + tile_width
-                        + j as f32 * (2.0 * tile_width)
-                        + (i % 2) as f32 * tile_width,
-                    bounds.top + 2.0 * tile_width + i as f32 * (1.5 * tile_width),
-                );
-
-                *item = Tile::new(origin, &off);
-            }
-        }
+        let (grid, ratio) = Game::layout(bounds, Game::DEFAULT_SIZE, Topology::Hex);
 
         Game {
             event_data: EventData { id, window },
@@ -70,56 +53,176 @@ impl Game {
             wall_texture: Texture::from_file("./assets/wall.png").unwrap(),
             player_texture: Texture::from_file("./assets/player.png").unwrap(),
             grid,
-            player_pos: (GRID_SIZE / 2, GRID_SIZE / 2),
+            size: Game::DEFAULT_SIZE,
+            topology: Topology::Hex,
+            player_pos: (Game::DEFAULT_SIZE / 2, Game::DEFAULT_SIZE / 2),
+            game: None,
             ratio,
             sender,
             began: false,
         }
     }
 
-    pub fn start(&mut self, state: GameStartedEvent) {
-        self.began = true;
-        self.player_pos = (state.devil_pos.0 as usize, state.devil_pos.1 as usize);
+    // places the tiles of a size x size board inside the bounds, returns them along with the
+    // scale of the sprites
+    fn layout(bounds: FloatRect, size: usize, topology: Topology) -> (Vec<Vec<Tile>>, f32) {
+        // half the width of a tile
+        let tile_width = bounds.width / (2 * size + 1) as f32;
+        let ratio = tile_width / Game::REAL_WIDTH;
+
+        let off = match topology {
+            Topology::Hex => vec![
+                Vector2f::new(0.0, -tile_width),
+                Vector2f::new(tile_width, -(tile_width / 2.0)),
+                Vector2f::new(tile_width, tile_width / 2.0),
+                Vector2f::new(0.0, tile_width),
+                Vector2f::new(-tile_width, tile_width / 2.0),
+                Vector2f::new(-tile_width, -(tile_width / 2.0)),
+            ],
+            Topology::Square4 | Topology::Square8 => vec![
+                Vector2f::new(-tile_width, -tile_width),
+                Vector2f::new(tile_width, -tile_width),
+                Vector2f::new(tile_width, tile_width),
+                Vector2f::new(-tile_width, tile_width),
+            ],
+        };
+
+        let mut grid = vec![vec![Tile::default(); size]; size];
 
-        for (i, line) in self.grid.iter_mut().enumerate() {
+        for (i, line) in grid.iter_mut().enumerate() {
             for (j, item) in line.iter_mut().enumerate() {
-                item.set_blocked(state.grid[i][j]);
+                let origin = match topology {
+                    // odd lines are shifted right by half a tile and lines overlap by a quarter
+                    Topology::Hex => Vector2f::new(
+                        bounds.left
+                            + tile_width
+                            + j as f32 * (2.0 * tile_width)
+                            + (i % 2) as f32 * tile_width,
+                        bounds.top + 2.0 * tile_width + i as f32 * (1.5 * tile_width),
+                    ),
+                    Topology::Square4 | Topology::Square8 => Vector2f::new(
+                        bounds.left + 1.5 * tile_width + j as f32 * (2.0 * tile_width),
+                        bounds.top + tile_width + i as f32 * (2.0 * tile_width),
+                    ),
+                };
+
+                *item = Tile::new(origin, &off);
+            }
+        }
+
+        (grid, ratio)
+    }
+
+    fn tile_shape(tile: &Tile) -> ConvexShape<'static> {
+        let mut shape = ConvexShape::new(tile.points.len());
+        for (k, point) in tile.points.iter().enumerate() {
+            shape.set_point(k, *point);
+        }
+
+        shape
+    }
+
+    pub fn start(&mut self, e: GameStartedEvent) {
+        let game = e.game;
+
+        if game.size != self.size || game.board.topology != self.topology {
+            (self.grid, self.ratio) = Game::layout(self.bounds, game.size, game.board.topology);
+            self.size = game.size;
+            self.topology = game.board.topology;
+        }
+
+        self.began = true;
+
+        for (line, tiles) in self.grid.iter_mut().zip(game.board.tiles.iter()) {
+            for (item, tile) in line.iter_mut().zip(tiles.iter()) {
+                item.on_board = *tile;
+            }
+        }
+
+        self.game = Some(game);
+        self.sync();
+    }
+
+    // shows the walls and the angel of the game, along with the tiles the angel can move to when
+    // it's its turn
+    fn sync(&mut self) {
+        let game = match self.game.as_ref() {
+            Some(game) => game,
+            None => return,
+        };
+
+        self.player_pos = (game.angel_pos.0 as usize, game.angel_pos.1 as usize);
+
+        for (line, blocked) in self.grid.iter_mut().zip(game.grid.iter()) {
+            for (item, blocked) in line.iter_mut().zip(blocked.iter()) {
+                item.set_blocked(*blocked);
+                item.set_reachable(false);
+            }
+        }
+
+        if !game.turn {
+            for (i, j) in game.angel_moves(game.angel_pos) {
+                self.grid[i as usize][j as usize].set_reachable(true);
             }
         }
     }
 
     pub fn stop(&mut self) {
         self.began = false;
-        self.player_pos = (GRID_SIZE / 2, GRID_SIZE / 2);
+        self.game = None;
+        self.player_pos = (self.size / 2, self.size / 2);
 
         for line in self.grid.iter_mut() {
             for item in line.iter_mut() {
                 item.set_blocked(false);
+                item.set_reachable(false);
+                item.on_board = true;
             }
         }
     }
 
-    pub fn update(&mut self, state: GameUpdatedEvent) {
-        let user_move = (state.user_move.0 as usize, state.user_move.1 as usize);
-        // devil move
-        if !state.turn {
-            self.player_pos = (user_move.0, user_move.1);
+    // the board, the walls and the turn are all sent again after a takeback
+    pub fn rewind(&mut self, e: GameRewoundEvent) {
+        if self.game.is_some() {
+            self.game = Some(e.game);
+            self.sync();
         }
-        // angel move
-        else {
-            self.grid[user_move.0][user_move.1].set_blocked(true);
+    }
+
+    pub fn update(&mut self, e: GameUpdatedEvent) {
+        if let Some(game) = self.game.as_mut() {
+            game.apply_move(e.update.user_move);
         }
 
-        if state.win.0 || state.win.1 {
+        self.sync();
+
+        if e.update.win.0 || e.update.win.1 {
             self.stop();
         }
     }
 
     pub fn click(&self, x: u32, y: u32) {
+        let game = match self.game.as_ref() {
+            Some(game) => game,
+            None => return,
+        };
+
         println!("{x} {y}");
         for (i, line) in self.grid.iter().enumerate() {
             for (j, item) in line.iter().enumerate() {
-                if item.inside(x, y) {
+                if !item.on_board || !item.inside(x, y) {
+                    continue;
+                }
+
+                // illegal moves are turned down here instead of waiting for the server to
+                let pos = (i as i32, j as i32);
+                let valid = if game.turn {
+                    game.valid_devil_move(pos)
+                } else {
+                    game.valid_angel_move(pos)
+                };
+
+                if valid {
                     if let Err(e) = self.sender.send(UIEvent::GameMove(GameMoveEventData {
                         x: i as i32,
                         y: j as i32,
@@ -165,15 +268,13 @@ impl Fixed for Game {
         self.bounds.left = position.x;
         self.bounds.top = position.y;
 
-        for i in 0..GRID_SIZE {
-            for j in 0..GRID_SIZE {
-                self.grid[i][j].origin.x += offset.x;
-                self.grid[i][j].origin.y += offset.y;
+        for tile in self.grid.iter_mut().flatten() {
+            tile.origin.x += offset.x;
+            tile.origin.y += offset.y;
 
-                for point in self.grid[i][j].points.iter_mut() {
-                    point.x += offset.x;
-                    point.y += offset.y;
-                }
+            for point in tile.points.iter_mut() {
+                point.x += offset.x;
+                point.y += offset.y;
             }
         }
     }
@@ -185,27 +286,50 @@ impl Drawable for Game {
         target: &mut dyn sfml::graphics::RenderTarget,
         _: &sfml::graphics::RenderStates<'texture, 'shader, 'shader_texture>,
     ) {
-        for i in 0..GRID_SIZE {
-            for j in 0..GRID_SIZE {
-                let mut sprite = Sprite::with_texture(&self.tile_texture);
-                sprite.set_origin((Game::REAL_WIDTH, Game::REAL_WIDTH));
-                sprite.set_scale((self.ratio, self.ratio));
-                sprite.set_position(self.grid[i][j].origin);
-                target.draw(&sprite);
-
-                if self.grid[i][j].blocked {
-                    let mut sprite = Sprite::with_texture(&self.wall_texture);
-                    sprite.set_origin((Game::REAL_WIDTH, 2.0 * Game::REAL_WIDTH));
+        for (i, line) in self.grid.iter().enumerate() {
+            for (j, tile) in line.iter().enumerate() {
+                if !tile.on_board {
+                    continue;
+                }
+
+                // the textures are hexagons, square tiles are drawn as plain shapes
+                if self.topology == Topology::Hex {
+                    let mut sprite = Sprite::with_texture(&self.tile_texture);
+                    sprite.set_origin((Game::REAL_WIDTH, Game::REAL_WIDTH));
                     sprite.set_scale((self.ratio, self.ratio));
-                    sprite.set_position(self.grid[i][j].origin);
+                    sprite.set_position(tile.origin);
                     target.draw(&sprite);
+
+                    if tile.blocked {
+                        let mut sprite = Sprite::with_texture(&self.wall_texture);
+                        sprite.set_origin((Game::REAL_WIDTH, 2.0 * Game::REAL_WIDTH));
+                        sprite.set_scale((self.ratio, self.ratio));
+                        sprite.set_position(tile.origin);
+                        target.draw(&sprite);
+                    }
+                } else {
+                    let mut shape = Game::tile_shape(tile);
+                    if tile.blocked {
+                        shape.set_fill_color(Color::rgb(90, 90, 90));
+                    } else {
+                        shape.set_fill_color(Color::rgb(200, 200, 200));
+                    }
+                    shape.set_outline_color(Color::rgb(120, 120, 120));
+                    shape.set_outline_thickness(1.0);
+                    target.draw(&shape);
+                }
+
+                if tile.reachable {
+                    let mut shape = Game::tile_shape(tile);
+                    shape.set_fill_color(Color::rgba(255, 255, 255, 70));
+                    target.draw(&shape);
                 }
 
                 if i == self.player_pos.0 && j == self.player_pos.1 {
                     let mut sprite = Sprite::with_texture(&self.player_texture);
                     sprite.set_origin((Game::REAL_WIDTH, Game::REAL_WIDTH));
                     sprite.set_scale((self.ratio, self.ratio));
-                    sprite.set_position(self.grid[self.player_pos.0][self.player_pos.1].origin);
+                    sprite.set_position(tile.origin);
                     target.draw(&sprite);
                 }
             }
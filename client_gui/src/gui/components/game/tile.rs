@@ -1,48 +1,41 @@
 use sfml::system::Vector2f;
 
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Default)]
 pub struct Tile {
     pub blocked: bool,
+    pub reachable: bool, // the angel can move here this turn
+    pub on_board: bool,  // tiles outside the outline of the board aren't drawn
     pub origin: Vector2f,
-    pub points: [Vector2f; 6],
+    pub points: Vec<Vector2f>, // the corners, clockwise
 }
 
 impl Tile {
-    pub fn new(origin: Vector2f, off: &[Vector2f; 6]) -> Tile {
-        let mut points = [Vector2f::new(0.0, 0.0); 6];
-
-        for i in 0..6 {
-            points[i] = Vector2f::new(origin.x + off[i].x, origin.y + off[i].y);
-        }
+    pub fn new(origin: Vector2f, off: &[Vector2f]) -> Tile {
+        let points = off
+            .iter()
+            .map(|off| Vector2f::new(origin.x + off.x, origin.y + off.y))
+            .collect();
 
         Tile {
             blocked: false,
+            reachable: false,
+            on_board: true,
             origin,
             points,
         }
     }
 
+    // tiles are convex, so a point is inside when it is on the inner side of every edge
     pub fn inside(&self, x: u32, y: u32) -> bool {
         let p = Vector2f::new(x as f32, y as f32);
+        let n = self.points.len();
 
-        !self.under_slope(p, self.points[5], self.points[0])
-            && !self.under_slope(p, self.points[0], self.points[1])
-            && self.under_slope(p, self.points[2], self.points[3])
-            && self.under_slope(p, self.points[3], self.points[4])
-            && self.points[5].x <= p.x
-            && p.x <= self.points[1].x
-    }
-
-    fn under_slope(&self, p: Vector2f, a: Vector2f, b: Vector2f) -> bool {
-        // ignore verticals
-        if a.x == b.x {
-            return false;
-        }
-
-        let g = (a.y - b.y) / (a.x - b.x);
-        let y = g * (p.x - a.x) + a.y;
+        (0..n).all(|i| {
+            let a = self.points[i];
+            let b = self.points[(i + 1) % n];
 
-        p.y < y
+            (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x) >= 0.0
+        })
     }
 
     // pub fn blocked(&self) -> bool { self.blocked }
@@ -50,4 +43,8 @@ impl Tile {
     pub fn set_blocked(&mut self, value: bool) {
         self.blocked = value;
     }
+
+    pub fn set_reachable(&mut self, value: bool) {
+        self.reachable = value;
+    }
 }
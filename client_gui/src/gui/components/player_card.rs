@@ -7,7 +7,7 @@ use sfml::{
 
 use crate::{
     events::{Event, PlayerCardEventData, UIEvent, Window},
-    types::{Player, UserType},
+    types::{Player, ProfileShort, UserType},
 };
 
 use super::{EventHandlerMut, Fixed, MouseEventObserver};
@@ -139,6 +139,25 @@ impl<'a> PlayerCard<'a> {
 
             self.name.set_string(&new_buf);
         }
+
+        self.event_data.data = data;
+    }
+
+    // shows the player's ratings and results next to their role
+    pub fn set_profile(&mut self, profile: &ProfileShort) {
+        let rating = |rating: Option<u32>| match rating {
+            Some(rating) => rating.to_string(),
+            None => String::from("-"),
+        };
+
+        self.user_type.set_string(&format!(
+            "{:?}  angel {}  devil {}  {}/{} won",
+            self.event_data.data.user_type,
+            rating(profile.angel_rating),
+            rating(profile.devil_rating),
+            profile.wins,
+            profile.games,
+        ));
     }
 }
 
@@ -135,7 +135,7 @@ impl<'a> EventHandler for CreateLobbyWindow<'a> {
                     'create: {
                         let mut state = self.state.borrow_mut();
                         let settings = self.settings.borrow();
-                        let lobby = match create_lobby_cmd(&state.id, settings.name.clone()) {
+                        let lobby = match create_lobby_cmd(&state.token, settings.name.clone()) {
                             Ok(lobby) => LobbyShort {
                                 id: lobby.id,
                                 addr: lobby.addr,
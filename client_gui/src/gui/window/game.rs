@@ -1,6 +1,7 @@
-use std::{cell::RefCell, rc::Rc, sync::mpsc};
+use std::{cell::RefCell, rc::Rc, sync::mpsc, time::Instant};
 
 use anyhow::anyhow;
+use rules::{Clocks, EndReason, Role};
 use sfml::{
     graphics::{Drawable, FloatRect, RcFont, RcText, Transformable},
     system::Vector2f,
@@ -9,8 +10,10 @@ use sfml::{
 
 use crate::{
     commands::{
-        become_role_cmd, check_error, close_lobby_cmd, join_lobby_cmd, leave_lobby_cmd,
-        make_host_cmd, make_move_cmd, send_message_cmd, start_game_cmd,
+        accept_draw_cmd, answer_takeback_cmd, become_role_cmd, check_error, close_lobby_cmd,
+        get_profile_short_cmd, join_lobby_cmd, leave_lobby_cmd, make_host_cmd, make_move_cmd,
+        offer_draw_cmd, rematch_cmd, request_takeback_cmd, resign_cmd, send_message_cmd,
+        start_game_cmd,
     },
     events::{Event, NetworkEvent, UIEvent, Window},
     gui::components::{
@@ -18,7 +21,7 @@ use crate::{
         MouseObserver, PlayerCard, Scrollable,
     },
     rc_cell,
-    types::{GameStateShared, Lobby, Player, RcCell, UserType},
+    types::{GameStateShared, Lobby, Phase, Player, RcCell, UserType},
     BUTTON_HEIGHT, PADDING, WINDOW_SIZE,
 };
 
@@ -32,6 +35,15 @@ pub struct GameWindow<'a> {
 
     font: &'a RcFont,
     game_state: RefCell<RcText>,
+    clocks_text: RefCell<RcText>,
+    // the clocks as the server last sent them and since when the one of the player to move runs
+    clocks: RefCell<Option<(Clocks, Option<(bool, Instant)>)>>,
+    // the player that offered a draw, until the next move
+    draw_offer: RefCell<Option<Role>>,
+    // the player that asked to take back their turn, until the next move
+    takeback_request: RefCell<Option<Role>>,
+    // ids of the (devil, angel) of the current or the last game
+    roles: RefCell<(u32, u32)>,
     buttons: Vec<RcCell<Button<'a>>>,
     show_buttons: RefCell<Vec<usize>>,
     players_scrollable: RcCell<Scrollable<'a, PlayerCard<'a>>>,
@@ -51,23 +63,28 @@ impl<'a> GameWindow<'a> {
         state: GameStateShared,
     ) -> GameWindow<'a> {
         let mut buttons = vec![];
+        // the ids after "Back" are taken by the players, the game and the chat
         let texts = [
-            "Start game",
-            "Make host",
-            "Close lobby",
-            "Spectate",
-            "Play",
-            "Back",
+            (0, "Start game"),
+            (1, "Make host"),
+            (2, "Close lobby"),
+            (3, "Spectate"),
+            (4, "Play"),
+            (5, "Back"),
+            (11, "Resign"),
+            (12, "Draw"),
+            (13, "Takeback"),
+            (14, "Rematch"),
         ];
 
-        for (i, text) in texts.into_iter().enumerate() {
+        for (id, text) in texts {
             buttons.push(rc_cell!(Button::builder()
                 .set_size(
                     WINDOW_SIZE - (GameWindow::GAME_WIDTH + 3.0 * PADDING),
                     BUTTON_HEIGHT
                 )
                 .set_text(text)
-                .build(i as u32, window, sender.clone(), font)));
+                .build(id, window, sender.clone(), font)));
         }
 
         let mut game_state = RcText::new("Waiting for host to start a new game", font, 20);
@@ -79,8 +96,17 @@ impl<'a> GameWindow<'a> {
             10.0 + GameWindow::GAME_HEIGHT / 2.0 + text_height / 2.0,
         ));
 
+        // under the buttons, there are at most 8 of them
+        let mut clocks_text = RcText::new("", font, 20);
+        clocks_text.set_position((PADDING, PADDING + 8.0 * (PADDING + BUTTON_HEIGHT)));
+
         GameWindow {
             game_state: RefCell::new(game_state),
+            clocks_text: RefCell::new(clocks_text),
+            clocks: RefCell::new(None),
+            draw_offer: RefCell::new(None),
+            takeback_request: RefCell::new(None),
+            roles: RefCell::new((0, 0)),
             window,
             state,
             selected_player: RefCell::new(None),
@@ -185,8 +211,8 @@ impl<'a> GameWindow<'a> {
         show_buttons.clear();
 
         match user_type {
-            UserType::Host => show_buttons.extend_from_slice(&[0, 1, 2, 5]),
-            UserType::Player => show_buttons.extend_from_slice(&[3, 5]),
+            UserType::Host => show_buttons.extend_from_slice(&[0, 1, 2, 6, 7, 8, 9, 5]),
+            UserType::Player => show_buttons.extend_from_slice(&[3, 6, 7, 8, 9, 5]),
             UserType::Spectator => show_buttons.extend_from_slice(&[4, 5]),
         }
 
@@ -214,6 +240,70 @@ impl<'a> GameWindow<'a> {
             10.0 + GameWindow::GAME_HEIGHT / 2.0 + text_height / 2.0,
         ));
     }
+
+    fn player_name(&self, id: u32) -> String {
+        if id == 0 {
+            return String::from("computer");
+        }
+
+        self.state
+            .borrow()
+            .lobby
+            .as_ref()
+            .unwrap()
+            .players
+            .iter()
+            .find(|p| p.id == id)
+            .map(|p| p.name.clone())
+            .unwrap_or_default()
+    }
+
+    // names of the (devil, angel) of the current or the last game
+    fn player_names(&self) -> (String, String) {
+        let (devil, angel) = *self.roles.borrow();
+        (self.player_name(devil), self.player_name(angel))
+    }
+
+    // the side the user plays, or the angel if they aren't playing
+    fn role(&self, id: u32) -> Role {
+        if self.roles.borrow().0 == id {
+            Role::Devil
+        } else {
+            Role::Angel
+        }
+    }
+
+    // the clock of the player to move counts down from the time in the last update
+    fn update_clocks(&self) {
+        let mut text = self.clocks_text.borrow_mut();
+
+        let (clocks, running) = match self.clocks.borrow().as_ref() {
+            Some(&(clocks, running)) => (clocks, running),
+            None => {
+                text.set_string("");
+                return;
+            }
+        };
+
+        let left = |devil: bool| match running {
+            Some((turn, since)) if turn == devil => clocks
+                .get(devil)
+                .saturating_sub(since.elapsed().as_millis() as u64),
+            _ => clocks.get(devil),
+        };
+
+        text.set_string(&format!(
+            "Devil {}\nAngel {}",
+            format_clock(left(true)),
+            format_clock(left(false))
+        ));
+    }
+}
+
+// minutes and seconds, rounded up so a clock only shows 0:00 once it ran out
+fn format_clock(ms: u64) -> String {
+    let seconds = (ms + 999) / 1000;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
 }
 
 impl<'a> WindowState for GameWindow<'a> {
@@ -223,8 +313,9 @@ impl<'a> WindowState for GameWindow<'a> {
 
     fn enter(&self) -> anyhow::Result<()> {
         let mut state = self.state.borrow_mut();
+        let mut backlog = vec![];
         if let Some(lobby) = state.selected_lobby.as_ref() {
-            match join_lobby_cmd(&state.id, lobby.addr, &state.lobby) {
+            match join_lobby_cmd(&state.token, lobby.addr, &state.lobby) {
                 Ok(lobby_state) => {
                     let user_type = lobby_state
                         .players
@@ -239,6 +330,7 @@ impl<'a> WindowState for GameWindow<'a> {
                         players: lobby_state.players,
                         user_type,
                     });
+                    backlog = lobby_state.chat;
                 }
                 Err(e) => {
                     if let Err(e) = self.sender.send(UIEvent::Error(check_error(e))) {
@@ -258,9 +350,16 @@ impl<'a> WindowState for GameWindow<'a> {
 
         self.update_state(lobby.user_type);
         self.set_game_state("Waiting for host to start a new game");
+        *self.clocks.borrow_mut() = None;
+        *self.draw_offer.borrow_mut() = None;
+        *self.takeback_request.borrow_mut() = None;
+        *self.roles.borrow_mut() = (0, 0);
 
         let mut chat = self.chat.borrow_mut();
         chat.clear();
+        for message in backlog {
+            chat.add_message(message.name, message.text);
+        }
 
         let mut players_scrollable = self.players_scrollable.borrow_mut();
         players_scrollable.clear();
@@ -292,7 +391,7 @@ impl<'a> WindowState for GameWindow<'a> {
     fn exit(&self) -> anyhow::Result<()> {
         let mut state = self.state.borrow_mut();
         if state.lobby.is_some() {
-            match leave_lobby_cmd(&state.id.clone(), &mut state.lobby) {
+            match leave_lobby_cmd(&state.token.clone(), &mut state.lobby) {
                 Ok(_) => {
                     self.players_scrollable.borrow_mut().clear();
                 }
@@ -333,7 +432,7 @@ impl<'a> EventHandler for GameWindow<'a> {
             Event::UI(UIEvent::ButtonClicked(event_data)) if event_data.window == self.window => {
                 let mut state = self.state.borrow_mut();
                 match event_data.id {
-                    0 => match start_game_cmd(&state.id, &state.lobby) {
+                    0 => match start_game_cmd(&state.token, &state.lobby) {
                         Ok(_) => {}
                         Err(e) => {
                             if let Err(e) = self.sender.send(UIEvent::Error(check_error(e))) {
@@ -343,7 +442,7 @@ impl<'a> EventHandler for GameWindow<'a> {
                     },
                     1 => {
                         if let Some(player) = self.selected_player.borrow().as_ref() {
-                            match make_host_cmd(&state.id, player.id, &state.lobby) {
+                            match make_host_cmd(&state.token, player.id, &state.lobby) {
                                 Ok(_) => {}
                                 Err(e) => {
                                     if let Err(e) = self.sender.send(UIEvent::Error(check_error(e)))
@@ -354,7 +453,7 @@ impl<'a> EventHandler for GameWindow<'a> {
                             }
                         }
                     }
-                    2 => match close_lobby_cmd(&state.id.clone(), &mut state.lobby) {
+                    2 => match close_lobby_cmd(&state.token.clone(), &mut state.lobby) {
                         Ok(_) => {}
                         Err(e) => {
                             if let Err(e) = self.sender.send(UIEvent::Error(check_error(e))) {
@@ -362,7 +461,7 @@ impl<'a> EventHandler for GameWindow<'a> {
                             }
                         }
                     },
-                    3 => match become_role_cmd(&state.id, UserType::Spectator, &state.lobby) {
+                    3 => match become_role_cmd(&state.token, UserType::Spectator, &state.lobby) {
                         Ok(_) => {}
                         Err(e) => {
                             if let Err(e) = self.sender.send(UIEvent::Error(check_error(e))) {
@@ -370,7 +469,58 @@ impl<'a> EventHandler for GameWindow<'a> {
                             }
                         }
                     },
-                    4 => match become_role_cmd(&state.id, UserType::Player, &state.lobby) {
+                    4 => match become_role_cmd(&state.token, UserType::Player, &state.lobby) {
+                        Ok(_) => {}
+                        Err(e) => {
+                            if let Err(e) = self.sender.send(UIEvent::Error(check_error(e))) {
+                                println!("send error: {e:?}");
+                            }
+                        }
+                    },
+                    11 => match resign_cmd(&state.token, &state.lobby) {
+                        Ok(_) => {}
+                        Err(e) => {
+                            if let Err(e) = self.sender.send(UIEvent::Error(check_error(e))) {
+                                println!("send error: {e:?}");
+                            }
+                        }
+                    },
+                    // accepts the offer of the opponent, or makes one
+                    12 => {
+                        let role = self.role(state.id);
+
+                        let res = match *self.draw_offer.borrow() {
+                            Some(offer) if offer != role => {
+                                accept_draw_cmd(&state.token, &state.lobby)
+                            }
+                            _ => offer_draw_cmd(&state.token, &state.lobby),
+                        };
+
+                        if let Err(e) = res {
+                            if let Err(e) = self.sender.send(UIEvent::Error(check_error(e))) {
+                                println!("send error: {e:?}");
+                            }
+                        }
+                    }
+                    // agrees to the opponent's takeback, or asks for one, making a move turns
+                    // the opponent's request down
+                    13 => {
+                        let role = self.role(state.id);
+
+                        let res = match *self.takeback_request.borrow() {
+                            Some(request) if request != role => {
+                                answer_takeback_cmd(&state.token, true, &state.lobby)
+                            }
+                            _ => request_takeback_cmd(&state.token, &state.lobby),
+                        };
+
+                        if let Err(e) = res {
+                            if let Err(e) = self.sender.send(UIEvent::Error(check_error(e))) {
+                                println!("send error: {e:?}");
+                            }
+                        }
+                    }
+                    14 => match rematch_cmd(&state.token, &state.lobby) {
                         Ok(_) => {}
                         Err(e) => {
                             if let Err(e) = self.sender.send(UIEvent::Error(check_error(e))) {
@@ -379,7 +529,7 @@ impl<'a> EventHandler for GameWindow<'a> {
                         }
                     },
                     8 => match send_message_cmd(
-                        &state.id,
+                        &state.token,
                         self.chat.borrow_mut().get_message(),
                         &state.lobby,
                     ) {
@@ -396,11 +546,30 @@ impl<'a> EventHandler for GameWindow<'a> {
             Event::UI(UIEvent::PlayerCardClicked(event_data))
                 if event_data.window == self.window =>
             {
+                let state = self.state.borrow();
+
+                match get_profile_short_cmd(&state.token, event_data.data.id) {
+                    Ok(profile) => {
+                        let players = &state.lobby.as_ref().unwrap().players;
+                        let mut players_scrollable = self.players_scrollable.borrow_mut();
+
+                        if let Some(i) = players.iter().position(|p| p.id == profile.id) {
+                            let card = players_scrollable.get(i);
+                            card.borrow_mut().set_profile(&profile);
+                        }
+                    }
+                    Err(e) => {
+                        if let Err(e) = self.sender.send(UIEvent::Error(check_error(e))) {
+                            println!("send error: {e:?}");
+                        }
+                    }
+                }
+
                 *self.selected_player.borrow_mut() = Some(event_data.data);
             }
             Event::UI(UIEvent::GameMove(e)) => {
                 let state = self.state.borrow();
-                match make_move_cmd(&state.id, (e.x, e.y), &state.lobby) {
+                match make_move_cmd(&state.token, (e.x, e.y), &state.lobby) {
                     Ok(_) => {}
                     Err(e) => {
                         if let Err(e) = self.sender.send(UIEvent::Error(check_error(e))) {
@@ -435,42 +604,145 @@ impl<'a> EventHandler for GameWindow<'a> {
                 }
             }
             Event::Network(NetworkEvent::GameStarted(e)) => {
+                *self.clocks.borrow_mut() = e
+                    .game
+                    .clocks
+                    .map(|clocks| (clocks, Some((e.game.turn, Instant::now()))));
+                *self.draw_offer.borrow_mut() = None;
+                *self.takeback_request.borrow_mut() = None;
+                *self.roles.borrow_mut() = (e.game.devil, e.game.angel);
+
                 self.game.borrow_mut().start(e);
                 self.mouse_observer.add_observer(self.game.clone());
             }
             Event::Network(NetworkEvent::GameUpdated(e)) => {
-                let mut host = String::new();
-                let mut player = String::from("computer");
-
-                self.state
-                    .borrow()
-                    .lobby
-                    .as_ref()
-                    .unwrap()
-                    .players
-                    .iter()
-                    .for_each(|p| match p.user_type {
-                        UserType::Host => host = p.name.clone(),
-                        UserType::Player => player = p.name.clone(),
-                        _ => {}
-                    });
+                let over = e.update.win.0 || e.update.win.1;
 
-                if e.win.0 {
-                    self.set_game_state(&format!(
-                        "Player {host} won! Waiting for host to start a new game"
-                    ));
-                    self.mouse_observer
-                        .remove_observer(self.game.borrow().get_id());
-                } else if e.win.1 {
-                    self.set_game_state(&format!(
-                        "Player {player} won! Waiting for host to start a new game"
-                    ));
-                    self.mouse_observer
-                        .remove_observer(self.game.borrow().get_id());
-                }
+                // the devil moves next while it has walls left
+                *self.clocks.borrow_mut() = e.update.clocks.map(|clocks| {
+                    let running = (!over).then(|| (e.update.walls_left > 0, Instant::now()));
+                    (clocks, running)
+                });
+                // a move turns down the offers
+                *self.draw_offer.borrow_mut() = None;
+                *self.takeback_request.borrow_mut() = None;
 
                 self.game.borrow_mut().update(e);
             }
+            Event::Network(NetworkEvent::PhaseChanged(e)) => match e.change.to {
+                Phase::Countdown => self.set_game_state("The game is about to start"),
+                // the message of the last game stays up until the lobby is set up again, a
+                // rematch starts right from the finished phase
+                Phase::Waiting if e.change.from == Phase::Finished => {
+                    self.set_game_state("Waiting for host to start a new game")
+                }
+                _ => {}
+            },
+            Event::Network(NetworkEvent::DrawOffered(e)) => {
+                let (devil, angel) = self.player_names();
+                let name = match e.role {
+                    Role::Devil => devil,
+                    Role::Angel => angel,
+                };
+
+                *self.draw_offer.borrow_mut() = Some(e.role);
+                self.chat
+                    .borrow_mut()
+                    .add_message(name, String::from("offers a draw"));
+            }
+            Event::Network(NetworkEvent::TakebackRequested(e)) => {
+                let (devil, angel) = self.player_names();
+                let name = match e.role {
+                    Role::Devil => devil,
+                    Role::Angel => angel,
+                };
+
+                *self.takeback_request.borrow_mut() = Some(e.role);
+                self.chat
+                    .borrow_mut()
+                    .add_message(name, String::from("asks to take back their turn"));
+            }
+            Event::Network(NetworkEvent::TakebackDeclined(e)) => {
+                let (devil, angel) = self.player_names();
+                let name = match e.role {
+                    Role::Devil => devil,
+                    Role::Angel => angel,
+                };
+
+                *self.takeback_request.borrow_mut() = None;
+                self.chat
+                    .borrow_mut()
+                    .add_message(name, String::from("declines the takeback"));
+            }
+            Event::Network(NetworkEvent::GameRewound(e)) => {
+                *self.clocks.borrow_mut() = e
+                    .game
+                    .clocks
+                    .map(|clocks| (clocks, Some((e.game.turn, Instant::now()))));
+                *self.draw_offer.borrow_mut() = None;
+                *self.takeback_request.borrow_mut() = None;
+
+                self.game.borrow_mut().rewind(e);
+            }
+            Event::Network(NetworkEvent::GameEnded(e)) => {
+                let (devil, angel) = self.player_names();
+                let result = e.result;
+
+                *self.clocks.borrow_mut() = result.clocks.map(|clocks| (clocks, None));
+                *self.draw_offer.borrow_mut() = None;
+                *self.takeback_request.borrow_mut() = None;
+
+                let message = match result.winner {
+                    Some(winner) => {
+                        let (name, loser) = match winner {
+                            Role::Devil => (devil, angel),
+                            Role::Angel => (angel, devil),
+                        };
+
+                        match result.reason {
+                            EndReason::Timeout => format!("Player {loser} ran out of time!"),
+                            EndReason::Resigned => format!("Player {loser} resigned!"),
+                            EndReason::Disconnected => format!("Player {loser} left!"),
+                            _ => format!("Player {name} won!"),
+                        }
+                    }
+                    None => String::from("The game ended in a draw!"),
+                };
+
+                self.set_game_state(&format!("{message} Waiting for a rematch"));
+                self.mouse_observer
+                    .remove_observer(self.game.borrow().get_id());
+                self.game.borrow_mut().stop();
+            }
+            // the score is sent after every game of a match, points are doubled to count draws
+            Event::Network(NetworkEvent::MatchUpdated(e)) => {
+                let series = e.series;
+                let (first, second) = series.points();
+                let mut text = format!(
+                    "{} {} - {} {}",
+                    self.player_name(series.players.0),
+                    first as f32 / 2.0,
+                    second as f32 / 2.0,
+                    self.player_name(series.players.1)
+                );
+
+                if series.is_over() {
+                    text += &match series.winner() {
+                        Some(id) => format!(", {} wins the match", self.player_name(id)),
+                        None => String::from(", the match is tied"),
+                    };
+                }
+
+                self.chat
+                    .borrow_mut()
+                    .add_message(String::from("match"), text);
+            }
+            Event::Network(NetworkEvent::RematchVoted(e)) => {
+                let name = self.player_name(e.user_id);
+                self.chat
+                    .borrow_mut()
+                    .add_message(name, String::from("wants a rematch"));
+            }
             Event::Network(NetworkEvent::LobbyClosing(_)) => {
                 let mut state = self.state.borrow_mut();
                 state.lobby = None;
@@ -492,6 +764,9 @@ impl<'a> Drawable for GameWindow<'a> {
         target.draw(&*self.players_scrollable.borrow());
         target.draw(&*self.game.borrow());
 
+        self.update_clocks();
+        target.draw(&*self.clocks_text.borrow());
+
         if !self.game.borrow().began {
             target.draw(&*self.game_state.borrow());
         }
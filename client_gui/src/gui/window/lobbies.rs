@@ -85,7 +85,7 @@ impl<'a> LobbiesWindow<'a> {
     fn search(&self) -> Result<()> {
         let state = self.state.borrow();
 
-        let new_lobbies = match get_lobbies_cmd(&state.id, self.range.0, self.range.1) {
+        let new_lobbies = match get_lobbies_cmd(&state.token, self.range.0, self.range.1) {
             Ok(lobbies) => lobbies,
             Err(e) => {
                 if let Err(e) = self.sender.send(UIEvent::Error(check_error(e))) {
@@ -6,7 +6,7 @@ use sfml::{
 };
 
 use crate::{
-    commands::{change_name_cmd, check_error},
+    commands::{change_name_cmd, check_error, disconnect_cmd, login_cmd, register_cmd},
     events::{Event, UIEvent, Window},
     gui::components::{Button, EventHandler, EventHandlerMut, Input, MouseObserver},
     rc_cell,
@@ -18,6 +18,7 @@ use super::WindowState;
 
 struct Settings {
     name: String,
+    password: String,
 }
 
 pub struct SettingsWindow<'a> {
@@ -27,6 +28,7 @@ pub struct SettingsWindow<'a> {
     sender: mpsc::Sender<UIEvent>,
 
     input: RcCell<Input>,
+    password_input: RcCell<Input>,
     buttons: Vec<RcCell<Button<'a>>>,
     mouse_observer: MouseObserver<'a>,
 }
@@ -43,11 +45,11 @@ impl<'a> SettingsWindow<'a> {
         let offset = BUTTON_HEIGHT + PADDING;
 
         let mut buttons = vec![];
-        let texts = ["Save", "Back"];
+        let texts = ["Save", "Log in", "Register", "Back"];
 
         for (i, text) in texts.into_iter().enumerate() {
             buttons.push(rc_cell!(Button::builder()
-                .set_position(x, y + (i + 1) as f32 * offset)
+                .set_position(x, y + (i + 2) as f32 * offset)
                 .set_text(text)
                 .build(i as u32 + 1, window, sender.clone(), font)));
         }
@@ -57,12 +59,18 @@ impl<'a> SettingsWindow<'a> {
             state,
             settings: RefCell::new(Settings {
                 name: String::from(DEFAULT_NAME),
+                password: String::new(),
             }),
             input: rc_cell!(Input::builder()
                 .set_bounds(x, y, BUTTON_WIDTH)
                 .set_font_size(20)
                 .set_placeholder("Your name")
                 .build(0, window, sender.clone(), font)),
+            password_input: rc_cell!(Input::builder()
+                .set_bounds(x, y + offset, BUTTON_WIDTH)
+                .set_font_size(20)
+                .set_placeholder("Password (accounts only)")
+                .build(5, window, sender.clone(), font)),
             buttons,
             mouse_observer: MouseObserver::new(WINDOW_SIZE as u32, WINDOW_SIZE as u32),
             sender,
@@ -71,6 +79,8 @@ impl<'a> SettingsWindow<'a> {
 
     pub fn init(&self) {
         self.mouse_observer.add_observer(self.input.clone());
+        self.mouse_observer
+            .add_observer(self.password_input.clone());
         for button in &self.buttons {
             self.mouse_observer.add_observer(button.clone());
         }
@@ -91,6 +101,9 @@ impl<'a> WindowState for SettingsWindow<'a> {
         let mut input = self.input.borrow_mut();
         input.set_value(state.name.clone());
 
+        settings.password.clear();
+        self.password_input.borrow_mut().set_value(String::new());
+
         Ok(())
     }
 
@@ -102,6 +115,7 @@ impl<'a> WindowState for SettingsWindow<'a> {
 impl<'a> EventHandler for SettingsWindow<'a> {
     fn handle_event(&self, e: Event) {
         self.input.borrow_mut().handle_event(e.clone());
+        self.password_input.borrow_mut().handle_event(e.clone());
 
         match e {
             Event::Sfml(sfml::window::Event::MouseButtonPressed { button, x, y }) => {
@@ -119,14 +133,18 @@ impl<'a> EventHandler for SettingsWindow<'a> {
             }
             Event::UI(UIEvent::InputChanged(e)) if e.window == self.window => {
                 let mut settings = self.settings.borrow_mut();
-                settings.name = e.data;
+                match e.id {
+                    0 => settings.name = e.data,
+                    5 => settings.password = e.data,
+                    _ => {}
+                }
             }
             Event::UI(UIEvent::ButtonClicked(e)) if e.window == self.window => {
                 if e.id == 1 {
                     'save: {
                         let mut state = self.state.borrow_mut();
                         let settings = self.settings.borrow();
-                        match change_name_cmd(&state.id, settings.name.clone(), &state.lobby) {
+                        match change_name_cmd(&state.token, settings.name.clone()) {
                             Ok(_) => println!("saved!"),
                             Err(e) => {
                                 if let Err(e) = self.sender.send(UIEvent::Error(check_error(e))) {
@@ -136,6 +154,40 @@ impl<'a> EventHandler for SettingsWindow<'a> {
                             }
                         }
 
+                        state.name = settings.name.clone();
+                    }
+                } else if e.id == 2 || e.id == 3 {
+                    'account: {
+                        let mut state = self.state.borrow_mut();
+                        let settings = self.settings.borrow();
+
+                        let res = if e.id == 2 {
+                            login_cmd(settings.name.clone(), settings.password.clone(), state.addr)
+                        } else {
+                            register_cmd(
+                                settings.name.clone(),
+                                settings.password.clone(),
+                                state.addr,
+                            )
+                        };
+
+                        let (id, token) = match res {
+                            Ok(res) => res,
+                            Err(e) => {
+                                if let Err(e) = self.sender.send(UIEvent::Error(check_error(e))) {
+                                    println!("send error: {e:?}");
+                                }
+                                break 'account;
+                            }
+                        };
+
+                        // the previous session is no longer needed once the account is used
+                        if let Err(e) = disconnect_cmd(&state.token) {
+                            check_error(e);
+                        }
+
+                        state.id = id;
+                        state.token = token;
                         state.name = settings.name.clone();
                     }
                 }
@@ -152,6 +204,7 @@ impl<'a> Drawable for SettingsWindow<'a> {
         _: &sfml::graphics::RenderStates<'texture, 'shader, 'shader_texture>,
     ) {
         target.draw(&*self.input.borrow());
+        target.draw(&*self.password_input.borrow());
         for button in &self.buttons {
             target.draw(&*button.borrow());
         }
@@ -35,20 +35,25 @@ const PADDING: f32 = 10.0;
 const DEFAULT_NAME: &str = "Player";
 
 fn main() {
+    let event_loop = EventLoop::new().unwrap();
+
     let game_state: GameStateShared = rc_cell!(GameState {
         id: 0, // invalid id, doesn't matter because we connect before using the id
+        token: String::new(),
         name: String::from(DEFAULT_NAME),
+        addr: event_loop.addr,
         lobby: None,
         selected_lobby: None,
     });
 
-    let event_loop = EventLoop::new().unwrap();
-
     {
         let mut game_state = game_state.borrow_mut();
 
         match connect_cmd(game_state.name.clone(), event_loop.addr) {
-            Ok(id) => game_state.id = id,
+            Ok((id, token)) => {
+                game_state.id = id;
+                game_state.token = token;
+            }
             Err(e) => {
                 check_error(e);
                 panic!("cannot connect to server");
@@ -183,7 +188,7 @@ fn main() {
                     },
                     Window::Settings => match event_data.id {
                         1 => switch_state(current_window.clone(), &start_window),
-                        2 => switch_state(current_window.clone(), &start_window),
+                        4 => switch_state(current_window.clone(), &start_window),
                         _ => {}
                     },
                     Window::Game => {
@@ -234,8 +239,11 @@ fn main() {
     {
         let mut game_state = game_state.borrow_mut();
 
-        match disconnect_cmd(&game_state.id) {
-            Ok(_) => game_state.id = 0,
+        match disconnect_cmd(&game_state.token) {
+            Ok(_) => {
+                game_state.id = 0;
+                game_state.token.clear();
+            }
             Err(e) => {
                 check_error(e);
             }
@@ -14,6 +14,7 @@ pub type BoolMutex = Arc<Mutex<bool>>;
 pub type EventQueue = Arc<Mutex<VecDeque<EventQueueItem>>>;
 pub type EventQueueItem = Event;
 pub type UserId = u32;
+pub type SessionToken = String;
 pub type UserName = String;
 
 #[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
@@ -39,6 +40,17 @@ impl Display for Player {
     }
 }
 
+// summary of a player's profile shown on their card
+#[derive(Debug, Deserialize)]
+pub struct ProfileShort {
+    pub id: u32,
+    pub name: String,
+    pub angel_rating: Option<u32>,
+    pub devil_rating: Option<u32>,
+    pub games: u32,
+    pub wins: u32,
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct LobbyAddr {
     pub id: u16,
@@ -49,6 +61,30 @@ pub struct LobbyAddr {
 pub struct LobbyState {
     pub name: String,
     pub players: Vec<Player>,
+    pub chat: Vec<ChatMessage>, // the latest messages, oldest first
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChatMessage {
+    pub id: u32,
+    pub user_id: UserId,
+    pub name: UserName,
+    pub text: String,
+    pub sent_at: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum Phase {
+    Waiting,
+    Countdown, // the game begins once it's over
+    InGame,
+    Finished,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct PhaseChange {
+    pub from: Phase,
+    pub to: Phase,
 }
 
 #[derive(Debug, Deserialize)]
@@ -95,14 +131,14 @@ pub type LobbyAddrVec = Vec<LobbyAddr>;
 /// Option is used because initially there is no state, throughout the code unwrap will be unused because we know for sure that the values exist because in order to get to window X part Y of state must be initialized
 pub struct GameState {
     pub id: UserId,
+    pub token: SessionToken, // sent with every request instead of the id
     pub name: UserName,
+    pub addr: SocketAddr, // address of the event loop, where the server sends notifications
     pub lobby: Option<Lobby>,
     pub selected_lobby: Option<LobbyShort>,
 }
 pub type GameStateShared = RcCell<GameState>;
 
-pub const GRID_SIZE: usize = 11;
-
 pub type RcCell<T> = Rc<RefCell<T>>;
 
 #[macro_export]
@@ -1,7 +1,7 @@
 // TODO: optimize allocations for packet reading, it isnt necessary to allocate on each recv
 
 thread_local! {
-    static HEADER_BYTES: RefCell<[u8; 8]> = RefCell::new([0u8; 8]);
+    static HEADER_BYTES: RefCell<[u8; 8]> = const { RefCell::new([0u8; 8]) };
 }
 
 use std::cell::RefCell;
@@ -28,29 +28,54 @@ pub enum Type {
     // main server requests
     Ping,
     Connect,
+    Register,
+    Login,
     Disconnect,
     FindLobby,
     CreateLobby,
     GetLobbies,
     ChangeName,
+    GetGameHistory,
+    GetGame,
+    GetMatch,
+    GetLeaderboard,
+    GetProfile,
+    GetProfileShort,
     // lobby requests
     GetLobbyState,
     JoinLobby,
+    SwitchLobby,
     LeaveLobby,
     CloseLobby,
     MakeHost,
     BecomeRole,
+    ChangeSettings,
     SendMessage,
-    ChangedName,
+    GetChatHistory,
     StartGame,
     MakeMove,
+    Resign,
+    OfferDraw,
+    AcceptDraw,
+    RequestTakeback,
+    AnswerTakeback,
+    Rematch,
     // client notifications
     PlayerJoined,
     PlayerLeft,
     PlayerUpdated,
     GameStarted,
     GameUpdated,
+    PhaseChanged,
+    DrawOffered,
+    GameEnded,
+    TakebackRequested,
+    TakebackDeclined,
+    GameRewound,
+    RematchVoted,
+    MatchUpdated,
     LobbyClosing,
+    SettingsChanged,
     Message,
     // responses
     Success,
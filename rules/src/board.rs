@@ -0,0 +1,312 @@
+use serde_derive::{Deserialize, Serialize};
+
+use super::game::Grid;
+
+// which tiles are next to each other
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Topology {
+    Hex,     // odd lines are shifted right by half a tile
+    Square4, // only across the sides of a square
+    Square8, // across the corners as well
+}
+
+// which tiles of the size x size grid make up the board
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Outline {
+    Rectangle,
+    Circle,
+    Hexagon,  // only on hex boards
+    Cylinder, // the left and right edges are joined, so the way out is up or down
+}
+
+// the edges the angel can leave the board through
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Exits {
+    pub top: bool,
+    pub bottom: bool,
+    pub left: bool,
+    pub right: bool,
+}
+
+impl Default for Exits {
+    fn default() -> Self {
+        Exits {
+            top: true,
+            bottom: true,
+            left: true,
+            right: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Board {
+    pub topology: Topology,
+    pub outline: Outline,
+    pub exits: Exits,
+    pub tiles: Grid, // whether the position is part of the board
+}
+
+impl Topology {
+    const HEX: [[(i32, i32); 6]; 2] = [
+        [(-1, 0), (0, 1), (1, 0), (1, -1), (0, -1), (-1, -1)],
+        [(-1, 0), (-1, 1), (0, 1), (1, 1), (1, 0), (0, -1)],
+    ];
+    const SQUARE4: [(i32, i32); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+    const SQUARE8: [(i32, i32); 8] = [
+        (-1, 0),
+        (-1, 1),
+        (0, 1),
+        (1, 1),
+        (1, 0),
+        (1, -1),
+        (0, -1),
+        (-1, -1),
+    ];
+
+    // offsets of the neighbours of a tile on the given line
+    pub fn offsets(&self, line: i32) -> &'static [(i32, i32)] {
+        match self {
+            Topology::Hex => &Topology::HEX[line.rem_euclid(2) as usize],
+            Topology::Square4 => &Topology::SQUARE4,
+            Topology::Square8 => &Topology::SQUARE8,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Topology::Hex => "hex",
+            Topology::Square4 => "square4",
+            Topology::Square8 => "square8",
+        }
+    }
+
+    pub fn parse(topology: &str) -> Option<Topology> {
+        match topology {
+            "hex" => Some(Topology::Hex),
+            "square4" => Some(Topology::Square4),
+            "square8" => Some(Topology::Square8),
+            _ => None,
+        }
+    }
+}
+
+impl Outline {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Outline::Rectangle => "rectangle",
+            Outline::Circle => "circle",
+            Outline::Hexagon => "hexagon",
+            Outline::Cylinder => "cylinder",
+        }
+    }
+
+    pub fn parse(outline: &str) -> Option<Outline> {
+        match outline {
+            "rectangle" => Some(Outline::Rectangle),
+            "circle" => Some(Outline::Circle),
+            "hexagon" => Some(Outline::Hexagon),
+            "cylinder" => Some(Outline::Cylinder),
+            _ => None,
+        }
+    }
+}
+
+impl Exits {
+    // one letter for each open edge, "tblr" when all of them are
+    pub fn as_string(&self) -> String {
+        [
+            (self.top, 't'),
+            (self.bottom, 'b'),
+            (self.left, 'l'),
+            (self.right, 'r'),
+        ]
+        .iter()
+        .filter(|(open, _)| *open)
+        .map(|(_, letter)| letter)
+        .collect()
+    }
+
+    pub fn parse(exits: &str) -> Option<Exits> {
+        if !exits.chars().all(|letter| "tblr".contains(letter)) {
+            return None;
+        }
+
+        Some(Exits {
+            top: exits.contains('t'),
+            bottom: exits.contains('b'),
+            left: exits.contains('l'),
+            right: exits.contains('r'),
+        })
+    }
+}
+
+impl Board {
+    pub fn new(size: usize, topology: Topology, outline: Outline, exits: Exits) -> Board {
+        let center = (size as i32 / 2, size as i32 / 2);
+        let radius = size as f64 / 2.0;
+
+        let mut tiles = vec![vec![true; size]; size];
+
+        for (i, line) in tiles.iter_mut().enumerate() {
+            for (j, tile) in line.iter_mut().enumerate() {
+                let pos = (i as i32, j as i32);
+
+                *tile = match outline {
+                    Outline::Rectangle | Outline::Cylinder => true,
+                    Outline::Circle => {
+                        let (x, y) = Board::point(topology, pos);
+                        let (cx, cy) = Board::point(topology, center);
+
+                        ((x - cx).powi(2) + (y - cy).powi(2)).sqrt() <= radius
+                    }
+                    Outline::Hexagon => Board::hex_distance(pos, center) <= size as i32 / 2,
+                };
+            }
+        }
+
+        Board {
+            topology,
+            outline,
+            exits,
+            tiles,
+        }
+    }
+
+    // where the center of a tile is drawn, tiles being a unit apart on their line
+    fn point(topology: Topology, pos: (i32, i32)) -> (f64, f64) {
+        match topology {
+            Topology::Hex => (
+                pos.1 as f64 + 0.5 * pos.0.rem_euclid(2) as f64,
+                pos.0 as f64 * 3f64.sqrt() / 2.0,
+            ),
+            Topology::Square4 | Topology::Square8 => (pos.1 as f64, pos.0 as f64),
+        }
+    }
+
+    // number of steps between two tiles of a hex board, going through cube coordinates
+    fn hex_distance(a: (i32, i32), b: (i32, i32)) -> i32 {
+        let cube = |pos: (i32, i32)| {
+            let q = pos.1 - (pos.0 - pos.0.rem_euclid(2)) / 2;
+            (q, pos.0, -q - pos.0)
+        };
+        let (a, b) = (cube(a), cube(b));
+
+        ((a.0 - b.0).abs() + (a.1 - b.1).abs() + (a.2 - b.2).abs()) / 2
+    }
+
+    pub fn size(&self) -> usize {
+        self.tiles.len()
+    }
+
+    // whether pos is inside the grid, without wrapping it around
+    fn in_grid(&self, pos: (i32, i32)) -> bool {
+        let size = self.size() as i32;
+
+        0 <= pos.0 && pos.0 < size && 0 <= pos.1 && pos.1 < size
+    }
+
+    pub fn contains(&self, pos: (i32, i32)) -> bool {
+        self.in_grid(pos) && self.tiles[pos.0 as usize][pos.1 as usize]
+    }
+
+    // the position a step off the grid lands on, or None if it leaves the board
+    fn wrap(&self, pos: (i32, i32)) -> Option<(i32, i32)> {
+        let size = self.size() as i32;
+        let pos = match self.outline {
+            Outline::Cylinder => (pos.0, pos.1.rem_euclid(size)),
+            _ => pos,
+        };
+
+        self.contains(pos).then_some(pos)
+    }
+
+    pub fn neighbours(&self, pos: (i32, i32)) -> Vec<(i32, i32)> {
+        self.topology
+            .offsets(pos.0)
+            .iter()
+            .filter_map(|off| self.wrap((pos.0 + off.0, pos.1 + off.1)))
+            .collect()
+    }
+
+    // whether the angel escapes when it reaches pos, that is when a step from pos leaves the board
+    // through an open edge, on round outlines the edge is the side of the board the tile faces
+    pub fn is_exit(&self, pos: (i32, i32)) -> bool {
+        let size = self.size() as i32;
+        let center = size / 2;
+
+        self.topology.offsets(pos.0).iter().any(|off| {
+            let out = (pos.0 + off.0, pos.1 + off.1);
+            if self.wrap(out).is_some() {
+                return false;
+            }
+
+            if out.0 < 0 {
+                self.exits.top
+            } else if out.0 >= size {
+                self.exits.bottom
+            } else if out.1 < 0 {
+                self.exits.left
+            } else if out.1 >= size {
+                self.exits.right
+            } else if (pos.0 - center).abs() >= (pos.1 - center).abs() {
+                if pos.0 < center {
+                    self.exits.top
+                } else {
+                    self.exits.bottom
+                }
+            } else if pos.1 < center {
+                self.exits.left
+            } else {
+                self.exits.right
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outlines() {
+        let hex = Board::new(11, Topology::Hex, Outline::Rectangle, Exits::default());
+        assert_eq!(hex.neighbours((5, 5)).len(), 6);
+        assert_eq!(hex.neighbours((0, 0)).len(), 2);
+        assert!(hex.is_exit((0, 5)) && hex.is_exit((5, 10)));
+        assert!(!hex.is_exit((5, 5)) && !hex.is_exit((1, 1)));
+
+        let square = Board::new(11, Topology::Square4, Outline::Rectangle, Exits::default());
+        assert_eq!(square.neighbours((5, 5)).len(), 4);
+        let square = Board::new(11, Topology::Square8, Outline::Rectangle, Exits::default());
+        assert_eq!(square.neighbours((5, 5)).len(), 8);
+
+        // the corners are cut off, the tiles next to them are on the border
+        let circle = Board::new(11, Topology::Square4, Outline::Circle, Exits::default());
+        assert!(!circle.contains((0, 0)) && circle.contains((0, 5)) && circle.contains((5, 5)));
+        assert!(circle.is_exit((1, 2)));
+
+        let hexagon = Board::new(11, Topology::Hex, Outline::Hexagon, Exits::default());
+        assert!(hexagon.contains((5, 0)) && hexagon.contains((0, 3)) && !hexagon.contains((0, 0)));
+        assert!(hexagon
+            .neighbours((5, 5))
+            .iter()
+            .all(|&pos| hexagon.contains(pos)));
+
+        // only the top and bottom edges are left on a cylinder
+        let exits = Exits {
+            top: true,
+            bottom: false,
+            left: true,
+            right: true,
+        };
+        let cylinder = Board::new(11, Topology::Hex, Outline::Cylinder, exits);
+        assert_eq!(cylinder.neighbours((5, 0)).len(), 6);
+        assert!(cylinder.neighbours((5, 0)).contains(&(5, 10)));
+        assert!(!cylinder.is_exit((5, 0)) && !cylinder.is_exit((10, 5)));
+        assert!(cylinder.is_exit((0, 5)));
+
+        assert_eq!(Exits::parse(&exits.as_string()), Some(exits));
+        assert_eq!(Exits::parse("tx"), None);
+    }
+}
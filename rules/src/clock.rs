@@ -0,0 +1,105 @@
+use serde_derive::{Deserialize, Serialize};
+
+// shorter games are decided by the connection more than by the players
+pub const MIN_TIME_CONTROL: u32 = 5;
+pub const MAX_TIME_CONTROL: u32 = 3 * 60 * 60;
+pub const MAX_INCREMENT: u32 = 60;
+
+// how much time the players get to think, in seconds
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TimeControl {
+    Unlimited,
+    Total { seconds: u32, increment: u32 }, // for the whole game, increment is added after every turn
+    PerMove { seconds: u32 },               // for every turn, the time left over is lost
+}
+
+// time left on the clock of each player, in milliseconds
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Clocks {
+    pub devil: u64,
+    pub angel: u64,
+}
+
+impl TimeControl {
+    // "unlimited", "300+5" or "30/move"
+    pub fn as_string(&self) -> String {
+        match self {
+            TimeControl::Unlimited => "unlimited".to_string(),
+            TimeControl::Total { seconds, increment } => format!("{seconds}+{increment}"),
+            TimeControl::PerMove { seconds } => format!("{seconds}/move"),
+        }
+    }
+
+    // the increment can be left out
+    pub fn parse(time_control: &str) -> Option<TimeControl> {
+        if time_control == "unlimited" {
+            return Some(TimeControl::Unlimited);
+        }
+
+        if let Some(seconds) = time_control.strip_suffix("/move") {
+            return Some(TimeControl::PerMove {
+                seconds: seconds.parse().ok()?,
+            });
+        }
+
+        let (seconds, increment) = time_control.split_once('+').unwrap_or((time_control, "0"));
+
+        Some(TimeControl::Total {
+            seconds: seconds.parse().ok()?,
+            increment: increment.parse().ok()?,
+        })
+    }
+
+    // what the clocks show at the start of the game, None if there is no limit
+    pub fn start(&self) -> Option<Clocks> {
+        let seconds = match *self {
+            TimeControl::Unlimited => return None,
+            TimeControl::Total { seconds, .. } | TimeControl::PerMove { seconds } => seconds,
+        };
+
+        Some(Clocks {
+            devil: seconds as u64 * 1000,
+            angel: seconds as u64 * 1000,
+        })
+    }
+}
+
+impl Clocks {
+    pub fn get(&self, devil: bool) -> u64 {
+        if devil {
+            self.devil
+        } else {
+            self.angel
+        }
+    }
+
+    pub fn get_mut(&mut self, devil: bool) -> &mut u64 {
+        if devil {
+            &mut self.devil
+        } else {
+            &mut self.angel
+        }
+    }
+
+    // called when the turn goes from one player to the other
+    pub fn pass(&mut self, time_control: TimeControl, devil: bool) {
+        match time_control {
+            TimeControl::Unlimited => {}
+            TimeControl::Total { increment, .. } => {
+                *self.get_mut(devil) += increment as u64 * 1000;
+            }
+            TimeControl::PerMove { seconds } => *self.get_mut(!devil) = seconds as u64 * 1000,
+        }
+    }
+
+    // called when the player that just moved gets the turn again because the other one passed
+    pub fn keep(&mut self, time_control: TimeControl, devil: bool) {
+        match time_control {
+            TimeControl::Unlimited => {}
+            TimeControl::Total { increment, .. } => {
+                *self.get_mut(devil) += increment as u64 * 1000;
+            }
+            TimeControl::PerMove { seconds } => *self.get_mut(devil) = seconds as u64 * 1000,
+        }
+    }
+}
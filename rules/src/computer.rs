@@ -0,0 +1,309 @@
+use std::collections::VecDeque;
+
+use rand::{seq::SliceRandom, Rng};
+use serde_derive::{Deserialize, Serialize};
+
+use super::game::{GameState, Grid};
+
+// how well the computer plays the devil, the angel always takes the shortest way out
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ComputerLevel {
+    Easy,   // walls a random tile of the angel's shortest way out
+    Normal, // walls the tile of the shortest way out that sets the angel back the most
+    Hard,   // weighs every tile of every shortest way out, then builds as far ahead as it can
+}
+
+impl ComputerLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ComputerLevel::Easy => "easy",
+            ComputerLevel::Normal => "normal",
+            ComputerLevel::Hard => "hard",
+        }
+    }
+
+    pub fn parse(level: &str) -> Option<ComputerLevel> {
+        match level {
+            "easy" => Some(ComputerLevel::Easy),
+            "normal" => Some(ComputerLevel::Normal),
+            "hard" => Some(ComputerLevel::Hard),
+            _ => None,
+        }
+    }
+}
+
+impl GameState {
+    // whether the side to move is played by the computer
+    pub fn computer_turn(&self) -> bool {
+        if self.turn {
+            self.devil == 0
+        } else {
+            self.angel == 0
+        }
+    }
+
+    // the wall the computer devil places next, rng picks the wall of the easy devil, the ways out
+    // are measured in single steps, None if there is no tile left to build on
+    pub fn devil_move(&self, rng: &mut impl Rng) -> Option<(i32, i32)> {
+        let path: Vec<(i32, i32)> = self
+            .escape_path()
+            .into_iter()
+            .filter(|&pos| self.valid_devil_move(pos))
+            .collect();
+
+        let wall = match self.computer_level {
+            ComputerLevel::Easy => path.choose(rng).copied(),
+            // the last of equally good walls is the one closest to the exit, building ahead of the
+            // angel works better than building in its way
+            ComputerLevel::Normal => path
+                .iter()
+                .copied()
+                .max_by_key(|&pos| self.ways_out(&self.with_wall(pos)).0),
+            ComputerLevel::Hard => {
+                let steps = self.steps();
+                let now = self.ways_out(&self.grid);
+
+                // every tile of every shortest way out, the ones that change nothing aren't on any
+                self.tiles()
+                    .filter(|&pos| self.valid_devil_move(pos))
+                    .filter_map(|pos| {
+                        let after = self.ways_out(&self.with_wall(pos));
+                        (after != now).then_some((pos, after))
+                    })
+                    .max_by_key(|&(pos, (distance, ways))| {
+                        let ahead = steps[pos.0 as usize][pos.1 as usize].unwrap_or(0);
+                        (distance, ahead, u64::MAX - ways)
+                    })
+                    .map(|(pos, _)| pos)
+            }
+        };
+
+        wall.or_else(|| self.nearest_wall())
+    }
+
+    fn tiles(&self) -> impl Iterator<Item = (i32, i32)> {
+        let size = self.size as i32;
+
+        (0..size).flat_map(move |line| (0..size).map(move |column| (line, column)))
+    }
+
+    fn with_wall(&self, pos: (i32, i32)) -> Grid {
+        let mut grid = self.grid.clone();
+        grid[pos.0 as usize][pos.1 as usize] = true;
+
+        grid
+    }
+
+    // steps from the angel to every tile it can reach
+    fn steps(&self) -> Vec<Vec<Option<u32>>> {
+        let mut steps = vec![vec![None; self.size]; self.size];
+        let mut q = VecDeque::from([self.angel_pos]);
+
+        steps[self.angel_pos.0 as usize][self.angel_pos.1 as usize] = Some(0);
+
+        while let Some(pos) = q.pop_front() {
+            let distance = steps[pos.0 as usize][pos.1 as usize].unwrap();
+
+            for next in self.board.neighbours(pos) {
+                let (line, column) = (next.0 as usize, next.1 as usize);
+
+                if !self.grid[line][column] && steps[line][column].is_none() {
+                    steps[line][column] = Some(distance + 1);
+                    q.push_back(next);
+                }
+            }
+        }
+
+        steps
+    }
+
+    // (steps to the closest exit, number of shortest ways out) on grid, a trapped angel is
+    // infinitely far from the border
+    fn ways_out(&self, grid: &Grid) -> (u32, u64) {
+        let mut steps = vec![vec![None; self.size]; self.size];
+        let mut ways = vec![vec![0u64; self.size]; self.size];
+        let mut q = VecDeque::from([self.angel_pos]);
+        let mut closest = None;
+        let mut total = 0u64;
+
+        steps[self.angel_pos.0 as usize][self.angel_pos.1 as usize] = Some(0);
+        ways[self.angel_pos.0 as usize][self.angel_pos.1 as usize] = 1;
+
+        while let Some(pos) = q.pop_front() {
+            let distance = steps[pos.0 as usize][pos.1 as usize].unwrap();
+            let here = ways[pos.0 as usize][pos.1 as usize];
+
+            if closest.is_some_and(|closest| distance > closest) {
+                break;
+            }
+
+            if self.board.is_exit(pos) {
+                closest = Some(distance);
+                total = total.saturating_add(here);
+                continue;
+            }
+
+            for next in self.board.neighbours(pos) {
+                let (line, column) = (next.0 as usize, next.1 as usize);
+                if grid[line][column] {
+                    continue;
+                }
+
+                match steps[line][column] {
+                    None => {
+                        steps[line][column] = Some(distance + 1);
+                        ways[line][column] = here;
+                        q.push_back(next);
+                    }
+                    Some(steps) if steps == distance + 1 => {
+                        ways[line][column] = ways[line][column].saturating_add(here);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        (closest.unwrap_or(u32::MAX), total)
+    }
+
+    // the tiles of one of the shortest ways out, from the angel's next step to the exit, empty if
+    // the angel is trapped
+    fn escape_path(&self) -> Vec<(i32, i32)> {
+        let mut prev = vec![vec![None; self.size]; self.size];
+        let mut q = VecDeque::from([self.angel_pos]);
+
+        prev[self.angel_pos.0 as usize][self.angel_pos.1 as usize] = Some(self.angel_pos);
+
+        while let Some(pos) = q.pop_front() {
+            if self.board.is_exit(pos) {
+                let mut path = vec![];
+                let mut pos = pos;
+
+                while pos != self.angel_pos {
+                    path.push(pos);
+                    pos = prev[pos.0 as usize][pos.1 as usize].unwrap();
+                }
+
+                path.reverse();
+                return path;
+            }
+
+            for next in self.board.neighbours(pos) {
+                let (line, column) = (next.0 as usize, next.1 as usize);
+
+                if !self.grid[line][column] && prev[line][column].is_none() {
+                    prev[line][column] = Some(pos);
+                    q.push_back(next);
+                }
+            }
+        }
+
+        vec![]
+    }
+
+    // the free tile closest to the angel the devil can build on, for when no way out can be walled
+    fn nearest_wall(&self) -> Option<(i32, i32)> {
+        let steps = self.steps();
+
+        self.tiles()
+            .filter(|&pos| self.valid_devil_move(pos))
+            .min_by_key(|&pos| steps[pos.0 as usize][pos.1 as usize].unwrap_or(u32::MAX))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::game::GameSettings;
+
+    // plays a game of the computer devil against an angel that takes the first of the shortest
+    // ways out, true if the angel escaped
+    fn play(level: ComputerLevel, seed: u64) -> bool {
+        let settings = GameSettings {
+            wall_density: 10,
+            seed: Some(seed),
+            computer_level: level,
+            ..GameSettings::default()
+        };
+        let mut game = GameState::new(0, 0, &settings);
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+        loop {
+            let pos = if game.turn {
+                let pos = game.devil_move(&mut rng).unwrap();
+                assert!(game.valid_devil_move(pos));
+                pos
+            } else {
+                match game.escape_path().first() {
+                    Some(&pos) => pos,
+                    None => return false,
+                }
+            };
+
+            game.apply_move(pos);
+
+            if game.angel_won() {
+                return true;
+            }
+        }
+    }
+
+    #[test]
+    fn computer_devil() {
+        let mut game = GameState::new(0, 1, &GameSettings::default());
+        assert!(!game.computer_turn());
+        game.devil = 0;
+        assert!(game.computer_turn());
+        game.turn = false;
+        game.angel = 2;
+        assert!(!game.computer_turn());
+
+        // the angel in the middle of an empty board, the easy devil walls a tile of its way out,
+        // the normal one the border at the end of it and the hard one builds far ahead on another
+        let mut game = GameState::new(1, 0, &GameSettings::default());
+        game.grid = vec![vec![false; game.size]; game.size];
+        assert_eq!(game.angel_pos, (5, 5));
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        for (level, wall) in [
+            (ComputerLevel::Easy, (1, 5)),
+            (ComputerLevel::Normal, (0, 5)),
+            (ComputerLevel::Hard, (10, 6)),
+        ] {
+            game.computer_level = level;
+            assert_eq!(game.devil_move(&mut rng), Some(wall), "{level:?}");
+        }
+
+        // walls can't go next to the angel when it isn't allowed, with nothing else left the
+        // devil has nowhere to build
+        game.adjacent_walls = false;
+        let around = game.board.neighbours(game.angel_pos);
+        assert!(!around.contains(&game.devil_move(&mut rng).unwrap()));
+        for line in game.grid.iter_mut() {
+            line.fill(true);
+        }
+        for pos in around.iter().chain([&game.angel_pos]) {
+            game.grid[pos.0 as usize][pos.1 as usize] = false;
+        }
+        assert_eq!(game.devil_move(&mut rng), None);
+
+        // over enough boards a harder devil never lets more angels out than an easier one
+        let escaped = |level| (0..200).filter(|&seed| play(level, seed)).count();
+        let (easy, normal, hard) = (
+            escaped(ComputerLevel::Easy),
+            escaped(ComputerLevel::Normal),
+            escaped(ComputerLevel::Hard),
+        );
+        assert!(hard <= normal && normal <= easy, "{easy} {normal} {hard}");
+
+        for level in [
+            ComputerLevel::Easy,
+            ComputerLevel::Normal,
+            ComputerLevel::Hard,
+        ] {
+            assert_eq!(ComputerLevel::parse(level.as_str()), Some(level));
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use std::collections::VecDeque;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde_derive::{Deserialize, Serialize};
+
+use super::{
+    board::Board,
+    game::{GameState, Grid, MAX_WALL_DENSITY},
+};
+
+// boards tried before giving up on the difficulty, each one only takes a few breadth first searches
+const ATTEMPTS: u32 = 200;
+
+// how hard the board the angel starts on is, Any keeps the board as the walls fall
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Any,
+    Easy,
+    Normal,
+    Hard,
+}
+
+// the number of ways out that share no tile is also the number of walls the devil needs to trap
+// the angel where it stands, both are measured in single steps so a stronger angel only has it
+// easier
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BoardRating {
+    pub routes: u32,
+    pub distance: Option<u32>, // steps to the closest exit, None if there is no way out
+}
+
+impl Difficulty {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Difficulty::Any => "any",
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    pub fn parse(difficulty: &str) -> Option<Difficulty> {
+        match difficulty {
+            "any" => Some(Difficulty::Any),
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    // whether a board of this difficulty exists against walls_per_turn walls, the angel has to
+    // keep a way out more than the devil builds walls and it can't have more ways out than
+    // tiles around its start
+    pub fn reachable(&self, board: &Board, walls_per_turn: u32) -> bool {
+        let most = board.neighbours(GameState::angel_start(board.size())).len() as u32;
+
+        match self {
+            Difficulty::Any => true,
+            Difficulty::Hard => walls_per_turn < most,
+            Difficulty::Easy => walls_per_turn + 1 < most,
+            Difficulty::Normal => walls_per_turn + 2 < most,
+        }
+    }
+
+    // the board a game with this difficulty starts on, the scattered walls of Any always leave
+    // the angel's line and column free which leaves it at least four ways out, the other
+    // difficulties only keep the angel's own tile free and are picked by their rating instead
+    pub fn grid(&self, board: &Board, wall_density: u32, seed: u64) -> Grid {
+        if *self == Difficulty::Any {
+            return GameState::board_grid(board, wall_density, seed);
+        }
+
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let start = GameState::angel_start(board.size());
+
+        let mut grid = vec![vec![false; board.size()]; board.size()];
+
+        for (i, line) in grid.iter_mut().enumerate() {
+            for (j, item) in line.iter_mut().enumerate() {
+                let pos = (i as i32, j as i32);
+                *item = rng.gen_range(0..100) < wall_density && board.contains(pos) && pos != start;
+            }
+        }
+
+        grid
+    }
+
+    // the (grid, wall density, seed) of a board of this difficulty, the candidates are drawn from
+    // the given seed so a seeded lobby always plays the same board and the seed of the board that
+    // was picked generates it again, the density moves away from the lobby's when it can't give
+    // the board that is asked for, and if none of the candidates is rated as asked the closest
+    // fair one is played
+    pub fn generate(
+        &self,
+        board: &Board,
+        wall_density: u32,
+        seed: u64,
+        walls_per_turn: u32,
+    ) -> (Grid, u32, u64) {
+        if *self == Difficulty::Any {
+            return (self.grid(board, wall_density, seed), wall_density, seed);
+        }
+
+        let start = GameState::angel_start(board.size());
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut candidate = seed;
+        let mut closest: Option<(u32, Grid, u32, u64)> = None;
+
+        for attempt in 0..ATTEMPTS {
+            let step = attempt / 10;
+            let density = match self {
+                Difficulty::Easy => wall_density.saturating_sub(step),
+                Difficulty::Hard => (wall_density + step).min(MAX_WALL_DENSITY),
+                _ => wall_density,
+            };
+
+            let grid = self.grid(board, density, candidate);
+            let rating = BoardRating::new(board, &grid, start);
+
+            match rating.difficulty(board, start, walls_per_turn) {
+                Some(difficulty) if difficulty == *self => return (grid, density, candidate),
+                Some(_) => {
+                    let miss = self.target(board, walls_per_turn).abs_diff(rating.routes);
+                    if closest.as_ref().map_or(true, |closest| miss < closest.0) {
+                        closest = Some((miss, grid, density, candidate));
+                    }
+                }
+                None => {}
+            }
+
+            candidate = rng.gen();
+        }
+
+        match closest {
+            Some((_, grid, density, candidate)) => (grid, density, candidate),
+            // an empty board leaves every way out open, which is as fair as it gets
+            None => (self.grid(board, 0, seed), 0, seed),
+        }
+    }
+
+    // the ways out a board of this difficulty has at its best
+    fn target(&self, board: &Board, walls_per_turn: u32) -> u32 {
+        match self {
+            Difficulty::Hard => walls_per_turn + 1,
+            Difficulty::Normal => walls_per_turn + 2,
+            _ => board.neighbours(GameState::angel_start(board.size())).len() as u32,
+        }
+    }
+}
+
+impl BoardRating {
+    pub fn new(board: &Board, grid: &Grid, start: (i32, i32)) -> BoardRating {
+        BoardRating {
+            routes: BoardRating::routes(board, grid, start),
+            distance: BoardRating::distance(board, grid, start),
+        }
+    }
+
+    // None if the devil can trap the angel before its first move
+    pub fn difficulty(
+        &self,
+        board: &Board,
+        start: (i32, i32),
+        walls_per_turn: u32,
+    ) -> Option<Difficulty> {
+        // every tile around the start can lead to a way out of its own, no more
+        let most = board.neighbours(start).len() as u32;
+        let least = walls_per_turn + 1;
+
+        if self.distance.is_none() || self.routes < least {
+            None
+        } else if self.routes == least {
+            Some(Difficulty::Hard)
+        } else if self.routes == least + 1 && self.routes < most {
+            Some(Difficulty::Normal)
+        } else {
+            Some(Difficulty::Easy)
+        }
+    }
+
+    // the most ways out that don't share a tile, found as a maximum flow where every free tile
+    // can be crossed once, tile i is entered through node 2i and left through node 2i + 1
+    fn routes(board: &Board, grid: &Grid, start: (i32, i32)) -> u32 {
+        let size = board.size();
+        let sink = 2 * size * size;
+        let index = |pos: (i32, i32)| pos.0 as usize * size + pos.1 as usize;
+        let free = |pos: (i32, i32)| board.contains(pos) && !grid[pos.0 as usize][pos.1 as usize];
+
+        // the residual capacity of each edge, edges e and e ^ 1 go opposite ways
+        let mut to = vec![];
+        let mut capacity = vec![];
+        let mut edges = vec![vec![]; sink + 1];
+        let mut add_edge = |from: usize, dest: usize| {
+            edges[from].push(to.len());
+            to.push(dest);
+            capacity.push(1);
+            edges[dest].push(to.len());
+            to.push(from);
+            capacity.push(0);
+        };
+
+        for line in 0..size as i32 {
+            for column in 0..size as i32 {
+                let pos = (line, column);
+                if !free(pos) && pos != start {
+                    continue;
+                }
+
+                if pos != start {
+                    add_edge(2 * index(pos), 2 * index(pos) + 1);
+                }
+
+                if board.is_exit(pos) {
+                    add_edge(2 * index(pos) + 1, sink);
+                }
+
+                for next in board.neighbours(pos) {
+                    if free(next) && next != start {
+                        add_edge(2 * index(pos) + 1, 2 * index(next));
+                    }
+                }
+            }
+        }
+
+        let source = 2 * index(start) + 1;
+        let mut routes = 0;
+
+        loop {
+            // the edge each node was reached through
+            let mut prev = vec![None; sink + 1];
+            let mut q = VecDeque::from([source]);
+
+            while let Some(node) = q.pop_front() {
+                if node == sink {
+                    break;
+                }
+
+                for &edge in edges[node].iter() {
+                    let next = to[edge];
+                    if capacity[edge] > 0 && next != source && prev[next].is_none() {
+                        prev[next] = Some(edge);
+                        q.push_back(next);
+                    }
+                }
+            }
+
+            if prev[sink].is_none() {
+                return routes;
+            }
+
+            let mut node = sink;
+            while let Some(edge) = prev[node] {
+                capacity[edge] -= 1;
+                capacity[edge ^ 1] += 1;
+                node = to[edge ^ 1];
+            }
+
+            routes += 1;
+        }
+    }
+
+    fn distance(board: &Board, grid: &Grid, start: (i32, i32)) -> Option<u32> {
+        let size = board.size();
+        let mut steps = vec![vec![None; size]; size];
+        let mut q = VecDeque::from([start]);
+
+        steps[start.0 as usize][start.1 as usize] = Some(0);
+
+        while let Some(pos) = q.pop_front() {
+            let distance = steps[pos.0 as usize][pos.1 as usize].unwrap();
+
+            if board.is_exit(pos) {
+                return Some(distance);
+            }
+
+            for next in board.neighbours(pos) {
+                let (line, column) = (next.0 as usize, next.1 as usize);
+
+                if !grid[line][column] && steps[line][column].is_none() {
+                    steps[line][column] = Some(distance + 1);
+                    q.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{Exits, Outline, Topology};
+
+    #[test]
+    fn ratings() {
+        let board = Board::new(11, Topology::Hex, Outline::Rectangle, Exits::default());
+        let start = GameState::angel_start(11);
+        let mut grid = vec![vec![false; 11]; 11];
+
+        // on an empty board every step away from the start leads out on its own
+        let rating = BoardRating::new(&board, &grid, start);
+        assert_eq!(rating.routes, 6);
+        assert_eq!(rating.distance, Some(5));
+        assert_eq!(rating.difficulty(&board, start, 1), Some(Difficulty::Easy));
+
+        // walled in but for one tile, one wall traps the angel
+        for pos in board.neighbours(start).into_iter().skip(1) {
+            grid[pos.0 as usize][pos.1 as usize] = true;
+        }
+        let rating = BoardRating::new(&board, &grid, start);
+        assert_eq!(rating.routes, 1);
+        assert_eq!(rating.difficulty(&board, start, 1), None);
+
+        // two ways out are enough against a single wall, not against two
+        let pos = board.neighbours(start)[3];
+        grid[pos.0 as usize][pos.1 as usize] = false;
+        let rating = BoardRating::new(&board, &grid, start);
+        assert_eq!(rating.routes, 2);
+        assert_eq!(rating.difficulty(&board, start, 1), Some(Difficulty::Hard));
+        assert_eq!(rating.difficulty(&board, start, 2), None);
+
+        for difficulty in [
+            Difficulty::Any,
+            Difficulty::Easy,
+            Difficulty::Normal,
+            Difficulty::Hard,
+        ] {
+            assert_eq!(Difficulty::parse(difficulty.as_str()), Some(difficulty));
+        }
+    }
+
+    #[test]
+    fn generated_boards() {
+        for (size, density, walls) in [(15, 30, 1), (11, 12, 1), (21, 20, 2)] {
+            let board = Board::new(size, Topology::Hex, Outline::Rectangle, Exits::default());
+            let start = GameState::angel_start(size);
+
+            for difficulty in [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard] {
+                for seed in 0..10 {
+                    let (grid, picked_density, picked) =
+                        difficulty.generate(&board, density, seed, walls);
+
+                    // the devil never traps the angel before its first move
+                    let rating = BoardRating::new(&board, &grid, start);
+                    assert_eq!(rating.difficulty(&board, start, walls), Some(difficulty));
+                    assert!(rating.routes > walls);
+                    assert_ne!(picked_density, 0);
+
+                    // the board is generated again from the seed and density that were picked
+                    assert_eq!(grid, difficulty.grid(&board, picked_density, picked));
+                    assert_eq!(difficulty.generate(&board, density, seed, walls).2, picked);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reachable_difficulties() {
+        let hex = Board::new(15, Topology::Hex, Outline::Rectangle, Exits::default());
+        let square = Board::new(15, Topology::Square4, Outline::Rectangle, Exits::default());
+        let start = GameState::angel_start(15);
+
+        // six ways out at most on hex, four on square boards, and always one more than the walls
+        assert!(Difficulty::Normal.reachable(&hex, 3));
+        assert!(!Difficulty::Normal.reachable(&hex, 4));
+        assert!(Difficulty::Easy.reachable(&hex, 4));
+        assert!(!Difficulty::Easy.reachable(&hex, 5));
+        assert!(Difficulty::Hard.reachable(&hex, 5));
+        assert!(Difficulty::Normal.reachable(&square, 1));
+        assert!(!Difficulty::Normal.reachable(&square, 2));
+        assert!(!Difficulty::Hard.reachable(&square, 4));
+        assert!(Difficulty::Any.reachable(&square, 5));
+
+        for (board, walls) in [
+            (&square, 1),
+            (&square, 2),
+            (&square, 3),
+            (&hex, 4),
+            (&hex, 5),
+        ] {
+            for difficulty in [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard] {
+                if !difficulty.reachable(board, walls) {
+                    continue;
+                }
+
+                for seed in 0..5 {
+                    let (grid, _, _) = difficulty.generate(board, 20, seed, walls);
+                    let rating = BoardRating::new(board, &grid, start);
+                    assert_eq!(rating.difficulty(board, start, walls), Some(difficulty));
+                }
+            }
+        }
+
+        // a level the board can't give falls back on the closest fair board that was generated
+        let (grid, density, _) = Difficulty::Normal.generate(&hex, 20, 0, 4);
+        let rating = BoardRating::new(&hex, &grid, start);
+        assert!(rating.difficulty(&hex, start, 4).is_some());
+        assert_eq!(rating.routes, 6);
+        assert_ne!(density, 0);
+    }
+}
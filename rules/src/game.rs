@@ -0,0 +1,959 @@
+use std::{
+    collections::VecDeque,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde_derive::{Deserialize, Serialize};
+
+use super::{
+    board::{Board, Exits, Outline, Topology},
+    clock::{Clocks, TimeControl},
+    computer::ComputerLevel,
+    difficulty::Difficulty,
+};
+
+// smaller boards are over in a few moves, bigger ones don't fit on the screen
+pub const MIN_GRID_SIZE: usize = 7;
+pub const MAX_GRID_SIZE: usize = 31;
+// percentage of tiles that start blocked, past half the angel is usually trapped from the start
+pub const MAX_WALL_DENSITY: u32 = 50;
+// a stronger angel escapes any board the devil can build on
+pub const MAX_ANGEL_POWER: u32 = 5;
+// the angel of power 1 is trapped quickly when the devil places more than a couple of walls
+pub const MAX_WALLS_PER_TURN: u32 = 5;
+
+// position tuples have the following meaning .0 - line, .1 - column
+
+// grid[line][column] is whether the tile is blocked or not
+pub type Grid = Vec<Vec<bool>>;
+
+// chosen by the host of a lobby, used for every game started in it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameSettings {
+    pub grid_size: usize,
+    pub wall_density: u32, // percentage of tiles that start blocked
+    pub seed: Option<u64>, // the same seed always gives the same board, random for each game if None
+    pub angel_power: u32,  // how many steps the angel can take in a single move
+    pub angel_rule: AngelRule,
+    pub walls_per_turn: u32,
+    pub wall_budget: Option<u32>, // walls the devil can place in the whole game, no limit if None
+    pub adjacent_walls: bool,     // whether the devil can build right next to the angel
+    pub topology: Topology,
+    pub outline: Outline,
+    pub exits: Exits,
+    pub time_control: TimeControl,
+    pub roles: RoleAssignment,
+    pub swap_roles: bool,  // rematches swap the sides of the last game
+    pub match_games: u32,  // the games are played as a best of n match when more than 1
+    pub match_rated: bool, // a match is rated as a single result instead of game by game
+    pub difficulty: Difficulty,
+    pub computer_level: ComputerLevel, // how well the computer plays the devil
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AngelRule {
+    Walk, // every tile on the way has to be free
+    Jump, // the angel flies over walls, only the tile it lands on has to be free
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        GameSettings {
+            grid_size: 11,
+            wall_density: 12,
+            seed: None,
+            angel_power: 1,
+            angel_rule: AngelRule::Walk,
+            walls_per_turn: 1,
+            wall_budget: None,
+            adjacent_walls: true,
+            topology: Topology::Hex,
+            outline: Outline::Rectangle,
+            exits: Exits::default(),
+            time_control: TimeControl::Unlimited,
+            roles: RoleAssignment::HostDevil,
+            swap_roles: false,
+            match_games: 1,
+            match_rated: false,
+            difficulty: Difficulty::Any,
+            computer_level: ComputerLevel::Normal,
+        }
+    }
+}
+
+// which side the host and the other player of a lobby take, being the host doesn't tie a user to
+// either of them
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RoleAssignment {
+    HostDevil,
+    HostAngel,
+    CoinFlip, // thrown again for every game
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameState {
+    pub devil: u32, // id of the user that is the devil
+    pub angel: u32, // id of the user that is the nagel, if 0 it's the computer
+    pub angel_pos: (i32, i32),
+    pub turn: bool,      // false - angel, true - devil
+    pub walls_left: u32, // walls the devil places before the angel moves again
+    pub budget_left: Option<u32>,
+    pub size: usize,
+    pub wall_density: u32,
+    pub seed: u64, // the board can be generated again from the size, density, seed and difficulty
+    pub difficulty: Difficulty,
+    pub angel_power: u32,
+    pub angel_rule: AngelRule,
+    pub walls_per_turn: u32,
+    pub wall_budget: Option<u32>,
+    pub adjacent_walls: bool,
+    pub computer_level: ComputerLevel,
+    pub board: Board,
+    pub grid: Grid,
+    pub time_control: TimeControl,
+    pub clocks: Option<Clocks>, // None if there is no time limit
+
+    // kept only on the server so the game can be stored once it ends
+    #[serde(skip)]
+    pub initial_grid: Grid,
+    #[serde(skip)]
+    pub moves: Vec<GameMove>,
+    #[serde(skip)]
+    pub started_at: u64,
+    #[serde(skip)]
+    pub turn_started: Option<Instant>, // the clocks only run on the server
+    #[serde(skip)]
+    pub draw_offer: Option<Role>, // who offered a draw, the offer stands until the next move
+    #[serde(skip)]
+    pub takeback_request: Option<Role>, // who asked to take back their turn, until the next move
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Role {
+    Angel,
+    Devil,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum EndReason {
+    Escaped,      // the angel reached the border
+    Trapped,      // the angel has no path left to the border
+    Timeout,      // the player to move ran out of time
+    Resigned,     // the loser gave up
+    Disconnected, // the loser left the lobby during the game
+    Draw,         // both players agreed to a draw
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameMove {
+    pub role: Role,
+    pub pos: (i32, i32),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameUpdate {
+    pub win: (bool, bool), // (devil won, angel won)
+    pub turn: bool,        // who made the move
+    pub user_move: (i32, i32),
+    pub walls_left: u32, // the devil moves next if this isn't 0
+    pub clocks: Option<Clocks>,
+}
+
+// sent to the lobby once a game is over, however it ended
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameResult {
+    pub game_id: u32,         // the game in the history
+    pub winner: Option<Role>, // None for a draw
+    pub reason: EndReason,
+    pub moves: u32,             // walls and angel moves together
+    pub walls: u32,             // placed by the devil during the game
+    pub duration: u64,          // in seconds
+    pub clocks: Option<Clocks>, // the time the players had left
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Angel => "angel",
+            Role::Devil => "devil",
+        }
+    }
+
+    pub fn opponent(&self) -> Role {
+        match self {
+            Role::Angel => Role::Devil,
+            Role::Devil => Role::Angel,
+        }
+    }
+
+    pub fn parse(role: &str) -> Option<Role> {
+        match role {
+            "angel" => Some(Role::Angel),
+            "devil" => Some(Role::Devil),
+            _ => None,
+        }
+    }
+}
+
+impl AngelRule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AngelRule::Walk => "walk",
+            AngelRule::Jump => "jump",
+        }
+    }
+
+    pub fn parse(rule: &str) -> Option<AngelRule> {
+        match rule {
+            "walk" => Some(AngelRule::Walk),
+            "jump" => Some(AngelRule::Jump),
+            _ => None,
+        }
+    }
+}
+
+impl RoleAssignment {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RoleAssignment::HostDevil => "host-devil",
+            RoleAssignment::HostAngel => "host-angel",
+            RoleAssignment::CoinFlip => "coin-flip",
+        }
+    }
+
+    pub fn parse(roles: &str) -> Option<RoleAssignment> {
+        match roles {
+            "host-devil" => Some(RoleAssignment::HostDevil),
+            "host-angel" => Some(RoleAssignment::HostAngel),
+            "coin-flip" => Some(RoleAssignment::CoinFlip),
+            _ => None,
+        }
+    }
+
+    // the (angel, devil) of a game between the host and the player
+    pub fn assign(&self, host: u32, player: u32) -> (u32, u32) {
+        let host_devil = match self {
+            RoleAssignment::HostDevil => true,
+            RoleAssignment::HostAngel => false,
+            RoleAssignment::CoinFlip => rand::random(),
+        };
+
+        if host_devil {
+            (player, host)
+        } else {
+            (host, player)
+        }
+    }
+}
+
+impl EndReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EndReason::Escaped => "escaped",
+            EndReason::Trapped => "trapped",
+            EndReason::Timeout => "timeout",
+            EndReason::Resigned => "resigned",
+            EndReason::Disconnected => "disconnected",
+            EndReason::Draw => "draw",
+        }
+    }
+
+    pub fn parse(reason: &str) -> Option<EndReason> {
+        match reason {
+            "escaped" => Some(EndReason::Escaped),
+            "trapped" => Some(EndReason::Trapped),
+            "timeout" => Some(EndReason::Timeout),
+            "resigned" => Some(EndReason::Resigned),
+            "disconnected" => Some(EndReason::Disconnected),
+            "draw" => Some(EndReason::Draw),
+            _ => None,
+        }
+    }
+}
+
+// seconds since the unix epoch
+pub fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl GameState {
+    pub fn new(angel: u32, devil: u32, settings: &GameSettings) -> GameState {
+        let size = settings.grid_size;
+        let board = Board::new(size, settings.topology, settings.outline, settings.exits);
+
+        // the board that is played is stored with its own density and seed so it can be generated
+        // again, they differ from the settings when the first boards were too easy or too hard
+        let (grid, wall_density, seed) = settings.difficulty.generate(
+            &board,
+            settings.wall_density,
+            settings.seed.unwrap_or_else(rand::random),
+            settings.walls_per_turn,
+        );
+
+        let mut game = GameState {
+            devil,
+            angel,
+            angel_pos: GameState::angel_start(size),
+            turn: true,
+            walls_left: 0,
+            budget_left: settings.wall_budget,
+            size,
+            wall_density,
+            seed,
+            difficulty: settings.difficulty,
+            angel_power: settings.angel_power,
+            angel_rule: settings.angel_rule,
+            walls_per_turn: settings.walls_per_turn,
+            wall_budget: settings.wall_budget,
+            adjacent_walls: settings.adjacent_walls,
+            computer_level: settings.computer_level,
+            board,
+            initial_grid: grid.clone(),
+            grid,
+            time_control: settings.time_control,
+            clocks: settings.time_control.start(),
+            moves: vec![],
+            started_at: timestamp(),
+            turn_started: None,
+            draw_offer: None,
+            takeback_request: None,
+        };
+
+        game.walls_left = game.walls_for_turn();
+        game.turn = game.walls_left > 0;
+
+        game
+    }
+
+    fn walls_for_turn(&self) -> u32 {
+        match self.budget_left {
+            Some(budget) => budget.min(self.walls_per_turn),
+            None => self.walls_per_turn,
+        }
+    }
+
+    // called after every move, the devil keeps the turn until it placed all of its walls and
+    // once its budget runs out the angel moves on its own, a devil with no legal wall left passes
+    // the rest of its turn so the game can't get stuck
+    pub fn end_move(&mut self) {
+        let devil = self.turn;
+
+        if self.turn {
+            self.walls_left -= 1;
+            if let Some(budget) = self.budget_left.as_mut() {
+                *budget -= 1;
+            }
+        } else {
+            self.walls_left = self.walls_for_turn();
+        }
+
+        let passed = self.walls_left > 0 && !self.devil_can_build();
+        if passed {
+            self.walls_left = 0;
+        }
+
+        self.turn = self.walls_left > 0;
+
+        if let Some(clocks) = self.clocks.as_mut() {
+            if self.turn != devil {
+                clocks.pass(self.time_control, devil);
+            } else if passed {
+                clocks.keep(self.time_control, devil);
+            }
+        }
+    }
+
+    // whether any tile is left for the devil to wall, walls can't always go next to the angel
+    pub fn devil_can_build(&self) -> bool {
+        let size = self.size as i32;
+
+        (0..size).any(|line| (0..size).any(|column| self.valid_devil_move((line, column))))
+    }
+
+    // starts timing the player to move
+    pub fn start_clock(&mut self) {
+        self.turn_started = Some(Instant::now());
+    }
+
+    // time the player to move has left, None if there is no time limit
+    pub fn time_left(&self) -> Option<u64> {
+        let elapsed = self
+            .turn_started
+            .map_or(0, |started| started.elapsed().as_millis() as u64);
+
+        Some(self.clocks?.get(self.turn).saturating_sub(elapsed))
+    }
+
+    // takes the time spent so far off the clock of the player to move, the server calls it right
+    // before playing a move
+    pub fn stop_clock(&mut self) {
+        if let Some(left) = self.time_left() {
+            *self.clocks.as_mut().unwrap().get_mut(self.turn) = left;
+        }
+
+        self.start_clock();
+    }
+
+    // plays pos for whoever's turn it is, the move has to be checked beforehand, clients replay
+    // the moves they receive with it to stay in sync with the server
+    pub fn apply_move(&mut self, pos: (i32, i32)) {
+        let role = if self.turn { Role::Devil } else { Role::Angel };
+
+        match role {
+            Role::Devil => self.grid[pos.0 as usize][pos.1 as usize] = true,
+            Role::Angel => self.angel_pos = pos,
+        }
+
+        self.moves.push(GameMove { role, pos });
+        self.draw_offer = None;
+        self.takeback_request = None;
+        self.end_move();
+    }
+
+    // undoes the last turn of role along with the opponent's reply if there was one, returns false
+    // if role has nothing to take back, the clocks aren't rewound so the time spent stays spent
+    pub fn take_back(&mut self, role: Role) -> bool {
+        let mut moves = self.moves.clone();
+
+        // the devil's walls of a turn always come one after the other, the angel moves once
+        for turn in [role.opponent(), role] {
+            while let Some(last) = moves.last() {
+                if last.role != turn {
+                    break;
+                }
+
+                moves.pop();
+
+                if turn == Role::Angel {
+                    break;
+                }
+            }
+        }
+
+        if self.moves[moves.len()..].iter().all(|m| m.role != role) {
+            return false;
+        }
+
+        let clocks = self.clocks;
+
+        self.grid = self.initial_grid.clone();
+        self.angel_pos = GameState::angel_start(self.size);
+        self.budget_left = self.wall_budget;
+        self.walls_left = self.walls_for_turn();
+        self.turn = self.walls_left > 0;
+        self.moves.clear();
+
+        for game_move in moves {
+            self.apply_move(game_move.pos);
+        }
+
+        self.clocks = clocks;
+
+        // the player to move starts over with a full clock
+        if let (TimeControl::PerMove { seconds }, Some(clocks)) =
+            (self.time_control, self.clocks.as_mut())
+        {
+            *clocks.get_mut(self.turn) = seconds as u64 * 1000;
+        }
+
+        true
+    }
+
+    // the role the user plays in this game, None for spectators
+    pub fn role_of(&self, user_id: u32) -> Option<Role> {
+        if user_id == self.devil {
+            Some(Role::Devil)
+        } else if user_id == self.angel {
+            Some(Role::Angel)
+        } else {
+            None
+        }
+    }
+
+    // the outcome of the game along with its final statistics, only the server knows when the
+    // game started
+    pub fn result(&self, game_id: u32, winner: Option<Role>, reason: EndReason) -> GameResult {
+        GameResult {
+            game_id,
+            winner,
+            reason,
+            moves: self.moves.len() as u32,
+            walls: self
+                .moves
+                .iter()
+                .filter(|game_move| game_move.role == Role::Devil)
+                .count() as u32,
+            duration: timestamp().saturating_sub(self.started_at),
+            clocks: self.clocks,
+        }
+    }
+
+    // the generator is fixed so a seed gives the same board on every platform and version, the
+    // angel's line and column are always left free
+    pub fn generate_grid(size: usize, wall_density: u32, seed: u64) -> Grid {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let angel_pos = GameState::angel_start(size);
+
+        let mut grid = vec![vec![false; size]; size];
+
+        for (i, line) in grid.iter_mut().enumerate() {
+            for (j, item) in line.iter_mut().enumerate() {
+                if i as i32 != angel_pos.0 && j as i32 != angel_pos.1 {
+                    *item = rng.gen_range(0..100) < wall_density;
+                }
+            }
+        }
+
+        grid
+    }
+
+    // the tiles that aren't part of the board are left free so they can't be told from walls
+    pub fn board_grid(board: &Board, wall_density: u32, seed: u64) -> Grid {
+        let mut grid = GameState::generate_grid(board.size(), wall_density, seed);
+        for (line, tiles) in grid.iter_mut().zip(board.tiles.iter()) {
+            for (item, tile) in line.iter_mut().zip(tiles.iter()) {
+                *item &= *tile;
+            }
+        }
+
+        grid
+    }
+
+    // the angel always starts in the middle of the grid
+    pub fn angel_start(size: usize) -> (i32, i32) {
+        (size as i32 / 2, size as i32 / 2)
+    }
+
+    fn blocked(&self, pos: (i32, i32)) -> bool {
+        self.grid[pos.0 as usize][pos.1 as usize]
+    }
+
+    pub fn valid_devil_move(&self, pos: (i32, i32)) -> bool {
+        if !self.board.contains(pos) || self.blocked(pos) || pos == self.angel_pos {
+            return false;
+        }
+
+        self.adjacent_walls || !self.board.neighbours(self.angel_pos).contains(&pos)
+    }
+
+    pub fn valid_angel_move(&self, pos: (i32, i32)) -> bool {
+        self.angel_moves(self.angel_pos).contains(&pos)
+    }
+
+    // every free tile the angel can reach from pos in a single move, at most angel_power steps
+    // away
+    pub fn angel_moves(&self, pos: (i32, i32)) -> Vec<(i32, i32)> {
+        let mut seen = vec![vec![false; self.size]; self.size];
+        let mut moves = Vec::new();
+        let mut frontier = vec![pos];
+
+        seen[pos.0 as usize][pos.1 as usize] = true;
+
+        for _ in 0..self.angel_power {
+            let mut next = Vec::new();
+
+            for pos in frontier {
+                for new_pos in self.board.neighbours(pos) {
+                    if seen[new_pos.0 as usize][new_pos.1 as usize] {
+                        continue;
+                    }
+
+                    seen[new_pos.0 as usize][new_pos.1 as usize] = true;
+
+                    if !self.blocked(new_pos) {
+                        moves.push(new_pos);
+                        next.push(new_pos);
+                    } else if self.angel_rule == AngelRule::Jump {
+                        next.push(new_pos);
+                    }
+                }
+            }
+
+            frontier = next;
+        }
+
+        moves
+    }
+
+    pub fn angel_won(&self) -> bool {
+        self.board.is_exit(self.angel_pos)
+    }
+
+    // the first move of the shortest way out, moves being as long as the angel's power allows,
+    // None if the angel is trapped
+    pub fn find_path(&self) -> Option<(i32, i32)> {
+        // if the angel reached the border there is no point in finding a path
+        if self.board.is_exit(self.angel_pos) {
+            return Some(self.angel_pos);
+        }
+
+        let mut q = VecDeque::new();
+        // the tile each visited tile was reached from
+        let mut prev = vec![vec![None; self.size]; self.size];
+
+        q.push_back(self.angel_pos);
+        prev[self.angel_pos.0 as usize][self.angel_pos.1 as usize] = Some(self.angel_pos);
+
+        while let Some(pos) = q.pop_front() {
+            if self.board.is_exit(pos) {
+                // walk back to the move made from the angel's tile
+                let mut pos = pos;
+                loop {
+                    let from = prev[pos.0 as usize][pos.1 as usize].unwrap();
+                    if from == self.angel_pos {
+                        return Some(pos);
+                    }
+                    pos = from;
+                }
+            }
+
+            // try random directions
+            let mut moves = self.angel_moves(pos);
+            moves.shuffle(&mut rand::thread_rng());
+
+            for new_pos in moves {
+                if prev[new_pos.0 as usize][new_pos.1 as usize].is_none() {
+                    prev[new_pos.0 as usize][new_pos.1 as usize] = Some(pos);
+                    q.push_back(new_pos);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn board_sizes() {
+        for size in [MIN_GRID_SIZE, 20, MAX_GRID_SIZE] {
+            let settings = GameSettings {
+                grid_size: size,
+                ..Default::default()
+            };
+            let mut game = GameState::new(0, 1, &settings);
+            assert_eq!(game.grid.len(), size);
+            assert!(game.grid.iter().all(|line| line.len() == size));
+
+            let last = size as i32 - 1;
+            assert!(game.board.contains((last, last)));
+            assert!(!game.board.contains((last + 1, 0)));
+
+            // on an empty board the angel always has a way out
+            game.grid = vec![vec![false; size]; size];
+            let step = game.find_path().unwrap();
+            assert!(game.valid_angel_move(step));
+
+            game.angel_pos = (last, size as i32 / 2);
+            assert!(game.angel_won());
+        }
+    }
+
+    #[test]
+    fn seeded_boards() {
+        let settings = GameSettings {
+            grid_size: 15,
+            wall_density: 30,
+            seed: Some(42),
+            ..Default::default()
+        };
+        let game = GameState::new(0, 1, &settings);
+        assert_eq!(game.seed, 42);
+        assert_eq!(game.grid, GameState::new(2, 3, &settings).grid);
+        assert_eq!(game.grid, GameState::generate_grid(15, 30, 42));
+        assert_ne!(game.grid, GameState::generate_grid(15, 30, 43));
+
+        // the angel's line and column are never blocked, whatever the density
+        let grid = GameState::generate_grid(15, 100, 42);
+        assert!(grid[7].iter().all(|&blocked| !blocked));
+        assert!(grid.iter().all(|line| !line[7]));
+        assert_eq!(
+            grid.iter().flatten().filter(|&&blocked| blocked).count(),
+            14 * 14
+        );
+
+        let grid = GameState::generate_grid(15, 0, 42);
+        assert!(grid.iter().flatten().all(|&blocked| !blocked));
+    }
+
+    #[test]
+    fn angel_power() {
+        let settings = GameSettings {
+            angel_power: 2,
+            ..Default::default()
+        };
+        let mut game = GameState::new(0, 1, &settings);
+        game.grid = vec![vec![false; 11]; 11];
+
+        // two rings of tiles around the angel
+        assert_eq!(game.angel_moves(game.angel_pos).len(), 18);
+        assert!(game.valid_angel_move((3, 5)));
+        assert!(!game.valid_angel_move((2, 5)));
+        assert!(!game.valid_angel_move(game.angel_pos));
+
+        // walled in, the angel can only get out by jumping
+        for pos in game.board.neighbours(game.angel_pos) {
+            game.grid[pos.0 as usize][pos.1 as usize] = true;
+        }
+        assert!(game.angel_moves(game.angel_pos).is_empty());
+        assert!(game.find_path().is_none());
+
+        game.angel_rule = AngelRule::Jump;
+        assert_eq!(game.angel_moves(game.angel_pos).len(), 12);
+        assert!(game.valid_angel_move((3, 5)));
+        assert!(!game.valid_angel_move((4, 5)));
+
+        // the border is 5 steps away, so the shortest way out takes 3 moves
+        for _ in 0..3 {
+            assert!(!game.angel_won());
+            let step = game.find_path().unwrap();
+            assert!(game.valid_angel_move(step));
+            game.angel_pos = step;
+        }
+        assert!(game.angel_won());
+    }
+
+    #[test]
+    fn devil_walls() {
+        let settings = GameSettings {
+            walls_per_turn: 2,
+            wall_budget: Some(3),
+            adjacent_walls: false,
+            ..Default::default()
+        };
+        let mut game = GameState::new(0, 1, &settings);
+        game.grid = vec![vec![false; 11]; 11];
+
+        assert!(!game.valid_devil_move((4, 5)));
+        assert!(!game.valid_devil_move(game.angel_pos));
+        assert!(!game.valid_devil_move((11, 0)));
+        assert!(game.valid_devil_move((3, 5)));
+
+        // two walls, then the angel, then the last wall of the budget
+        let turns: Vec<_> = (0..6)
+            .map(|_| {
+                let turn = game.turn;
+                game.end_move();
+                (turn, game.walls_left)
+            })
+            .collect();
+        assert_eq!(
+            turns,
+            [
+                (true, 1),
+                (true, 0),
+                (false, 1),
+                (true, 0),
+                (false, 0),
+                (false, 0)
+            ]
+        );
+        assert_eq!(game.budget_left, Some(0));
+    }
+
+    #[test]
+    fn devil_passes() {
+        let settings = GameSettings {
+            walls_per_turn: 2,
+            adjacent_walls: false,
+            topology: Topology::Square4,
+            time_control: TimeControl::PerMove { seconds: 10 },
+            ..Default::default()
+        };
+        let mut game = GameState::new(0, 1, &settings);
+        let (line, column) = game.angel_pos;
+
+        // only the angel's tile, the one to its right and a last wall spot are left free
+        game.grid = vec![vec![true; 11]; 11];
+        for (line, column) in [(line, column), (line, column + 1), (line - 2, column)] {
+            game.grid[line as usize][column as usize] = false;
+        }
+        assert!(game.devil_can_build());
+
+        // the first wall leaves nothing to build on so the second one is passed
+        game.apply_move((line - 2, column));
+        assert!(!game.devil_can_build());
+        assert!(!game.turn);
+        assert_eq!(game.walls_left, 0);
+
+        // every free tile stays next to the angel, it keeps moving with a fresh clock
+        game.clocks.as_mut().unwrap().angel = 1000;
+        game.apply_move((line, column + 1));
+        assert!(!game.turn);
+        assert_eq!(game.walls_left, 0);
+        assert_eq!(game.clocks.unwrap().angel, 10_000);
+
+        // once a tile away from the angel is free the devil gets its turn back
+        game.grid[line as usize + 2][column as usize] = false;
+        game.apply_move((line, column));
+        assert!(game.turn);
+        assert_eq!(game.walls_left, 2);
+    }
+
+    #[test]
+    fn replayed_moves() {
+        let settings = GameSettings {
+            walls_per_turn: 2,
+            seed: Some(7),
+            ..Default::default()
+        };
+        let mut game = GameState::new(0, 1, &settings);
+        let mut replay = game.clone();
+        game.grid = vec![vec![false; 11]; 11];
+        replay.grid = game.grid.clone();
+
+        // the same moves bring both copies to the same state
+        for pos in [(2, 2), (3, 3), (4, 5)] {
+            let turn = game.turn;
+            game.apply_move(pos);
+            replay.apply_move(pos);
+            assert_eq!(game.moves.last().unwrap().role == Role::Devil, turn);
+        }
+
+        assert!(game.grid[2][2] && game.grid[3][3]);
+        assert_eq!(game.angel_pos, (4, 5));
+        assert_eq!(replay.grid, game.grid);
+        assert_eq!(replay.angel_pos, game.angel_pos);
+        assert!(replay.turn && replay.walls_left == 2);
+
+        // a draw offer lapses with the next move
+        game.draw_offer = Some(Role::Devil);
+        game.apply_move((6, 6));
+        assert_eq!(game.draw_offer, None);
+
+        assert_eq!(game.role_of(1), Some(Role::Devil));
+        assert_eq!(game.role_of(0), Some(Role::Angel));
+        assert_eq!(game.role_of(2), None);
+
+        let result = game.result(3, None, EndReason::Draw);
+        assert_eq!((result.game_id, result.winner), (3, None));
+        assert_eq!((result.moves, result.walls), (4, 3));
+    }
+
+    #[test]
+    fn takebacks() {
+        let settings = GameSettings {
+            walls_per_turn: 2,
+            time_control: TimeControl::PerMove { seconds: 10 },
+            ..Default::default()
+        };
+        let mut game = GameState::new(0, 1, &settings);
+        game.grid = vec![vec![false; 11]; 11];
+        game.initial_grid = game.grid.clone();
+        let start = game.clone();
+
+        // the angel has nothing to take back before its first move
+        assert!(!game.take_back(Role::Angel));
+
+        game.apply_move((2, 2));
+        game.apply_move((3, 3));
+        game.apply_move((4, 5));
+        game.apply_move((6, 6));
+
+        // the devil takes back its unfinished turn, the angel's move stays
+        game.takeback_request = Some(Role::Devil);
+        assert!(game.take_back(Role::Devil));
+        assert_eq!(game.takeback_request, None);
+        assert_eq!(game.moves.len(), 3);
+        assert!(!game.grid[6][6] && game.grid[3][3]);
+        assert!(game.turn && game.walls_left == 2);
+
+        // the angel's move goes along with the devil's reply
+        game.apply_move((6, 6));
+        assert!(game.take_back(Role::Angel));
+        assert_eq!(game.moves.len(), 2);
+        assert_eq!(game.angel_pos, start.angel_pos);
+        assert!(!game.turn && !game.grid[6][6]);
+        assert_eq!(game.clocks.unwrap().angel, 10_000);
+
+        // the devil's whole turn is taken back along with the angel's reply
+        game.apply_move((4, 5));
+        assert!(game.take_back(Role::Devil));
+        assert_eq!(game.grid, start.grid);
+        assert_eq!(game.angel_pos, start.angel_pos);
+        assert!(game.moves.is_empty() && game.turn && game.walls_left == 2);
+    }
+
+    #[test]
+    fn role_assignment() {
+        assert_eq!(RoleAssignment::HostDevil.assign(1, 2), (2, 1));
+        assert_eq!(RoleAssignment::HostAngel.assign(1, 2), (1, 2));
+
+        // the coin decides who is the devil, both still play
+        let (angel, devil) = RoleAssignment::CoinFlip.assign(1, 2);
+        assert_eq!(angel + devil, 3);
+
+        for roles in [
+            RoleAssignment::HostDevil,
+            RoleAssignment::HostAngel,
+            RoleAssignment::CoinFlip,
+        ] {
+            assert_eq!(RoleAssignment::parse(roles.as_str()), Some(roles));
+        }
+    }
+
+    #[test]
+    fn clocks() {
+        let settings = GameSettings {
+            walls_per_turn: 2,
+            time_control: TimeControl::Total {
+                seconds: 60,
+                increment: 5,
+            },
+            ..Default::default()
+        };
+        let mut game = GameState::new(0, 1, &settings);
+        assert_eq!(game.time_left(), Some(60_000));
+
+        // the increment comes once the devil placed both walls
+        game.end_move();
+        assert_eq!(game.clocks.unwrap().devil, 60_000);
+        game.end_move();
+        assert_eq!(game.clocks.unwrap().devil, 65_000);
+        game.end_move();
+        assert_eq!(game.clocks.unwrap().angel, 65_000);
+
+        game.time_control = TimeControl::PerMove { seconds: 10 };
+        game.clocks.as_mut().unwrap().devil = 1;
+        game.end_move();
+        game.end_move();
+        assert_eq!(game.clocks.unwrap().angel, 10_000);
+        assert_eq!(game.clocks.unwrap().devil, 1);
+        game.end_move();
+        assert_eq!(game.clocks.unwrap().devil, 10_000);
+
+        let game = GameState::new(0, 1, &GameSettings::default());
+        assert_eq!(game.clocks, None);
+        assert_eq!(game.time_left(), None);
+
+        for time_control in [
+            TimeControl::Unlimited,
+            TimeControl::Total {
+                seconds: 300,
+                increment: 0,
+            },
+            TimeControl::PerMove { seconds: 30 },
+        ] {
+            assert_eq!(
+                TimeControl::parse(&time_control.as_string()),
+                Some(time_control)
+            );
+        }
+        assert_eq!(
+            TimeControl::parse("300"),
+            Some(TimeControl::Total {
+                seconds: 300,
+                increment: 0
+            })
+        );
+        assert_eq!(TimeControl::parse("fast"), None);
+    }
+}
@@ -0,0 +1,18 @@
+// the rules of the game, shared by the server that enforces them and the clients that preview
+// moves without waiting for the server
+
+mod board;
+mod clock;
+mod computer;
+mod difficulty;
+mod game;
+mod series;
+#[cfg(feature = "sql")]
+mod sql;
+
+pub use board::*;
+pub use clock::*;
+pub use computer::*;
+pub use difficulty::*;
+pub use game::*;
+pub use series::*;
@@ -0,0 +1,152 @@
+use serde_derive::{Deserialize, Serialize};
+
+use super::game::{timestamp, Role};
+
+// longer matches keep a lobby busy for hours
+pub const MAX_MATCH_GAMES: u32 = 9;
+
+// a best of n match between two players, who swap sides after every game, it's over as soon as
+// one of them can't be caught up with anymore
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MatchState {
+    pub games: u32,           // the most games the match can take
+    pub players: (u32, u32),  // the first one plays the devil in the first game
+    pub wins: (u32, u32),     // of each player
+    pub draws: u32,           // worth half a win to both
+    pub game_ids: Vec<u32>,   // the games played so far, in order
+    pub rated: bool,          // rated as a single result rather than game by game
+    pub forfeit: Option<u32>, // the player that left before the match was over
+    pub abandoned: bool,      // the lobby was set up for other games before it was over
+    pub started_at: u64,
+}
+
+impl MatchState {
+    pub fn new(games: u32, devil: u32, angel: u32, rated: bool) -> MatchState {
+        MatchState {
+            games,
+            players: (devil, angel),
+            wins: (0, 0),
+            draws: 0,
+            game_ids: vec![],
+            rated,
+            forfeit: None,
+            abandoned: false,
+            started_at: timestamp(),
+        }
+    }
+
+    pub fn has_player(&self, id: u32) -> bool {
+        self.players.0 == id || self.players.1 == id
+    }
+
+    // the (angel, devil) of the next game
+    pub fn next_roles(&self) -> (u32, u32) {
+        if self.game_ids.len() % 2 == 0 {
+            (self.players.1, self.players.0)
+        } else {
+            (self.players.0, self.players.1)
+        }
+    }
+
+    // counts the game that just ended, (angel, devil) are the ones that played it
+    pub fn record(&mut self, game_id: u32, angel: u32, devil: u32, winner: Option<Role>) {
+        let winner = match winner {
+            Some(Role::Angel) => angel,
+            Some(Role::Devil) => devil,
+            None => 0,
+        };
+
+        if winner == 0 {
+            self.draws += 1;
+        } else if winner == self.players.0 {
+            self.wins.0 += 1;
+        } else {
+            self.wins.1 += 1;
+        }
+
+        self.game_ids.push(game_id);
+    }
+
+    // twice the score of each player, so draws don't need fractions
+    pub fn points(&self) -> (u32, u32) {
+        (2 * self.wins.0 + self.draws, 2 * self.wins.1 + self.draws)
+    }
+
+    pub fn is_over(&self) -> bool {
+        let left = self.games.saturating_sub(self.game_ids.len() as u32);
+        let (first, second) = self.points();
+
+        self.forfeit.is_some() || self.abandoned || left == 0 || first.abs_diff(second) > 2 * left
+    }
+
+    // None while the match goes on, if it ended in a tie or if nobody finished it
+    pub fn winner(&self) -> Option<u32> {
+        if let Some(id) = self.forfeit {
+            return Some(if id == self.players.0 {
+                self.players.1
+            } else {
+                self.players.0
+            });
+        }
+
+        if self.abandoned || !self.is_over() {
+            return None;
+        }
+
+        let (first, second) = self.points();
+        match first.cmp(&second) {
+            std::cmp::Ordering::Greater => Some(self.players.0),
+            std::cmp::Ordering::Less => Some(self.players.1),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn best_of_five() {
+        let mut series = MatchState::new(5, 1, 2, false);
+
+        // the sides swap after every game
+        assert_eq!(series.next_roles(), (2, 1));
+        series.record(10, 2, 1, Some(Role::Devil));
+        assert_eq!(series.next_roles(), (1, 2));
+        series.record(11, 1, 2, Some(Role::Angel));
+        assert_eq!(series.wins, (2, 0));
+        assert!(!series.is_over());
+
+        // 5 half points to 1 with two games left, the second player can still draw level
+        series.record(12, 2, 1, None);
+        assert!(!series.is_over());
+        assert_eq!(series.winner(), None);
+
+        series.record(13, 1, 2, Some(Role::Angel));
+        assert!(series.is_over());
+        assert_eq!(series.winner(), Some(1));
+        assert_eq!(series.game_ids, [10, 11, 12, 13]);
+
+        // all games played and level
+        let mut series = MatchState::new(2, 1, 2, false);
+        series.record(10, 2, 1, Some(Role::Angel));
+        series.record(11, 1, 2, Some(Role::Angel));
+        assert!(series.is_over());
+        assert_eq!(series.winner(), None);
+
+        // leaving gives the match away whatever the score
+        let mut series = MatchState::new(3, 1, 2, false);
+        series.record(10, 2, 1, Some(Role::Devil));
+        series.forfeit = Some(1);
+        assert!(series.is_over());
+        assert_eq!(series.winner(), Some(2));
+
+        // setting the lobby up for other games leaves it without a winner
+        let mut series = MatchState::new(3, 1, 2, false);
+        series.record(10, 2, 1, Some(Role::Devil));
+        series.abandoned = true;
+        assert!(series.is_over());
+        assert_eq!(series.winner(), None);
+    }
+}
@@ -0,0 +1,131 @@
+use rusqlite::{
+    types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef},
+    Result,
+};
+
+use super::{
+    AngelRule, ComputerLevel, Difficulty, EndReason, Exits, Outline, Role, RoleAssignment,
+    TimeControl, Topology,
+};
+
+// the enums are stored by name so the database stays readable and doesn't depend on their order
+
+impl FromSql for Role {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        Role::parse(value.as_str()?).ok_or(FromSqlError::InvalidType)
+    }
+}
+
+impl ToSql for Role {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+impl FromSql for AngelRule {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        AngelRule::parse(value.as_str()?).ok_or(FromSqlError::InvalidType)
+    }
+}
+
+impl ToSql for AngelRule {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+impl FromSql for Topology {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        Topology::parse(value.as_str()?).ok_or(FromSqlError::InvalidType)
+    }
+}
+
+impl ToSql for Topology {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+impl FromSql for Outline {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        Outline::parse(value.as_str()?).ok_or(FromSqlError::InvalidType)
+    }
+}
+
+impl ToSql for Outline {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+impl FromSql for Exits {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        Exits::parse(value.as_str()?).ok_or(FromSqlError::InvalidType)
+    }
+}
+
+impl ToSql for Exits {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(self.as_string().into())
+    }
+}
+
+impl FromSql for EndReason {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        EndReason::parse(value.as_str()?).ok_or(FromSqlError::InvalidType)
+    }
+}
+
+impl ToSql for EndReason {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+impl FromSql for RoleAssignment {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        RoleAssignment::parse(value.as_str()?).ok_or(FromSqlError::InvalidType)
+    }
+}
+
+impl ToSql for RoleAssignment {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+impl FromSql for TimeControl {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        TimeControl::parse(value.as_str()?).ok_or(FromSqlError::InvalidType)
+    }
+}
+
+impl ToSql for TimeControl {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(self.as_string().into())
+    }
+}
+
+impl FromSql for Difficulty {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        Difficulty::parse(value.as_str()?).ok_or(FromSqlError::InvalidType)
+    }
+}
+
+impl ToSql for Difficulty {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+impl FromSql for ComputerLevel {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        ComputerLevel::parse(value.as_str()?).ok_or(FromSqlError::InvalidType)
+    }
+}
+
+impl ToSql for ComputerLevel {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
@@ -0,0 +1,228 @@
+use rules::{timestamp, EndReason, GameMove, GameState, Role};
+use rusqlite::{params, Connection, Result};
+
+use super::{rate_game, ratings::RatingOps, users::UserOps, GameRecord, GameSummary};
+
+pub trait GameOps {
+    const ADD_GAME: &'static str;
+    const ADD_GAME_MOVE: &'static str;
+    const GET_GAME: &'static str;
+    const GET_GAME_MOVES: &'static str;
+    const GET_USER_GAMES: &'static str;
+
+    fn add_game(
+        &self,
+        lobby: (u16, &str),
+        game: &GameState,
+        winner: Option<Role>,
+        reason: EndReason,
+        rate: bool,
+    ) -> Result<u32>;
+    fn get_game(&self, id: u32) -> Result<GameRecord>;
+    fn get_user_games(&self, user_id: u32, start: u32, count: u32) -> Result<Vec<GameSummary>>;
+}
+
+impl GameOps for Connection {
+    const ADD_GAME: &'static str = "
+INSERT INTO game (
+    lobby_id, lobby_name, angel, devil, size, grid, angel_line, angel_column, winner, reason,
+    rated, started_at, ended_at, wall_density, seed, angel_power, angel_rule, walls_per_turn,
+    wall_budget, adjacent_walls, topology, outline, exits, time_control, difficulty
+)
+VALUES(
+    ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20,
+    ?21, ?22, ?23, ?24, ?25
+)";
+    const ADD_GAME_MOVE: &'static str =
+        "INSERT INTO game_move (game_id, ply, role, line, column) VALUES(?1, ?2, ?3, ?4, ?5)";
+    // the computer has no user, so its name is filled in here
+    const GET_GAME: &'static str = "
+SELECT g.id, g.lobby_id, g.lobby_name, g.angel, COALESCE(a.name, 'computer'), g.devil,
+    COALESCE(d.name, 'computer'), g.size, g.grid, g.angel_line, g.angel_column, g.winner,
+    g.reason, g.rated, g.started_at, g.ended_at, g.wall_density, g.seed, g.angel_power,
+    g.angel_rule, g.walls_per_turn, g.wall_budget, g.adjacent_walls, g.topology, g.outline,
+    g.exits, g.time_control, g.series_id, g.difficulty
+FROM game g
+LEFT JOIN user a ON a.id = g.angel
+LEFT JOIN user d ON d.id = g.devil
+WHERE g.id = ?1";
+    const GET_GAME_MOVES: &'static str =
+        "SELECT role, line, column FROM game_move WHERE game_id = ?1 ORDER BY ply";
+    const GET_USER_GAMES: &'static str = "
+SELECT g.id, g.angel, COALESCE(a.name, 'computer'), g.devil, COALESCE(d.name, 'computer'),
+    g.winner, g.reason, g.rated, g.series_id,
+    (SELECT COUNT(*) FROM game_move m WHERE m.game_id = g.id), g.started_at, g.ended_at
+FROM game g
+LEFT JOIN user a ON a.id = g.angel
+LEFT JOIN user d ON d.id = g.devil
+WHERE g.angel = ?1 OR g.devil = ?1
+ORDER BY g.id DESC
+LIMIT ?3 OFFSET ?2";
+
+    // the game, its moves and the rating changes are written in a single transaction
+    fn add_game(
+        &self,
+        lobby: (u16, &str),
+        game: &GameState,
+        winner: Option<Role>,
+        reason: EndReason,
+        rate: bool,
+    ) -> Result<u32> {
+        let tx = self.unchecked_transaction()?;
+
+        let grid: String = game
+            .initial_grid
+            .iter()
+            .flatten()
+            .map(|&blocked| if blocked { '1' } else { '0' })
+            .collect();
+
+        let angel_start = GameState::angel_start(game.size);
+
+        let rated = if rate && game.angel != 0 && game.devil != 0 && game.angel != game.devil {
+            let angel = tx.get_user_by_id(game.angel)?;
+            let devil = tx.get_user_by_id(game.devil)?;
+
+            match rate_game(&angel, &devil, winner) {
+                Some((angel_rating, devil_rating)) => {
+                    tx.set_ratings((angel.id, angel_rating), (devil.id, devil_rating))?;
+                    true
+                }
+                None => false,
+            }
+        } else {
+            false
+        };
+
+        tx.execute(
+            Self::ADD_GAME,
+            params![
+                lobby.0,
+                lobby.1,
+                game.angel,
+                game.devil,
+                game.size as u32,
+                grid,
+                angel_start.0,
+                angel_start.1,
+                winner,
+                reason,
+                rated,
+                game.started_at,
+                timestamp(),
+                game.wall_density,
+                game.seed as i64,
+                game.angel_power,
+                game.angel_rule,
+                game.walls_per_turn,
+                game.wall_budget,
+                game.adjacent_walls,
+                game.board.topology,
+                game.board.outline,
+                game.board.exits,
+                game.time_control,
+                game.difficulty,
+            ],
+        )?;
+
+        let id = tx.last_insert_rowid() as u32;
+
+        {
+            let mut stmt = tx.prepare(Self::ADD_GAME_MOVE)?;
+
+            for (ply, game_move) in game.moves.iter().enumerate() {
+                stmt.execute(params![
+                    id,
+                    ply as u32,
+                    game_move.role,
+                    game_move.pos.0,
+                    game_move.pos.1
+                ])?;
+            }
+        }
+
+        tx.commit()?;
+
+        Ok(id)
+    }
+
+    fn get_game(&self, id: u32) -> Result<GameRecord> {
+        let mut stmt = self.prepare(Self::GET_GAME_MOVES)?;
+
+        let moves = stmt
+            .query_map([id], |row| {
+                Ok(GameMove {
+                    role: row.get(0)?,
+                    pos: (row.get(1)?, row.get(2)?),
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut stmt = self.prepare(Self::GET_GAME)?;
+
+        stmt.query_row([id], |row| {
+            let size: usize = row.get(7)?;
+            let grid: String = row.get(8)?;
+
+            Ok(GameRecord {
+                id: row.get(0)?,
+                lobby_id: row.get(1)?,
+                lobby_name: row.get(2)?,
+                angel: row.get(3)?,
+                angel_name: row.get(4)?,
+                devil: row.get(5)?,
+                devil_name: row.get(6)?,
+                grid: grid
+                    .as_bytes()
+                    .chunks(size)
+                    .map(|line| line.iter().map(|&tile| tile == b'1').collect())
+                    .collect(),
+                angel_start: (row.get(9)?, row.get(10)?),
+                wall_density: row.get(16)?,
+                seed: row.get::<_, Option<i64>>(17)?.map(|seed| seed as u64),
+                difficulty: row.get(28)?,
+                angel_power: row.get(18)?,
+                angel_rule: row.get(19)?,
+                walls_per_turn: row.get(20)?,
+                wall_budget: row.get(21)?,
+                adjacent_walls: row.get(22)?,
+                topology: row.get(23)?,
+                outline: row.get(24)?,
+                exits: row.get(25)?,
+                time_control: row.get(26)?,
+                winner: row.get(11)?,
+                reason: row.get(12)?,
+                rated: row.get(13)?,
+                match_id: row.get(27)?,
+                moves,
+                started_at: row.get(14)?,
+                ended_at: row.get(15)?,
+            })
+        })
+    }
+
+    fn get_user_games(&self, user_id: u32, start: u32, count: u32) -> Result<Vec<GameSummary>> {
+        let mut stmt = self.prepare(Self::GET_USER_GAMES)?;
+
+        let games = stmt
+            .query_map(params![user_id, start, count], |row| {
+                Ok(GameSummary {
+                    id: row.get(0)?,
+                    angel: row.get(1)?,
+                    angel_name: row.get(2)?,
+                    devil: row.get(3)?,
+                    devil_name: row.get(4)?,
+                    winner: row.get(5)?,
+                    reason: row.get(6)?,
+                    rated: row.get(7)?,
+                    match_id: row.get(8)?,
+                    moves: row.get(9)?,
+                    started_at: row.get(10)?,
+                    ended_at: row.get(11)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(games)
+    }
+}
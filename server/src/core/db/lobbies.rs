@@ -0,0 +1,224 @@
+use rules::{GameMove, GameSettings, GameState, Grid, MatchState};
+use rusqlite::{params, Connection};
+
+use super::{LobbyRecord, Result};
+
+// the parts of a game that are kept only on the server aren't serialized with it, so they are
+// stored next to it
+type StoredGame = (GameState, Grid, Vec<GameMove>, u64);
+
+fn encode_game(game: &GameState) -> Result<Vec<u8>> {
+    Ok(bincode::serialize(&(
+        game,
+        &game.initial_grid,
+        &game.moves,
+        game.started_at,
+    ))?)
+}
+
+fn decode_game(buf: &[u8]) -> Result<GameState> {
+    let (mut game, initial_grid, moves, started_at): StoredGame = bincode::deserialize(buf)?;
+
+    game.initial_grid = initial_grid;
+    game.moves = moves;
+    game.started_at = started_at;
+
+    Ok(game)
+}
+
+pub trait LobbyOps {
+    const ADD_LOBBY: &'static str;
+    const SET_LOBBY_SETTINGS: &'static str;
+    const SET_LOBBY_GAME: &'static str;
+    const SET_LOBBY_MATCH: &'static str;
+    const REMOVE_LOBBY: &'static str;
+    const REMOVE_LOBBY_MESSAGES: &'static str;
+    const GET_LOBBIES: &'static str;
+
+    fn add_lobby(&self, id: u16, name: &str, settings: &GameSettings) -> Result<()>;
+    fn set_lobby_settings(&self, id: u16, settings: &GameSettings) -> Result<()>;
+    fn set_lobby_game(&self, id: u16, game: Option<&GameState>) -> Result<()>;
+    fn set_lobby_match(&self, id: u16, series: Option<&MatchState>) -> Result<()>;
+    fn remove_lobby(&self, id: u16) -> Result<()>;
+    fn get_lobbies(&self) -> Result<Vec<LobbyRecord>>;
+}
+
+impl LobbyOps for Connection {
+    const ADD_LOBBY: &'static str = "
+INSERT INTO lobby (
+    id, name, grid_size, wall_density, seed, angel_power, angel_rule, walls_per_turn, wall_budget,
+    adjacent_walls, topology, outline, exits, time_control, roles, swap_roles, match_games,
+    match_rated, difficulty, computer_level
+)
+VALUES(
+    ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20
+)";
+    const SET_LOBBY_SETTINGS: &'static str = "
+UPDATE lobby
+SET grid_size = ?2, wall_density = ?3, seed = ?4, angel_power = ?5, angel_rule = ?6,
+    walls_per_turn = ?7, wall_budget = ?8, adjacent_walls = ?9, topology = ?10, outline = ?11,
+    exits = ?12, time_control = ?13, roles = ?14, swap_roles = ?15, match_games = ?16,
+    match_rated = ?17, difficulty = ?18, computer_level = ?19
+WHERE id = ?1";
+    const SET_LOBBY_GAME: &'static str = "UPDATE lobby SET game = ?2 WHERE id = ?1";
+    const SET_LOBBY_MATCH: &'static str = "UPDATE lobby SET series = ?2 WHERE id = ?1";
+    const REMOVE_LOBBY: &'static str = "DELETE FROM lobby WHERE id = ?1";
+    // lobby ids are reused after a restart, so the chat can't outlive its lobby
+    const REMOVE_LOBBY_MESSAGES: &'static str = "DELETE FROM message WHERE lobby_id = ?1";
+    const GET_LOBBIES: &'static str = "
+SELECT id, name, grid_size, wall_density, seed, angel_power, angel_rule, walls_per_turn,
+    wall_budget, adjacent_walls, topology, outline, exits, time_control, roles, swap_roles,
+    match_games, match_rated, difficulty, computer_level, game, series
+FROM lobby
+ORDER BY id";
+
+    fn add_lobby(&self, id: u16, name: &str, settings: &GameSettings) -> Result<()> {
+        let mut stmt = self.prepare(Self::ADD_LOBBY)?;
+
+        stmt.execute(params![
+            id,
+            name,
+            settings.grid_size,
+            settings.wall_density,
+            settings.seed.map(|seed| seed as i64),
+            settings.angel_power,
+            settings.angel_rule,
+            settings.walls_per_turn,
+            settings.wall_budget,
+            settings.adjacent_walls,
+            settings.topology,
+            settings.outline,
+            settings.exits,
+            settings.time_control,
+            settings.roles,
+            settings.swap_roles,
+            settings.match_games,
+            settings.match_rated,
+            settings.difficulty,
+            settings.computer_level,
+        ])?;
+
+        Ok(())
+    }
+
+    fn set_lobby_settings(&self, id: u16, settings: &GameSettings) -> Result<()> {
+        let mut stmt = self.prepare(Self::SET_LOBBY_SETTINGS)?;
+
+        stmt.execute(params![
+            id,
+            settings.grid_size,
+            settings.wall_density,
+            settings.seed.map(|seed| seed as i64),
+            settings.angel_power,
+            settings.angel_rule,
+            settings.walls_per_turn,
+            settings.wall_budget,
+            settings.adjacent_walls,
+            settings.topology,
+            settings.outline,
+            settings.exits,
+            settings.time_control,
+            settings.roles,
+            settings.swap_roles,
+            settings.match_games,
+            settings.match_rated,
+            settings.difficulty,
+            settings.computer_level,
+        ])?;
+
+        Ok(())
+    }
+
+    fn set_lobby_game(&self, id: u16, game: Option<&GameState>) -> Result<()> {
+        let game = game.map(encode_game).transpose()?;
+
+        let mut stmt = self.prepare(Self::SET_LOBBY_GAME)?;
+
+        stmt.execute(params![id, game])?;
+
+        Ok(())
+    }
+
+    fn set_lobby_match(&self, id: u16, series: Option<&MatchState>) -> Result<()> {
+        let series = series.map(bincode::serialize).transpose()?;
+
+        let mut stmt = self.prepare(Self::SET_LOBBY_MATCH)?;
+
+        stmt.execute(params![id, series])?;
+
+        Ok(())
+    }
+
+    fn remove_lobby(&self, id: u16) -> Result<()> {
+        let tx = self.unchecked_transaction()?;
+
+        tx.execute(Self::REMOVE_LOBBY_MESSAGES, params![id])?;
+        tx.execute(Self::REMOVE_LOBBY, params![id])?;
+
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    fn get_lobbies(&self) -> Result<Vec<LobbyRecord>> {
+        let mut stmt = self.prepare(Self::GET_LOBBIES)?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, u16>(0)?,
+                    row.get::<_, String>(1)?,
+                    GameSettings {
+                        grid_size: row.get(2)?,
+                        wall_density: row.get(3)?,
+                        seed: row.get::<_, Option<i64>>(4)?.map(|seed| seed as u64),
+                        angel_power: row.get(5)?,
+                        angel_rule: row.get(6)?,
+                        walls_per_turn: row.get(7)?,
+                        wall_budget: row.get(8)?,
+                        adjacent_walls: row.get(9)?,
+                        topology: row.get(10)?,
+                        outline: row.get(11)?,
+                        exits: row.get(12)?,
+                        time_control: row.get(13)?,
+                        roles: row.get(14)?,
+                        swap_roles: row.get(15)?,
+                        match_games: row.get(16)?,
+                        match_rated: row.get(17)?,
+                        difficulty: row.get(18)?,
+                        computer_level: row.get(19)?,
+                    },
+                    row.get::<_, Option<Vec<u8>>>(20)?,
+                    row.get::<_, Option<Vec<u8>>>(21)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let lobbies = rows
+            .into_iter()
+            .map(|(id, name, settings, game, series)| LobbyRecord {
+                id,
+                name,
+                settings,
+                // games saved by an older version can't be decoded, the lobby is restored
+                // without them rather than not at all
+                game: game.and_then(|game| match decode_game(&game) {
+                    Ok(game) => Some(game),
+                    Err(e) => {
+                        println!("couldn't restore the game of lobby {id}: {e:?}");
+                        None
+                    }
+                }),
+                series: series.and_then(|series| match bincode::deserialize(&series) {
+                    Ok(series) => Some(series),
+                    Err(e) => {
+                        println!("couldn't restore the match of lobby {id}: {e:?}");
+                        None
+                    }
+                }),
+            })
+            .collect();
+
+        Ok(lobbies)
+    }
+}
@@ -0,0 +1,119 @@
+use rules::{timestamp, MatchState};
+use rusqlite::{params, Connection, Result};
+
+use super::{rate_match, ratings::RatingOps, users::UserOps, MatchRecord};
+
+pub trait MatchOps {
+    const ADD_MATCH: &'static str;
+    const SET_GAME_MATCH: &'static str;
+    const GET_MATCH: &'static str;
+    const GET_MATCH_GAMES: &'static str;
+
+    fn add_match(&self, lobby: (u16, &str), series: &MatchState) -> Result<u32>;
+    fn get_match(&self, id: u32) -> Result<MatchRecord>;
+}
+
+impl MatchOps for Connection {
+    const ADD_MATCH: &'static str = "
+INSERT INTO series (
+    lobby_id, lobby_name, first, second, games, first_wins, second_wins, draws, winner, forfeit,
+    abandoned, rated, started_at, ended_at
+)
+VALUES(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)";
+    const SET_GAME_MATCH: &'static str = "UPDATE game SET series_id = ?2 WHERE id = ?1";
+    const GET_MATCH: &'static str = "
+SELECT s.id, s.lobby_id, s.lobby_name, s.first, f.name, s.second, n.name, s.games, s.first_wins,
+    s.second_wins, s.draws, s.winner, s.forfeit, s.abandoned, s.rated, s.started_at, s.ended_at
+FROM series s
+JOIN user f ON f.id = s.first
+JOIN user n ON n.id = s.second
+WHERE s.id = ?1";
+    const GET_MATCH_GAMES: &'static str = "SELECT id FROM game WHERE series_id = ?1 ORDER BY id";
+
+    // the match, the links from its games and the rating changes are written in a single
+    // transaction
+    fn add_match(&self, lobby: (u16, &str), series: &MatchState) -> Result<u32> {
+        let tx = self.unchecked_transaction()?;
+
+        let rated = if series.rated {
+            let first = tx.get_user_by_id(series.players.0)?;
+            let second = tx.get_user_by_id(series.players.1)?;
+
+            match rate_match(&first, &second, series) {
+                Some((first_ratings, second_ratings)) => {
+                    // the angel of one is rated against the devil of the other
+                    tx.set_ratings((first.id, first_ratings.0), (second.id, second_ratings.1))?;
+                    tx.set_ratings((second.id, second_ratings.0), (first.id, first_ratings.1))?;
+                    true
+                }
+                None => false,
+            }
+        } else {
+            false
+        };
+
+        tx.execute(
+            Self::ADD_MATCH,
+            params![
+                lobby.0,
+                lobby.1,
+                series.players.0,
+                series.players.1,
+                series.games,
+                series.wins.0,
+                series.wins.1,
+                series.draws,
+                series.winner(),
+                series.forfeit,
+                series.abandoned,
+                rated,
+                series.started_at,
+                timestamp(),
+            ],
+        )?;
+
+        let id = tx.last_insert_rowid() as u32;
+
+        {
+            let mut stmt = tx.prepare(Self::SET_GAME_MATCH)?;
+
+            for game_id in series.game_ids.iter() {
+                stmt.execute(params![game_id, id])?;
+            }
+        }
+
+        tx.commit()?;
+
+        Ok(id)
+    }
+
+    fn get_match(&self, id: u32) -> Result<MatchRecord> {
+        let mut stmt = self.prepare(Self::GET_MATCH_GAMES)?;
+
+        let game_ids = stmt
+            .query_map([id], |row| row.get(0))?
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut stmt = self.prepare(Self::GET_MATCH)?;
+
+        stmt.query_row([id], |row| {
+            Ok(MatchRecord {
+                id: row.get(0)?,
+                lobby_id: row.get(1)?,
+                lobby_name: row.get(2)?,
+                players: (row.get(3)?, row.get(5)?),
+                names: (row.get(4)?, row.get(6)?),
+                games: row.get(7)?,
+                wins: (row.get(8)?, row.get(9)?),
+                draws: row.get(10)?,
+                winner: row.get(11)?,
+                forfeit: row.get(12)?,
+                abandoned: row.get(13)?,
+                rated: row.get(14)?,
+                game_ids,
+                started_at: row.get(15)?,
+                ended_at: row.get(16)?,
+            })
+        })
+    }
+}
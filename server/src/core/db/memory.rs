@@ -0,0 +1,660 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use rules::{timestamp, EndReason, GameSettings, GameState, MatchState, Role};
+
+use super::{
+    rate_game, rate_match, ChatMessage, GameRecord, GameSummary, Leaderboard, LeaderboardEntry,
+    LobbyRecord, MatchRecord, ProfileStats, Result, RoleStats, Storage, StorageError, User,
+    RECENT_GAMES, SESSION_TTL,
+};
+
+// keeps everything in memory and loses it when the server stops, meant for throwaway servers and
+// tests, behaves the same as the sqlite storage
+#[derive(Default)]
+pub struct MemoryStorage {
+    tables: Mutex<Tables>,
+}
+
+#[derive(Default)]
+struct Tables {
+    users: Vec<User>,                      // a user's id is its index + 1
+    sessions: HashMap<String, (u32, u64)>, // token -> (user id, expires at)
+    games: Vec<GameRecord>,                // a game's id is its index + 1, names are filled on read
+    matches: Vec<MatchRecord>,             // same as games
+    lobbies: BTreeMap<u16, LobbyRecord>,
+    messages: Vec<(u16, ChatMessage)>, // (lobby id, message), names are filled on read
+    last_message_id: u32,              // ids aren't reused once a lobby's messages are removed
+}
+
+impl Tables {
+    fn user(&self, id: u32) -> Option<&User> {
+        self.users.get(id.checked_sub(1)? as usize)
+    }
+
+    fn user_mut(&mut self, id: u32) -> Option<&mut User> {
+        self.users.get_mut(id.checked_sub(1)? as usize)
+    }
+
+    // the computer has no user
+    fn name(&self, id: u32) -> String {
+        match self.user(id) {
+            Some(user) => user.name.clone(),
+            None => "computer".to_string(),
+        }
+    }
+
+    fn user_games(&self, user_id: u32) -> impl Iterator<Item = &GameRecord> {
+        self.games
+            .iter()
+            .rev()
+            .filter(move |game| game.angel == user_id || game.devil == user_id)
+    }
+
+    fn summary(&self, game: &GameRecord) -> GameSummary {
+        GameSummary {
+            id: game.id,
+            angel: game.angel,
+            angel_name: self.name(game.angel),
+            devil: game.devil,
+            devil_name: self.name(game.devil),
+            winner: game.winner,
+            reason: game.reason,
+            rated: game.rated,
+            match_id: game.match_id,
+            moves: game.moves.len() as u32,
+            started_at: game.started_at,
+            ended_at: game.ended_at,
+        }
+    }
+
+    fn get_user_games(&self, user_id: u32, start: u32, count: u32) -> Vec<GameSummary> {
+        self.user_games(user_id)
+            .skip(start as usize)
+            .take(count as usize)
+            .map(|game| self.summary(game))
+            .collect()
+    }
+
+    fn role_stats(&self, user_id: u32, role: Role) -> RoleStats {
+        let mut stats = RoleStats {
+            games: 0,
+            wins: 0,
+            losses: 0,
+        };
+
+        let games = self.games.iter().filter(|game| match role {
+            Role::Angel => game.angel == user_id,
+            Role::Devil => game.devil == user_id,
+        });
+
+        for game in games {
+            stats.games += 1;
+
+            match game.winner {
+                Some(winner) if winner == role => stats.wins += 1,
+                Some(_) => stats.losses += 1,
+                None => {}
+            }
+        }
+
+        stats
+    }
+
+    fn is_account(&self, name: &str) -> bool {
+        self.users
+            .iter()
+            .any(|user| !user.is_guest() && user.name.eq_ignore_ascii_case(name))
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn get_user_by_id(&self, id: u32) -> Result<User> {
+        let tables = self.tables.lock().unwrap();
+
+        tables.user(id).cloned().ok_or(StorageError::NotFound)
+    }
+
+    fn get_user_by_key(&self, name: &str, addr: &str) -> Result<User> {
+        let tables = self.tables.lock().unwrap();
+
+        tables
+            .users
+            .iter()
+            .find(|user| user.is_guest() && user.name == name && user.addr == addr)
+            .cloned()
+            .ok_or(StorageError::NotFound)
+    }
+
+    fn add_user(&self, name: &str, addr: &str) -> Result<()> {
+        let mut tables = self.tables.lock().unwrap();
+
+        if tables
+            .users
+            .iter()
+            .any(|user| user.is_guest() && user.name == name && user.addr == addr)
+        {
+            return Err(StorageError::AlreadyExists);
+        }
+
+        let id = tables.users.len() as u32 + 1;
+
+        tables.users.push(User {
+            id,
+            name: name.to_string(),
+            addr: addr.to_string(),
+            angel_rating: None,
+            connected: 1,
+            password: None,
+            devil_rating: None,
+        });
+
+        Ok(())
+    }
+
+    fn change_user_name(&self, id: u32, name: &str) -> Result<()> {
+        let mut tables = self.tables.lock().unwrap();
+
+        if let Some(user) = tables.user_mut(id) {
+            user.name = name.to_string();
+        }
+
+        Ok(())
+    }
+
+    fn toggle_connected(&self, id: u32) -> Result<()> {
+        let mut tables = self.tables.lock().unwrap();
+
+        if let Some(user) = tables.user_mut(id) {
+            user.connected = u32::from(user.connected == 0);
+        }
+
+        Ok(())
+    }
+
+    fn name_taken(&self, id: u32, name: &str) -> Result<bool> {
+        let tables = self.tables.lock().unwrap();
+
+        let now = timestamp();
+        let live = |user_id| {
+            tables
+                .sessions
+                .values()
+                .any(|&(owner, expires_at)| owner == user_id && expires_at > now)
+        };
+
+        Ok(tables.users.iter().any(|user| {
+            user.id != id
+                && user.name.eq_ignore_ascii_case(name)
+                && (!user.is_guest() || live(user.id))
+        }))
+    }
+
+    fn get_account(&self, name: &str) -> Result<User> {
+        let tables = self.tables.lock().unwrap();
+
+        tables
+            .users
+            .iter()
+            .find(|user| !user.is_guest() && user.name.eq_ignore_ascii_case(name))
+            .cloned()
+            .ok_or(StorageError::NotFound)
+    }
+
+    fn add_account(&self, name: &str, addr: &str, password: &str) -> Result<u32> {
+        let mut tables = self.tables.lock().unwrap();
+
+        if tables.is_account(name) {
+            return Err(StorageError::AlreadyExists);
+        }
+
+        let id = tables.users.len() as u32 + 1;
+
+        tables.users.push(User {
+            id,
+            name: name.to_string(),
+            addr: addr.to_string(),
+            angel_rating: None,
+            connected: 1,
+            password: Some(password.to_string()),
+            devil_rating: None,
+        });
+
+        Ok(id)
+    }
+
+    fn log_in(&self, id: u32, addr: &str) -> Result<()> {
+        let mut tables = self.tables.lock().unwrap();
+
+        if let Some(user) = tables.user_mut(id) {
+            user.addr = addr.to_string();
+            user.connected = 1;
+        }
+
+        Ok(())
+    }
+
+    fn add_session(&self, token: &str, user_id: u32) -> Result<()> {
+        let mut tables = self.tables.lock().unwrap();
+
+        if tables.sessions.contains_key(token) {
+            return Err(StorageError::AlreadyExists);
+        }
+
+        tables.sessions.insert(
+            token.to_string(),
+            (user_id, timestamp() + SESSION_TTL as u64),
+        );
+
+        Ok(())
+    }
+
+    fn get_session_user(&self, token: &str) -> Result<Option<User>> {
+        let mut tables = self.tables.lock().unwrap();
+
+        let now = timestamp();
+
+        let user_id = match tables.sessions.get_mut(token) {
+            Some((user_id, expires_at)) if *expires_at > now => {
+                *expires_at = now + SESSION_TTL as u64;
+                *user_id
+            }
+            Some(_) => {
+                tables.sessions.remove(token);
+                return Ok(None);
+            }
+            None => return Err(StorageError::NotFound),
+        };
+
+        tables
+            .user(user_id)
+            .cloned()
+            .map(Some)
+            .ok_or(StorageError::NotFound)
+    }
+
+    fn revoke_session(&self, token: &str) -> Result<()> {
+        let mut tables = self.tables.lock().unwrap();
+
+        tables.sessions.remove(token);
+
+        Ok(())
+    }
+
+    fn has_sessions(&self, user_id: u32) -> Result<bool> {
+        let tables = self.tables.lock().unwrap();
+
+        Ok(tables.sessions.values().any(|(id, _)| *id == user_id))
+    }
+
+    fn add_game(
+        &self,
+        lobby: (u16, &str),
+        game: &GameState,
+        winner: Option<Role>,
+        reason: EndReason,
+        rate: bool,
+    ) -> Result<u32> {
+        let mut tables = self.tables.lock().unwrap();
+
+        let ratings = if rate && game.angel != 0 && game.devil != 0 && game.angel != game.devil {
+            let angel = tables.user(game.angel).ok_or(StorageError::NotFound)?;
+            let devil = tables.user(game.devil).ok_or(StorageError::NotFound)?;
+
+            rate_game(angel, devil, winner)
+        } else {
+            None
+        };
+
+        if let Some((angel_rating, devil_rating)) = ratings {
+            if let Some(angel) = tables.user_mut(game.angel) {
+                angel.angel_rating = Some(angel_rating);
+            }
+            if let Some(devil) = tables.user_mut(game.devil) {
+                devil.devil_rating = Some(devil_rating);
+            }
+        }
+
+        let id = tables.games.len() as u32 + 1;
+
+        tables.games.push(GameRecord {
+            id,
+            lobby_id: lobby.0,
+            lobby_name: lobby.1.to_string(),
+            angel: game.angel,
+            angel_name: String::new(),
+            devil: game.devil,
+            devil_name: String::new(),
+            grid: game.initial_grid.clone(),
+            angel_start: GameState::angel_start(game.size),
+            wall_density: Some(game.wall_density),
+            seed: Some(game.seed),
+            difficulty: game.difficulty,
+            angel_power: game.angel_power,
+            angel_rule: game.angel_rule,
+            walls_per_turn: game.walls_per_turn,
+            wall_budget: game.wall_budget,
+            adjacent_walls: game.adjacent_walls,
+            topology: game.board.topology,
+            outline: game.board.outline,
+            exits: game.board.exits,
+            time_control: game.time_control,
+            winner,
+            reason,
+            rated: ratings.is_some(),
+            match_id: None,
+            moves: game.moves.clone(),
+            started_at: game.started_at,
+            ended_at: timestamp(),
+        });
+
+        Ok(id)
+    }
+
+    fn get_game(&self, id: u32) -> Result<GameRecord> {
+        let tables = self.tables.lock().unwrap();
+
+        let mut game = id
+            .checked_sub(1)
+            .and_then(|index| tables.games.get(index as usize))
+            .cloned()
+            .ok_or(StorageError::NotFound)?;
+
+        game.angel_name = tables.name(game.angel);
+        game.devil_name = tables.name(game.devil);
+
+        Ok(game)
+    }
+
+    fn get_user_games(&self, user_id: u32, start: u32, count: u32) -> Result<Vec<GameSummary>> {
+        let tables = self.tables.lock().unwrap();
+
+        Ok(tables.get_user_games(user_id, start, count))
+    }
+
+    fn get_leaderboard(
+        &self,
+        role: Role,
+        user_id: u32,
+        start: u32,
+        count: u32,
+    ) -> Result<Leaderboard> {
+        let tables = self.tables.lock().unwrap();
+
+        let mut rated: Vec<(u32, &User)> = tables
+            .users
+            .iter()
+            .filter_map(|user| {
+                let rating = match role {
+                    Role::Angel => user.angel_rating,
+                    Role::Devil => user.devil_rating,
+                };
+
+                rating.map(|rating| (rating, user))
+            })
+            .collect();
+
+        rated.sort_by_key(|(rating, user)| (Reverse(*rating), user.id));
+
+        // players with the same rating share the same rank
+        let mut ranking: Vec<LeaderboardEntry> = Vec::with_capacity(rated.len());
+
+        for (index, (rating, user)) in rated.into_iter().enumerate() {
+            let rank = match ranking.last() {
+                Some(last) if last.rating == rating => last.rank,
+                _ => index as u32 + 1,
+            };
+
+            ranking.push(LeaderboardEntry {
+                rank,
+                id: user.id,
+                name: user.name.clone(),
+                rating,
+            });
+        }
+
+        let user = ranking.iter().find(|entry| entry.id == user_id).cloned();
+
+        let entries = ranking
+            .into_iter()
+            .skip(start as usize)
+            .take(count as usize)
+            .collect();
+
+        Ok(Leaderboard { entries, user })
+    }
+
+    fn get_last_game_id(&self, user_id: u32) -> Result<Option<u32>> {
+        let tables = self.tables.lock().unwrap();
+
+        let last_game = tables.user_games(user_id).next().map(|game| game.id);
+
+        Ok(last_game)
+    }
+
+    fn get_profile_stats(&self, user_id: u32) -> Result<ProfileStats> {
+        let tables = self.tables.lock().unwrap();
+
+        let games = tables.user_games(user_id).count() as u32;
+        let moves: usize = tables
+            .user_games(user_id)
+            .map(|game| game.moves.len())
+            .sum();
+
+        let longest_survival = tables
+            .user_games(user_id)
+            .filter(|game| game.angel == user_id)
+            .map(|game| {
+                game.moves
+                    .iter()
+                    .filter(|game_move| game_move.role == Role::Angel)
+                    .count() as u32
+            })
+            .max()
+            .unwrap_or(0);
+
+        Ok(ProfileStats {
+            games,
+            angel: tables.role_stats(user_id, Role::Angel),
+            devil: tables.role_stats(user_id, Role::Devil),
+            average_moves: if games > 0 {
+                moves as f32 / games as f32
+            } else {
+                0.0
+            },
+            longest_survival,
+            recent: tables.get_user_games(user_id, 0, RECENT_GAMES),
+        })
+    }
+
+    fn add_match(&self, lobby: (u16, &str), series: &MatchState) -> Result<u32> {
+        let mut tables = self.tables.lock().unwrap();
+
+        let ratings = if series.rated {
+            let first = tables
+                .user(series.players.0)
+                .ok_or(StorageError::NotFound)?;
+            let second = tables
+                .user(series.players.1)
+                .ok_or(StorageError::NotFound)?;
+
+            rate_match(first, second, series)
+        } else {
+            None
+        };
+
+        if let Some((first_ratings, second_ratings)) = ratings {
+            if let Some(first) = tables.user_mut(series.players.0) {
+                (first.angel_rating, first.devil_rating) =
+                    (Some(first_ratings.0), Some(first_ratings.1));
+            }
+            if let Some(second) = tables.user_mut(series.players.1) {
+                (second.angel_rating, second.devil_rating) =
+                    (Some(second_ratings.0), Some(second_ratings.1));
+            }
+        }
+
+        let id = tables.matches.len() as u32 + 1;
+
+        for game_id in series.game_ids.iter() {
+            if let Some(game) = game_id
+                .checked_sub(1)
+                .and_then(|index| tables.games.get_mut(index as usize))
+            {
+                game.match_id = Some(id);
+            }
+        }
+
+        tables.matches.push(MatchRecord {
+            id,
+            lobby_id: lobby.0,
+            lobby_name: lobby.1.to_string(),
+            players: series.players,
+            names: (String::new(), String::new()),
+            games: series.games,
+            wins: series.wins,
+            draws: series.draws,
+            winner: series.winner(),
+            forfeit: series.forfeit,
+            abandoned: series.abandoned,
+            rated: ratings.is_some(),
+            game_ids: series.game_ids.clone(),
+            started_at: series.started_at,
+            ended_at: timestamp(),
+        });
+
+        Ok(id)
+    }
+
+    fn get_match(&self, id: u32) -> Result<MatchRecord> {
+        let tables = self.tables.lock().unwrap();
+
+        let mut series = id
+            .checked_sub(1)
+            .and_then(|index| tables.matches.get(index as usize))
+            .cloned()
+            .ok_or(StorageError::NotFound)?;
+
+        series.names = (tables.name(series.players.0), tables.name(series.players.1));
+
+        Ok(series)
+    }
+
+    fn add_lobby(&self, id: u16, name: &str, settings: &GameSettings) -> Result<()> {
+        let mut tables = self.tables.lock().unwrap();
+
+        if tables.lobbies.contains_key(&id) {
+            return Err(StorageError::AlreadyExists);
+        }
+
+        tables.lobbies.insert(
+            id,
+            LobbyRecord {
+                id,
+                name: name.to_string(),
+                settings: settings.clone(),
+                game: None,
+                series: None,
+            },
+        );
+
+        Ok(())
+    }
+
+    fn set_lobby_settings(&self, id: u16, settings: &GameSettings) -> Result<()> {
+        let mut tables = self.tables.lock().unwrap();
+
+        if let Some(lobby) = tables.lobbies.get_mut(&id) {
+            lobby.settings = settings.clone();
+        }
+
+        Ok(())
+    }
+
+    fn set_lobby_game(&self, id: u16, game: Option<&GameState>) -> Result<()> {
+        let mut tables = self.tables.lock().unwrap();
+
+        if let Some(lobby) = tables.lobbies.get_mut(&id) {
+            lobby.game = game.cloned();
+        }
+
+        Ok(())
+    }
+
+    fn set_lobby_match(&self, id: u16, series: Option<&MatchState>) -> Result<()> {
+        let mut tables = self.tables.lock().unwrap();
+
+        if let Some(lobby) = tables.lobbies.get_mut(&id) {
+            lobby.series = series.cloned();
+        }
+
+        Ok(())
+    }
+
+    fn remove_lobby(&self, id: u16) -> Result<()> {
+        let mut tables = self.tables.lock().unwrap();
+
+        tables.lobbies.remove(&id);
+        tables.messages.retain(|(lobby_id, _)| *lobby_id != id);
+
+        Ok(())
+    }
+
+    fn get_lobbies(&self) -> Result<Vec<LobbyRecord>> {
+        let tables = self.tables.lock().unwrap();
+
+        Ok(tables.lobbies.values().cloned().collect())
+    }
+
+    fn add_message(&self, lobby_id: u16, user_id: u32, text: &str) -> Result<ChatMessage> {
+        let mut tables = self.tables.lock().unwrap();
+
+        let name = tables
+            .user(user_id)
+            .ok_or(StorageError::NotFound)?
+            .name
+            .clone();
+
+        tables.last_message_id += 1;
+
+        let message = ChatMessage {
+            id: tables.last_message_id,
+            user_id,
+            name,
+            text: text.to_string(),
+            sent_at: timestamp(),
+        };
+
+        tables.messages.push((lobby_id, message.clone()));
+
+        Ok(message)
+    }
+
+    fn get_messages(
+        &self,
+        lobby_id: u16,
+        before: Option<u32>,
+        count: u32,
+    ) -> Result<Vec<ChatMessage>> {
+        let tables = self.tables.lock().unwrap();
+
+        let mut messages: Vec<ChatMessage> = tables
+            .messages
+            .iter()
+            .rev()
+            .filter(|(id, message)| {
+                *id == lobby_id && before.map_or(true, |before| message.id < before)
+            })
+            .take(count as usize)
+            .map(|(_, message)| ChatMessage {
+                name: tables.name(message.user_id),
+                ..message.clone()
+            })
+            .collect();
+
+        messages.reverse();
+
+        Ok(messages)
+    }
+}
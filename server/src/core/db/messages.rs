@@ -0,0 +1,75 @@
+use rules::timestamp;
+use rusqlite::{params, Connection, Result};
+
+use super::ChatMessage;
+
+pub trait MessageOps {
+    const ADD_MESSAGE: &'static str;
+    const GET_MESSAGES: &'static str;
+
+    fn add_message(&self, lobby_id: u16, user_id: u32, text: &str) -> Result<ChatMessage>;
+    fn get_messages(
+        &self,
+        lobby_id: u16,
+        before: Option<u32>,
+        count: u32,
+    ) -> Result<Vec<ChatMessage>>;
+}
+
+impl MessageOps for Connection {
+    const ADD_MESSAGE: &'static str =
+        "INSERT INTO message (lobby_id, user_id, text, sent_at) VALUES(?1, ?2, ?3, ?4)";
+    // the newest messages are selected and then put back in the order they were sent
+    const GET_MESSAGES: &'static str = "
+SELECT * FROM (
+    SELECT m.id, m.user_id, u.name, m.text, m.sent_at
+    FROM message m
+    JOIN user u ON u.id = m.user_id
+    WHERE m.lobby_id = ?1 AND (?2 IS NULL OR m.id < ?2)
+    ORDER BY m.id DESC
+    LIMIT ?3
+)
+ORDER BY id";
+
+    fn add_message(&self, lobby_id: u16, user_id: u32, text: &str) -> Result<ChatMessage> {
+        let name = self.query_row("SELECT name FROM user WHERE id = ?1", [user_id], |row| {
+            row.get(0)
+        })?;
+        let sent_at = timestamp();
+
+        self.execute(Self::ADD_MESSAGE, params![lobby_id, user_id, text, sent_at])?;
+
+        let id = self.last_insert_rowid() as u32;
+
+        Ok(ChatMessage {
+            id,
+            user_id,
+            name,
+            text: text.to_string(),
+            sent_at,
+        })
+    }
+
+    fn get_messages(
+        &self,
+        lobby_id: u16,
+        before: Option<u32>,
+        count: u32,
+    ) -> Result<Vec<ChatMessage>> {
+        let mut stmt = self.prepare(Self::GET_MESSAGES)?;
+
+        let messages = stmt
+            .query_map(params![lobby_id, before, count], |row| {
+                Ok(ChatMessage {
+                    id: row.get(0)?,
+                    user_id: row.get(1)?,
+                    name: row.get(2)?,
+                    text: row.get(3)?,
+                    sent_at: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(messages)
+    }
+}
@@ -0,0 +1,243 @@
+use anyhow::{anyhow, Result};
+use rusqlite::{params, Connection};
+
+// migration i brings the schema from version i to version i + 1, a migration must never change
+// once released, schema changes always go into a new migration at the end of the list
+const MIGRATIONS: &[&str] = &[
+    // 1: users, IF NOT EXISTS adopts databases created before migrations existed
+    "
+CREATE TABLE IF NOT EXISTS user (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    addr TEXT NOT NULL,
+    highscore INTEGER,
+    connected INTEGER,
+    UNIQUE (name, addr)
+);",
+    // 2: accounts, guests are still identified by (name, addr) while accounts are identified by
+    // their name alone and carry a password hash
+    "
+CREATE TABLE user_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    addr TEXT NOT NULL,
+    highscore INTEGER,
+    connected INTEGER,
+    password TEXT
+);
+INSERT INTO user_new (id, name, addr, highscore, connected)
+    SELECT id, name, addr, highscore, connected FROM user;
+DROP TABLE user;
+ALTER TABLE user_new RENAME TO user;
+CREATE UNIQUE INDEX user_guest ON user (name, addr) WHERE password IS NULL;
+CREATE UNIQUE INDEX user_account ON user (name COLLATE NOCASE) WHERE password IS NOT NULL;",
+    // 3: sessions, every authenticated request carries a token instead of the user's id
+    "
+CREATE TABLE session (
+    token TEXT PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES user (id),
+    expires_at INTEGER NOT NULL
+);
+CREATE INDEX session_user ON session (user_id);",
+    // 4: finished games, the angel or the devil is 0 when played by the computer, the grid is the
+    // one the game started with, one character per tile in row-major order
+    "
+CREATE TABLE game (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    lobby_id INTEGER NOT NULL,
+    lobby_name TEXT NOT NULL,
+    angel INTEGER NOT NULL,
+    devil INTEGER NOT NULL,
+    size INTEGER NOT NULL,
+    grid TEXT NOT NULL,
+    angel_line INTEGER NOT NULL,
+    angel_column INTEGER NOT NULL,
+    winner TEXT,
+    reason TEXT NOT NULL,
+    started_at INTEGER NOT NULL,
+    ended_at INTEGER NOT NULL
+);
+CREATE INDEX game_angel ON game (angel);
+CREATE INDEX game_devil ON game (devil);
+CREATE TABLE game_move (
+    game_id INTEGER NOT NULL REFERENCES game (id),
+    ply INTEGER NOT NULL,
+    role TEXT NOT NULL,
+    line INTEGER NOT NULL,
+    column INTEGER NOT NULL,
+    PRIMARY KEY (game_id, ply)
+);",
+    // 5: ratings, the never used highscore becomes the angel rating, NULL until the first rated
+    // game in that role
+    "
+ALTER TABLE user RENAME COLUMN highscore TO angel_rating;
+UPDATE user SET angel_rating = NULL;
+ALTER TABLE user ADD COLUMN devil_rating INTEGER;
+ALTER TABLE game ADD COLUMN rated INTEGER NOT NULL DEFAULT 0;",
+    // 6: lobbies, restored when the server starts, the game is the one in progress if any
+    "
+CREATE TABLE lobby (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    game BLOB
+);",
+    // 7: chat messages, removed along with their lobby
+    "
+CREATE TABLE message (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    lobby_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES user (id),
+    text TEXT NOT NULL,
+    sent_at INTEGER NOT NULL
+);
+CREATE INDEX message_lobby ON message (lobby_id, id);",
+    // 8: lobby settings, one column per setting
+    "
+ALTER TABLE lobby ADD COLUMN grid_size INTEGER NOT NULL DEFAULT 11;",
+    // 9: seeded boards, sqlite has no unsigned integers so seeds are stored as their bits in an
+    // i64, games recorded before this have no seed
+    "
+ALTER TABLE lobby ADD COLUMN wall_density INTEGER NOT NULL DEFAULT 12;
+ALTER TABLE lobby ADD COLUMN seed INTEGER;
+ALTER TABLE game ADD COLUMN wall_density INTEGER;
+ALTER TABLE game ADD COLUMN seed INTEGER;",
+    // 10: angel power, every game recorded before this was played by a walking angel of power 1
+    "
+ALTER TABLE lobby ADD COLUMN angel_power INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE lobby ADD COLUMN angel_rule TEXT NOT NULL DEFAULT 'walk';
+ALTER TABLE game ADD COLUMN angel_power INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE game ADD COLUMN angel_rule TEXT NOT NULL DEFAULT 'walk';",
+    // 11: devil walls, a budget of NULL means no limit
+    "
+ALTER TABLE lobby ADD COLUMN walls_per_turn INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE lobby ADD COLUMN wall_budget INTEGER;
+ALTER TABLE lobby ADD COLUMN adjacent_walls INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE game ADD COLUMN walls_per_turn INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE game ADD COLUMN wall_budget INTEGER;
+ALTER TABLE game ADD COLUMN adjacent_walls INTEGER NOT NULL DEFAULT 1;",
+    // 12: board topology, exits are stored as one letter per open edge
+    "
+ALTER TABLE lobby ADD COLUMN topology TEXT NOT NULL DEFAULT 'hex';
+ALTER TABLE lobby ADD COLUMN outline TEXT NOT NULL DEFAULT 'rectangle';
+ALTER TABLE lobby ADD COLUMN exits TEXT NOT NULL DEFAULT 'tblr';
+ALTER TABLE game ADD COLUMN topology TEXT NOT NULL DEFAULT 'hex';
+ALTER TABLE game ADD COLUMN outline TEXT NOT NULL DEFAULT 'rectangle';
+ALTER TABLE game ADD COLUMN exits TEXT NOT NULL DEFAULT 'tblr';",
+    // 13: time controls, stored like "300+5" or "30/move"
+    "
+ALTER TABLE lobby ADD COLUMN time_control TEXT NOT NULL DEFAULT 'unlimited';
+ALTER TABLE game ADD COLUMN time_control TEXT NOT NULL DEFAULT 'unlimited';",
+    // 14: the sides the players of a lobby take, the games already record who played which
+    "
+ALTER TABLE lobby ADD COLUMN roles TEXT NOT NULL DEFAULT 'host-devil';
+ALTER TABLE lobby ADD COLUMN swap_roles INTEGER NOT NULL DEFAULT 0;",
+    // 15: best of n matches, stored once they are over and pointed to by their games, MATCH is a
+    // keyword so the table is called series, the winner is NULL for a tie
+    "
+CREATE TABLE series (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    lobby_id INTEGER NOT NULL,
+    lobby_name TEXT NOT NULL,
+    first INTEGER NOT NULL REFERENCES user (id),
+    second INTEGER NOT NULL REFERENCES user (id),
+    games INTEGER NOT NULL,
+    first_wins INTEGER NOT NULL,
+    second_wins INTEGER NOT NULL,
+    draws INTEGER NOT NULL,
+    winner INTEGER,
+    forfeit INTEGER,
+    rated INTEGER NOT NULL,
+    started_at INTEGER NOT NULL,
+    ended_at INTEGER NOT NULL
+);
+ALTER TABLE game ADD COLUMN series_id INTEGER REFERENCES series (id);
+ALTER TABLE lobby ADD COLUMN match_games INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE lobby ADD COLUMN match_rated INTEGER NOT NULL DEFAULT 0;",
+    // 16: how fair the boards of a lobby are, lobbies created before keep scattering walls
+    "ALTER TABLE lobby ADD COLUMN difficulty TEXT NOT NULL DEFAULT 'any';",
+    // 17: how well the computer plays the devil in the lobby's games without a second player
+    "ALTER TABLE lobby ADD COLUMN computer_level TEXT NOT NULL DEFAULT 'normal';",
+    // 18: the difficulty a game's board was picked for, it's generated again from it along with
+    // the seed and density
+    "ALTER TABLE game ADD COLUMN difficulty TEXT NOT NULL DEFAULT 'any';",
+    // 19: matches the lobby was set up away from before they were over, and the match a lobby is
+    // playing so it goes on after a restart
+    "
+ALTER TABLE series ADD COLUMN abandoned INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE lobby ADD COLUMN series BLOB;",
+];
+
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    conn.execute(
+        "
+CREATE TABLE IF NOT EXISTS schema_version (
+    version INTEGER PRIMARY KEY,
+    applied_at INTEGER NOT NULL
+)",
+        (),
+    )?;
+
+    let version: Option<u32> =
+        conn.query_row("SELECT MAX(version) FROM schema_version", (), |row| {
+            row.get(0)
+        })?;
+
+    Ok(version.unwrap_or(0))
+}
+
+// applies every migration the database is missing, each one in its own transaction
+pub fn migrate(conn: &mut Connection) -> Result<u32> {
+    let latest = MIGRATIONS.len() as u32;
+    let mut version = schema_version(conn)?;
+
+    if version > latest {
+        return Err(anyhow!(
+            "database schema version {version} is newer than the latest known version {latest}"
+        ));
+    }
+
+    while version < latest {
+        let tx = conn.transaction()?;
+
+        tx.execute_batch(MIGRATIONS[version as usize])?;
+        tx.execute(
+            "INSERT INTO schema_version (version, applied_at) VALUES (?1, strftime('%s', 'now'))",
+            params![version + 1],
+        )?;
+
+        tx.commit()?;
+
+        version += 1;
+        println!("applied migration {version}");
+    }
+
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrate_fresh_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+
+        assert_eq!(migrate(&mut conn).unwrap(), MIGRATIONS.len() as u32);
+        // running again is a no-op
+        assert_eq!(migrate(&mut conn).unwrap(), MIGRATIONS.len() as u32);
+    }
+
+    #[test]
+    fn refuse_newer_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+
+        conn.execute(
+            "INSERT INTO schema_version (version, applied_at) VALUES (?1, 0)",
+            params![MIGRATIONS.len() as u32 + 1],
+        )
+        .unwrap();
+
+        assert!(migrate(&mut conn).is_err());
+    }
+}
@@ -1,129 +1,579 @@
+mod games;
+mod lobbies;
+mod matches;
+mod memory;
+mod messages;
+mod migrations;
 mod models;
+mod profiles;
+mod ratings;
+mod sessions;
+mod sqlite;
+mod users;
 
+pub use memory::MemoryStorage;
 pub use models::*;
+pub use sqlite::SqliteStorage;
 
-pub use rusqlite::{params, Connection, Result};
+use std::sync::Arc;
+
+use rules::{EndReason, GameSettings, GameState, MatchState, Role};
+use thiserror::Error;
+
+use super::rating::{self, INITIAL_RATING};
 
 pub static DB_NAME: &str = "db.db";
 
-pub fn init_db() -> Result<()> {
-    let conn = Connection::open(DB_NAME)?;
-
-    let _ = conn.execute("DROP TABLE user", ());
-
-    conn.execute(
-        "
-CREATE TABLE user (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    name TEXT NOT NULL, 
-    addr TEXT NOT NULL,
-    highscore INTEGER, 
-    connected INTEGER,
-    UNIQUE (name, addr)
-)",
-        (),
-    )?;
-
-    Ok(())
+// sessions expire after a day without any requests
+pub static SESSION_TTL: u32 = 24 * 60 * 60;
+
+// number of games included in a profile's recent results
+pub const RECENT_GAMES: u32 = 5;
+
+#[derive(Error, Debug)]
+pub enum StorageError {
+    #[error("not found")]
+    NotFound,
+
+    // a unique name was already taken
+    #[error("already exists")]
+    AlreadyExists,
+
+    #[error("sqlite error")]
+    Sqlite(rusqlite::Error),
+
+    #[error("connection pool error")]
+    Pool(#[from] r2d2::Error),
+
+    #[error("couldn't encode or decode a stored value")]
+    Encoding(#[from] bincode::Error),
 }
 
-pub trait UserOps {
-    const GET_USER_BY_ID: &'static str;
-    const GET_USER_BY_KEY: &'static str;
-    const ADD_USER: &'static str;
-    const CHANGE_USER_NAME: &'static str;
-    const REMOVE_USER: &'static str;
-    const TOGGLE_CONNECTED: &'static str;
+impl From<rusqlite::Error> for StorageError {
+    fn from(e: rusqlite::Error) -> Self {
+        match e {
+            rusqlite::Error::QueryReturnedNoRows => StorageError::NotFound,
+            rusqlite::Error::SqliteFailure(ref err, _)
+                if err.code == rusqlite::ErrorCode::ConstraintViolation =>
+            {
+                StorageError::AlreadyExists
+            }
+            e => StorageError::Sqlite(e),
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, StorageError>;
 
+// everything the request handlers need to persist, handlers only ever see a Db so the server can
+// run on sqlite or entirely in memory
+pub trait Storage: Send + Sync {
     fn get_user_by_id(&self, id: u32) -> Result<User>;
+    // guests are identified by their name and address
     fn get_user_by_key(&self, name: &str, addr: &str) -> Result<User>;
     fn add_user(&self, name: &str, addr: &str) -> Result<()>;
     fn change_user_name(&self, id: u32, name: &str) -> Result<()>;
-    fn remove_user(&self, id: u32) -> Result<()>;
     fn toggle_connected(&self, id: u32) -> Result<()>;
-    fn is_connected(&self, id: u32) -> Result<Option<User>>;
+    // taken by a guest with a live session or by any account other than the user itself
+    fn name_taken(&self, id: u32, name: &str) -> Result<bool>;
+    fn get_account(&self, name: &str) -> Result<User>;
+    fn add_account(&self, name: &str, addr: &str, password: &str) -> Result<u32>;
+    fn log_in(&self, id: u32, addr: &str) -> Result<()>;
+
+    fn add_session(&self, token: &str, user_id: u32) -> Result<()>;
+    // unknown tokens return NotFound, expired ones are revoked and return None
+    fn get_session_user(&self, token: &str) -> Result<Option<User>>;
+    fn revoke_session(&self, token: &str) -> Result<()>;
+    fn has_sessions(&self, user_id: u32) -> Result<bool>;
+
+    // stores a finished game and updates the players' ratings if it was rated, the games of a
+    // rated match aren't rated on their own
+    fn add_game(
+        &self,
+        lobby: (u16, &str),
+        game: &GameState,
+        winner: Option<Role>,
+        reason: EndReason,
+        rate: bool,
+    ) -> Result<u32>;
+    fn get_game(&self, id: u32) -> Result<GameRecord>;
+    fn get_user_games(&self, user_id: u32, start: u32, count: u32) -> Result<Vec<GameSummary>>;
+    fn get_leaderboard(
+        &self,
+        role: Role,
+        user_id: u32,
+        start: u32,
+        count: u32,
+    ) -> Result<Leaderboard>;
+    fn get_last_game_id(&self, user_id: u32) -> Result<Option<u32>>;
+    fn get_profile_stats(&self, user_id: u32) -> Result<ProfileStats>;
+
+    // stores a match that is over and links its games to it, a rated match updates the players'
+    // ratings as a single result
+    fn add_match(&self, lobby: (u16, &str), series: &MatchState) -> Result<u32>;
+    fn get_match(&self, id: u32) -> Result<MatchRecord>;
+
+    fn add_lobby(&self, id: u16, name: &str, settings: &GameSettings) -> Result<()>;
+    fn set_lobby_settings(&self, id: u16, settings: &GameSettings) -> Result<()>;
+    // saves the game in progress, None once it's over
+    fn set_lobby_game(&self, id: u16, game: Option<&GameState>) -> Result<()>;
+    // saves the match the lobby is playing, None once it's over
+    fn set_lobby_match(&self, id: u16, series: Option<&MatchState>) -> Result<()>;
+    fn remove_lobby(&self, id: u16) -> Result<()>;
+    fn get_lobbies(&self) -> Result<Vec<LobbyRecord>>;
+
+    fn add_message(&self, lobby_id: u16, user_id: u32, text: &str) -> Result<ChatMessage>;
+    // the latest count messages sent before the given id, or the latest overall, oldest first
+    fn get_messages(
+        &self,
+        lobby_id: u16,
+        before: Option<u32>,
+        count: u32,
+    ) -> Result<Vec<ChatMessage>>;
+}
+
+pub type Db = Arc<dyn Storage>;
+
+// only games between two accounts are rated, guests could farm ratings by coming back under new
+// names and the computer isn't a user
+pub fn is_rated(angel: &User, devil: &User) -> bool {
+    !angel.is_guest() && !devil.is_guest()
 }
 
-impl UserOps for Connection {
-    const GET_USER_BY_ID: &'static str = "SELECT * FROM user WHERE id = ?1";
-    const GET_USER_BY_KEY: &'static str = "SELECT * FROM user WHERE (name, addr) = (?1, ?2)";
-    const ADD_USER: &'static str = "INSERT INTO user (name, addr, connected) VALUES(?1, ?2, 1)";
-    const CHANGE_USER_NAME: &'static str = "UPDATE user SET name = ?2 WHERE id = ?1";
-    const REMOVE_USER: &'static str = "DELETE FROM user WHERE id = ?1";
-    const TOGGLE_CONNECTED: &'static str =
-        "UPDATE user SET connected = NOT connected WHERE id = (?1)";
-
-    fn get_user_by_id(&self, id: u32) -> Result<User> {
-        let mut stmt = self.prepare(Self::GET_USER_BY_ID)?;
-
-        let user = stmt.query_row([id], |row| {
-            Ok(User {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                addr: row.get(2)?,
-                highscore: row.get(3)?,
-                connected: row.get(4)?,
-            })
-        })?;
-
-        Ok(user)
+// returns the new (angel, devil) ratings of rated games
+fn rate_game(angel: &User, devil: &User, winner: Option<Role>) -> Option<(u32, u32)> {
+    if !is_rated(angel, devil) {
+        return None;
     }
 
-    fn get_user_by_key(&self, name: &str, addr: &str) -> Result<User> {
-        let mut stmt = self.prepare(Self::GET_USER_BY_KEY)?;
+    // a draw is worth half a win to both
+    let angel_score = match winner {
+        Some(Role::Angel) => 1.0,
+        Some(Role::Devil) => 0.0,
+        None => 0.5,
+    };
 
-        let user = stmt.query_row([name, addr], |row| {
-            Ok(User {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                addr: row.get(2)?,
-                highscore: row.get(3)?,
-                connected: row.get(4)?,
-            })
-        })?;
+    Some(rating::update(
+        angel.angel_rating.unwrap_or(INITIAL_RATING),
+        devil.devil_rating.unwrap_or(INITIAL_RATING),
+        angel_score,
+    ))
+}
 
-        Ok(user)
+// returns the new (angel, devil) ratings of both players of a rated match, they played both sides
+// so the angel of each is rated against the devil of the other with the score of the whole match
+fn rate_match(
+    first: &User,
+    second: &User,
+    series: &MatchState,
+) -> Option<((u32, u32), (u32, u32))> {
+    // nobody won a match that wasn't finished
+    if series.abandoned || !is_rated(first, second) {
+        return None;
     }
 
-    fn add_user(&self, name: &str, addr: &str) -> Result<()> {
-        let mut stmt = self.prepare(Self::ADD_USER)?;
+    let first_score = match series.winner() {
+        Some(id) if id == first.id => 1.0,
+        Some(_) => 0.0,
+        None => 0.5,
+    };
 
-        stmt.execute(params![name, addr])?;
+    let (first_angel, second_devil) = rating::update(
+        first.angel_rating.unwrap_or(INITIAL_RATING),
+        second.devil_rating.unwrap_or(INITIAL_RATING),
+        first_score,
+    );
+    let (second_angel, first_devil) = rating::update(
+        second.angel_rating.unwrap_or(INITIAL_RATING),
+        first.devil_rating.unwrap_or(INITIAL_RATING),
+        1.0 - first_score,
+    );
+
+    Some(((first_angel, first_devil), (second_angel, second_devil)))
+}
 
-        Ok(())
+// both storages must behave the same, so every test runs against each of them
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rules::{
+        AngelRule, ComputerLevel, Difficulty, Exits, GameMove, Outline, RoleAssignment,
+        TimeControl, Topology,
+    };
+
+    fn storages() -> Vec<Db> {
+        vec![
+            Arc::new(SqliteStorage::open_in_memory().unwrap()),
+            Arc::new(MemoryStorage::default()),
+        ]
     }
 
-    fn change_user_name(&self, id: u32, name: &str) -> Result<()> {
-        let mut stmt = self.prepare(Self::CHANGE_USER_NAME)?;
+    #[test]
+    fn users_and_sessions() {
+        for db in storages() {
+            db.add_user("guest", "127.0.0.1:1").unwrap();
+            assert!(matches!(
+                db.add_user("guest", "127.0.0.1:1"),
+                Err(StorageError::AlreadyExists)
+            ));
+
+            // a guest only holds its name while it has a session
+            let guest = db.get_user_by_key("guest", "127.0.0.1:1").unwrap();
+            assert!(guest.is_guest());
+            assert!(!db.name_taken(0, "guest").unwrap());
+            db.add_session("guest token", guest.id).unwrap();
+            assert!(db.name_taken(0, "GUEST").unwrap());
+            assert!(!db.name_taken(guest.id, "guest").unwrap());
+            db.revoke_session("guest token").unwrap();
+            assert!(!db.name_taken(0, "guest").unwrap());
 
-        stmt.execute(params![id, name])?;
+            let id = db.add_account("alice", "127.0.0.1:2", "hash").unwrap();
+            assert!(matches!(
+                db.add_account("Alice", "127.0.0.1:3", "hash"),
+                Err(StorageError::AlreadyExists)
+            ));
+            assert_eq!(db.get_account("ALICE").unwrap().id, id);
 
-        Ok(())
+            db.add_session("token", id).unwrap();
+            assert_eq!(db.get_session_user("token").unwrap().unwrap().id, id);
+            assert!(db.has_sessions(id).unwrap());
+            assert!(matches!(
+                db.get_session_user("unknown"),
+                Err(StorageError::NotFound)
+            ));
+
+            db.revoke_session("token").unwrap();
+            assert!(!db.has_sessions(id).unwrap());
+            assert!(matches!(
+                db.get_session_user("token"),
+                Err(StorageError::NotFound)
+            ));
+        }
     }
 
-    fn remove_user(&self, id: u32) -> Result<()> {
-        let mut stmt = self.prepare(Self::REMOVE_USER)?;
+    #[test]
+    fn games_ratings_and_profiles() {
+        for db in storages() {
+            let alice = db.add_account("alice", "127.0.0.1:1", "hash").unwrap();
+            let bob = db.add_account("bob", "127.0.0.1:2", "hash").unwrap();
+
+            let settings = GameSettings {
+                difficulty: Difficulty::Normal,
+                ..GameSettings::default()
+            };
+            let mut game = GameState::new(alice, bob, &settings);
+            game.moves.push(GameMove {
+                role: Role::Devil,
+                pos: (0, 0),
+            });
+            game.moves.push(GameMove {
+                role: Role::Angel,
+                pos: (4, 5),
+            });
+
+            let id = db
+                .add_game(
+                    (1, "lobby"),
+                    &game,
+                    Some(Role::Angel),
+                    EndReason::Escaped,
+                    true,
+                )
+                .unwrap();
+
+            let record = db.get_game(id).unwrap();
+            assert_eq!(record.angel_name, "alice");
+            assert_eq!(record.devil_name, "bob");
+            assert_eq!(record.moves.len(), 2);
+            assert!(record.rated);
+            assert_eq!(record.seed, Some(game.seed));
+            assert_eq!(record.angel_power, 1);
+            assert_eq!(record.angel_rule, AngelRule::Walk);
+            assert_eq!(record.wall_budget, None);
+            assert_eq!(record.time_control, TimeControl::Unlimited);
+            // the board is generated again from what's recorded
+            assert_eq!(record.difficulty, Difficulty::Normal);
+            assert_eq!(
+                record.grid,
+                record.difficulty.grid(
+                    &game.board,
+                    record.wall_density.unwrap(),
+                    record.seed.unwrap()
+                )
+            );
+            assert!(matches!(db.get_game(id + 1), Err(StorageError::NotFound)));
 
-        stmt.execute(params![id])?;
+            // games against the computer aren't rated
+            let computer = GameState::new(0, bob, &GameSettings::default());
+            db.add_game(
+                (1, "lobby"),
+                &computer,
+                Some(Role::Devil),
+                EndReason::Trapped,
+                true,
+            )
+            .unwrap();
 
-        Ok(())
+            let history = db.get_user_games(bob, 0, 10).unwrap();
+            assert_eq!(history.len(), 2);
+            assert_eq!(history[0].angel_name, "computer");
+            assert!(!history[0].rated);
+            assert_eq!(db.get_last_game_id(bob).unwrap(), Some(history[0].id));
+
+            let leaderboard = db.get_leaderboard(Role::Angel, bob, 0, 10).unwrap();
+            assert_eq!(leaderboard.entries.len(), 1);
+            assert_eq!(leaderboard.entries[0].id, alice);
+            assert!(leaderboard.entries[0].rating > INITIAL_RATING);
+            assert!(leaderboard.user.is_none());
+
+            let stats = db.get_profile_stats(bob).unwrap();
+            assert_eq!(stats.games, 2);
+            assert_eq!(stats.devil.games, 2);
+            assert_eq!(stats.devil.wins, 1);
+            assert_eq!(stats.devil.losses, 1);
+            assert_eq!(stats.average_moves, 1.0);
+            assert_eq!(stats.recent.len(), 2);
+
+            let stats = db.get_profile_stats(alice).unwrap();
+            assert_eq!(stats.angel.wins, 1);
+            assert_eq!(stats.longest_survival, 1);
+
+            // draws are rated but count as neither a win nor a loss
+            let rating = db.get_user_by_id(alice).unwrap().angel_rating.unwrap();
+            let id = db
+                .add_game((1, "lobby"), &game, None, EndReason::Draw, true)
+                .unwrap();
+            let record = db.get_game(id).unwrap();
+            assert!(record.rated);
+            assert_eq!(record.winner, None);
+            assert_eq!(record.reason, EndReason::Draw);
+            assert!(db.get_user_by_id(alice).unwrap().angel_rating.unwrap() < rating);
+
+            let stats = db.get_profile_stats(alice).unwrap();
+            assert_eq!(stats.angel.games, 2);
+            assert_eq!((stats.angel.wins, stats.angel.losses), (1, 0));
+
+            // neither are games against the computer devil
+            let rating = db.get_user_by_id(alice).unwrap().angel_rating;
+            let computer = GameState::new(alice, 0, &GameSettings::default());
+            let id = db
+                .add_game(
+                    (1, "lobby"),
+                    &computer,
+                    Some(Role::Angel),
+                    EndReason::Escaped,
+                    true,
+                )
+                .unwrap();
+            let record = db.get_game(id).unwrap();
+            assert!(!record.rated);
+            assert_eq!(record.devil_name, "computer");
+            assert_eq!(db.get_user_by_id(alice).unwrap().angel_rating, rating);
+        }
     }
 
-    fn toggle_connected(&self, id: u32) -> Result<()> {
-        let mut stmt = self.prepare(Self::TOGGLE_CONNECTED)?;
+    #[test]
+    fn matches() {
+        for db in storages() {
+            let alice = db.add_account("alice", "127.0.0.1:1", "hash").unwrap();
+            let bob = db.add_account("bob", "127.0.0.1:2", "hash").unwrap();
+
+            // alice wins both games of a rated best of three, the games aren't rated on their own
+            let mut series = MatchState::new(3, alice, bob, true);
+            for (angel, devil) in [(bob, alice), (alice, bob)] {
+                let game = GameState::new(angel, devil, &GameSettings::default());
+                let winner = if angel == alice {
+                    Role::Angel
+                } else {
+                    Role::Devil
+                };
 
-        stmt.execute(params![id])?;
+                let id = db
+                    .add_game((1, "lobby"), &game, Some(winner), EndReason::Escaped, false)
+                    .unwrap();
+                series.record(id, angel, devil, Some(winner));
+            }
+            assert!(series.is_over());
+            assert!(db.get_user_by_id(alice).unwrap().angel_rating.is_none());
 
-        Ok(())
+            let id = db.add_match((1, "lobby"), &series).unwrap();
+
+            let record = db.get_match(id).unwrap();
+            assert_eq!(record.players, (alice, bob));
+            assert_eq!(record.names, ("alice".to_string(), "bob".to_string()));
+            assert_eq!(record.wins, (2, 0));
+            assert_eq!(record.winner, Some(alice));
+            assert!(record.rated);
+            assert_eq!(record.game_ids, series.game_ids);
+            assert!(matches!(db.get_match(id + 1), Err(StorageError::NotFound)));
+
+            for game_id in series.game_ids.iter() {
+                let game = db.get_game(*game_id).unwrap();
+                assert_eq!(game.match_id, Some(id));
+                assert!(!game.rated);
+            }
+            assert_eq!(db.get_user_games(bob, 0, 10).unwrap()[0].match_id, Some(id));
+
+            // the match counts once for both sides of each player
+            let alice = db.get_user_by_id(alice).unwrap();
+            let bob = db.get_user_by_id(bob).unwrap();
+            assert_eq!(alice.angel_rating, Some(INITIAL_RATING + 16));
+            assert_eq!(alice.devil_rating, Some(INITIAL_RATING + 16));
+            assert_eq!(bob.angel_rating, Some(INITIAL_RATING - 16));
+            assert_eq!(bob.devil_rating, Some(INITIAL_RATING - 16));
+
+            // a match left unfinished is stored without a winner and doesn't change the ratings
+            let (alice, bob) = (alice.id, bob.id);
+            let mut series = MatchState::new(3, alice, bob, true);
+            let game = GameState::new(bob, alice, &GameSettings::default());
+            let winner = Some(Role::Devil);
+            let game_id = db
+                .add_game((1, "lobby"), &game, winner, EndReason::Escaped, false)
+                .unwrap();
+            series.record(game_id, bob, alice, winner);
+            series.abandoned = true;
+
+            let id = db.add_match((1, "lobby"), &series).unwrap();
+            let record = db.get_match(id).unwrap();
+            assert!(record.abandoned);
+            assert_eq!(record.winner, None);
+            assert!(!record.rated);
+            let alice = db.get_user_by_id(alice).unwrap();
+            assert_eq!(alice.angel_rating, Some(INITIAL_RATING + 16));
+        }
     }
 
-    fn is_connected(&self, id: u32) -> Result<Option<User>> {
-        let user = self.get_user_by_id(id)?;
+    #[test]
+    fn lobbies() {
+        for db in storages() {
+            let settings = GameSettings::default();
+            db.add_lobby(3, "lobby", &settings).unwrap();
+            assert!(matches!(
+                db.add_lobby(3, "lobby", &settings),
+                Err(StorageError::AlreadyExists)
+            ));
+
+            // seeds use all 64 bits even though sqlite only has signed integers
+            let settings = GameSettings {
+                grid_size: 21,
+                wall_density: 20,
+                seed: Some(u64::MAX - 1),
+                angel_power: 3,
+                angel_rule: AngelRule::Jump,
+                walls_per_turn: 2,
+                wall_budget: Some(30),
+                adjacent_walls: false,
+                topology: Topology::Square8,
+                outline: Outline::Circle,
+                exits: Exits {
+                    top: true,
+                    bottom: true,
+                    left: false,
+                    right: false,
+                },
+                time_control: TimeControl::Total {
+                    seconds: 600,
+                    increment: 10,
+                },
+                roles: RoleAssignment::CoinFlip,
+                swap_roles: true,
+                match_games: 5,
+                match_rated: true,
+                difficulty: Difficulty::Hard,
+                computer_level: ComputerLevel::Easy,
+            };
+            db.set_lobby_settings(3, &settings).unwrap();
+
+            let mut game = GameState::new(0, 1, &settings);
+            game.grid[0][0] = true;
+            game.moves.push(GameMove {
+                role: Role::Devil,
+                pos: (0, 0),
+            });
+            db.set_lobby_game(3, Some(&game)).unwrap();
+
+            let mut series = MatchState::new(5, 0, 1, true);
+            series.record(7, 1, 0, Some(Role::Angel));
+            db.set_lobby_match(3, Some(&series)).unwrap();
+
+            let lobbies = db.get_lobbies().unwrap();
+            assert_eq!(lobbies.len(), 1);
+            assert_eq!(lobbies[0].id, 3);
+            assert_eq!(lobbies[0].name, "lobby");
+            assert_eq!(lobbies[0].settings.grid_size, 21);
+            assert_eq!(lobbies[0].settings.wall_density, 20);
+            assert_eq!(lobbies[0].settings.seed, Some(u64::MAX - 1));
+            assert_eq!(lobbies[0].settings.angel_power, 3);
+            assert_eq!(lobbies[0].settings.angel_rule, AngelRule::Jump);
+            assert_eq!(lobbies[0].settings.walls_per_turn, 2);
+            assert_eq!(lobbies[0].settings.wall_budget, Some(30));
+            assert!(!lobbies[0].settings.adjacent_walls);
+            assert_eq!(lobbies[0].settings.outline, Outline::Circle);
+            assert_eq!(lobbies[0].settings.exits, settings.exits);
+            assert_eq!(lobbies[0].settings.time_control, settings.time_control);
+            assert_eq!(lobbies[0].settings.roles, RoleAssignment::CoinFlip);
+            assert!(lobbies[0].settings.swap_roles);
+            assert_eq!(lobbies[0].settings.match_games, 5);
+            assert!(lobbies[0].settings.match_rated);
+            assert_eq!(lobbies[0].settings.difficulty, Difficulty::Hard);
+            assert_eq!(lobbies[0].settings.computer_level, ComputerLevel::Easy);
+
+            // the fields that aren't sent to clients are stored as well
+            let restored = lobbies[0].game.as_ref().unwrap();
+            assert_eq!(restored.size, 21);
+            assert_eq!(restored.seed, game.seed);
+            assert_eq!(restored.difficulty, Difficulty::Hard);
+            assert_eq!(restored.board.tiles, game.board.tiles);
+            assert_eq!(restored.grid, game.grid);
+            assert_eq!(restored.initial_grid, game.initial_grid);
+            assert_eq!(restored.moves.len(), 1);
+            assert_eq!(restored.started_at, game.started_at);
+            assert_eq!(restored.clocks, game.clocks);
+
+            assert_eq!(lobbies[0].series, Some(series));
+
+            db.set_lobby_game(3, None).unwrap();
+            db.set_lobby_match(3, None).unwrap();
+            assert!(db.get_lobbies().unwrap()[0].game.is_none());
+            assert!(db.get_lobbies().unwrap()[0].series.is_none());
+
+            db.remove_lobby(3).unwrap();
+            assert!(db.get_lobbies().unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn messages() {
+        for db in storages() {
+            let alice = db.add_account("alice", "127.0.0.1:1", "hash").unwrap();
+            db.add_lobby(1, "lobby", &GameSettings::default()).unwrap();
+            db.add_lobby(2, "other", &GameSettings::default()).unwrap();
+
+            for i in 0..5 {
+                db.add_message(1, alice, &format!("message {}", i)).unwrap();
+            }
+            db.add_message(2, alice, "elsewhere").unwrap();
+            assert!(matches!(
+                db.add_message(1, alice + 1, "nobody"),
+                Err(StorageError::NotFound)
+            ));
+
+            let latest = db.get_messages(1, None, 3).unwrap();
+            let texts: Vec<_> = latest.iter().map(|m| m.text.as_str()).collect();
+            assert_eq!(texts, ["message 2", "message 3", "message 4"]);
+            assert_eq!(latest[0].name, "alice");
+
+            // paging back from the oldest message that was returned
+            let older = db.get_messages(1, Some(latest[0].id), 10).unwrap();
+            assert_eq!(older.len(), 2);
+            assert_eq!(older[1].text, "message 1");
+
+            db.change_user_name(alice, "alicia").unwrap();
+            assert_eq!(db.get_messages(2, None, 10).unwrap()[0].name, "alicia");
 
-        if user.connected == 1 {
-            Ok(Some(user))
-        } else {
-            Ok(None)
+            db.remove_lobby(1).unwrap();
+            assert!(db.get_messages(1, None, 10).unwrap().is_empty());
+            assert_eq!(db.get_messages(2, None, 10).unwrap().len(), 1);
         }
     }
 }
@@ -1,8 +1,161 @@
-#[derive(Debug)]
+use rules::{
+    AngelRule, Difficulty, EndReason, Exits, GameMove, GameSettings, GameState, MatchState,
+    Outline, Role, TimeControl, Topology,
+};
+use serde_derive::Serialize;
+
+#[derive(Clone, Debug)]
 pub struct User {
     pub id: u32,
     pub name: String,
     pub addr: String,
-    pub highscore: Option<u32>,
+    pub angel_rating: Option<u32>, // None until the first rated game as the angel
     pub connected: u32,
+    pub password: Option<String>,  // argon2 hash, None for guests
+    pub devil_rating: Option<u32>, // None until the first rated game as the devil
+}
+
+impl User {
+    pub fn is_guest(&self) -> bool {
+        self.password.is_none()
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GameSummary {
+    pub id: u32,
+    pub angel: u32,
+    pub angel_name: String,
+    pub devil: u32,
+    pub devil_name: String,
+    pub winner: Option<Role>,
+    pub reason: EndReason,
+    pub rated: bool,
+    pub match_id: Option<u32>, // the match the game was part of, if any
+    pub moves: u32,
+    pub started_at: u64,
+    pub ended_at: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GameRecord {
+    pub id: u32,
+    pub lobby_id: u16,
+    pub lobby_name: String,
+    pub angel: u32,
+    pub angel_name: String,
+    pub devil: u32,
+    pub devil_name: String,
+    pub grid: Vec<Vec<bool>>, // the grid the game started with
+    pub angel_start: (i32, i32),
+    pub wall_density: Option<u32>, // None for games recorded before boards were seeded
+    pub seed: Option<u64>,
+    pub difficulty: Difficulty, // Any for games recorded before boards were rated
+    pub angel_power: u32,
+    pub angel_rule: AngelRule,
+    pub walls_per_turn: u32,
+    pub wall_budget: Option<u32>,
+    pub adjacent_walls: bool,
+    pub topology: Topology,
+    pub outline: Outline,
+    pub exits: Exits,
+    pub time_control: TimeControl,
+    pub winner: Option<Role>,
+    pub reason: EndReason,
+    pub rated: bool,
+    pub match_id: Option<u32>,
+    pub moves: Vec<GameMove>,
+    pub started_at: u64,
+    pub ended_at: u64,
+}
+
+// a best of n match, stored once it's over
+#[derive(Clone, Debug, Serialize)]
+pub struct MatchRecord {
+    pub id: u32,
+    pub lobby_id: u16,
+    pub lobby_name: String,
+    pub players: (u32, u32), // the first one played the devil in the first game
+    pub names: (String, String),
+    pub games: u32, // the most games the match could take
+    pub wins: (u32, u32),
+    pub draws: u32,
+    pub winner: Option<u32>, // None for a tie
+    pub forfeit: Option<u32>,
+    pub abandoned: bool, // the lobby was set up for other games before it was over
+    pub rated: bool,
+    pub game_ids: Vec<u32>, // in the order they were played
+    pub started_at: u64,
+    pub ended_at: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LeaderboardEntry {
+    pub rank: u32,
+    pub id: u32,
+    pub name: String,
+    pub rating: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Leaderboard {
+    pub entries: Vec<LeaderboardEntry>,
+    pub user: Option<LeaderboardEntry>, // the requesting user, None if they aren't rated yet
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RoleStats {
+    pub games: u32,
+    pub wins: u32,
+    pub losses: u32,
+}
+
+// everything in a profile that only changes when the user finishes a game
+#[derive(Clone, Debug, Serialize)]
+pub struct ProfileStats {
+    pub games: u32,
+    pub angel: RoleStats,
+    pub devil: RoleStats,
+    pub average_moves: f32, // average game length, counting both players' moves
+    pub longest_survival: u32, // most moves made as the angel in a single game
+    pub recent: Vec<GameSummary>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Profile {
+    pub id: u32,
+    pub name: String,
+    pub guest: bool,
+    pub angel_rating: Option<u32>,
+    pub devil_rating: Option<u32>,
+    pub stats: ProfileStats,
+}
+
+// shown on player cards in lobbies
+#[derive(Debug, Serialize)]
+pub struct ProfileShort {
+    pub id: u32,
+    pub name: String,
+    pub angel_rating: Option<u32>,
+    pub devil_rating: Option<u32>,
+    pub games: u32,
+    pub wins: u32,
+}
+
+#[derive(Clone)]
+pub struct LobbyRecord {
+    pub id: u16,
+    pub name: String,
+    pub settings: GameSettings,
+    pub game: Option<GameState>, // the game in progress when the lobby was last saved
+    pub series: Option<MatchState>, // the match it was part of, or the one between its games
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ChatMessage {
+    pub id: u32,
+    pub user_id: u32,
+    pub name: String, // the sender's current name
+    pub text: String,
+    pub sent_at: u64,
 }
@@ -0,0 +1,67 @@
+use rusqlite::{Connection, Result};
+
+use super::{games::GameOps, ProfileStats, RoleStats, RECENT_GAMES};
+
+pub trait ProfileOps {
+    const GET_LAST_GAME_ID: &'static str;
+    const GET_PROFILE_STATS: &'static str;
+
+    fn get_last_game_id(&self, user_id: u32) -> Result<Option<u32>>;
+    fn get_profile_stats(&self, user_id: u32) -> Result<ProfileStats>;
+}
+
+impl ProfileOps for Connection {
+    const GET_LAST_GAME_ID: &'static str =
+        "SELECT MAX(id) FROM game WHERE angel = ?1 OR devil = ?1";
+    const GET_PROFILE_STATS: &'static str = "
+SELECT
+    COUNT(*),
+    COALESCE(SUM(g.angel = ?1), 0),
+    COALESCE(SUM(g.angel = ?1 AND g.winner = 'angel'), 0),
+    COALESCE(SUM(g.angel = ?1 AND g.winner = 'devil'), 0),
+    COALESCE(SUM(g.devil = ?1), 0),
+    COALESCE(SUM(g.devil = ?1 AND g.winner = 'devil'), 0),
+    COALESCE(SUM(g.devil = ?1 AND g.winner = 'angel'), 0),
+    COALESCE(AVG(COALESCE(m.moves, 0)), 0),
+    COALESCE(MAX(CASE WHEN g.angel = ?1 THEN COALESCE(m.angel_moves, 0) END), 0)
+FROM game g
+LEFT JOIN (
+    SELECT game_id, COUNT(*) AS moves, SUM(role = 'angel') AS angel_moves
+    FROM game_move
+    GROUP BY game_id
+) m ON m.game_id = g.id
+WHERE g.angel = ?1 OR g.devil = ?1";
+
+    fn get_last_game_id(&self, user_id: u32) -> Result<Option<u32>> {
+        let mut stmt = self.prepare(Self::GET_LAST_GAME_ID)?;
+
+        stmt.query_row([user_id], |row| row.get(0))
+    }
+
+    fn get_profile_stats(&self, user_id: u32) -> Result<ProfileStats> {
+        let mut stmt = self.prepare(Self::GET_PROFILE_STATS)?;
+
+        let mut stats = stmt.query_row([user_id], |row| {
+            Ok(ProfileStats {
+                games: row.get(0)?,
+                angel: RoleStats {
+                    games: row.get(1)?,
+                    wins: row.get(2)?,
+                    losses: row.get(3)?,
+                },
+                devil: RoleStats {
+                    games: row.get(4)?,
+                    wins: row.get(5)?,
+                    losses: row.get(6)?,
+                },
+                average_moves: row.get::<_, f64>(7)? as f32,
+                longest_survival: row.get(8)?,
+                recent: vec![],
+            })
+        })?;
+
+        stats.recent = self.get_user_games(user_id, 0, RECENT_GAMES)?;
+
+        Ok(stats)
+    }
+}
@@ -0,0 +1,84 @@
+use rules::Role;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+
+use super::{Leaderboard, LeaderboardEntry};
+
+pub trait RatingOps {
+    const SET_ANGEL_RATING: &'static str;
+    const SET_DEVIL_RATING: &'static str;
+    const GET_LEADERBOARD: &'static str;
+    const GET_RANK: &'static str;
+
+    fn set_ratings(&self, angel: (u32, u32), devil: (u32, u32)) -> Result<()>;
+    fn get_leaderboard(
+        &self,
+        role: Role,
+        user_id: u32,
+        start: u32,
+        count: u32,
+    ) -> Result<Leaderboard>;
+}
+
+impl RatingOps for Connection {
+    const SET_ANGEL_RATING: &'static str = "UPDATE user SET angel_rating = ?2 WHERE id = ?1";
+    const SET_DEVIL_RATING: &'static str = "UPDATE user SET devil_rating = ?2 WHERE id = ?1";
+    // players with the same rating share the same rank
+    const GET_LEADERBOARD: &'static str = "
+WITH ranking AS (
+    SELECT id, name, rating, RANK() OVER (ORDER BY rating DESC) AS rank
+    FROM (
+        SELECT id, name, CASE ?1 WHEN 'angel' THEN angel_rating ELSE devil_rating END AS rating
+        FROM user
+    )
+    WHERE rating IS NOT NULL
+)
+SELECT rank, id, name, rating FROM ranking ORDER BY rank, id LIMIT ?3 OFFSET ?2";
+    const GET_RANK: &'static str = "
+WITH ranking AS (
+    SELECT id, name, rating, RANK() OVER (ORDER BY rating DESC) AS rank
+    FROM (
+        SELECT id, name, CASE ?1 WHEN 'angel' THEN angel_rating ELSE devil_rating END AS rating
+        FROM user
+    )
+    WHERE rating IS NOT NULL
+)
+SELECT rank, id, name, rating FROM ranking WHERE id = ?2";
+
+    fn set_ratings(&self, angel: (u32, u32), devil: (u32, u32)) -> Result<()> {
+        self.execute(Self::SET_ANGEL_RATING, params![angel.0, angel.1])?;
+        self.execute(Self::SET_DEVIL_RATING, params![devil.0, devil.1])?;
+
+        Ok(())
+    }
+
+    fn get_leaderboard(
+        &self,
+        role: Role,
+        user_id: u32,
+        start: u32,
+        count: u32,
+    ) -> Result<Leaderboard> {
+        let to_entry = |row: &rusqlite::Row| {
+            Ok(LeaderboardEntry {
+                rank: row.get(0)?,
+                id: row.get(1)?,
+                name: row.get(2)?,
+                rating: row.get(3)?,
+            })
+        };
+
+        let mut stmt = self.prepare(Self::GET_LEADERBOARD)?;
+
+        let entries = stmt
+            .query_map(params![role, start, count], to_entry)?
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut stmt = self.prepare(Self::GET_RANK)?;
+
+        let user = stmt
+            .query_row(params![role, user_id], to_entry)
+            .optional()?;
+
+        Ok(Leaderboard { entries, user })
+    }
+}
@@ -0,0 +1,71 @@
+use rusqlite::{params, Connection, Result};
+
+use super::{users::UserOps, User, SESSION_TTL};
+
+pub trait SessionOps {
+    const ADD_SESSION: &'static str;
+    const GET_SESSION: &'static str;
+    const REFRESH_SESSION: &'static str;
+    const REVOKE_SESSION: &'static str;
+    const COUNT_SESSIONS: &'static str;
+
+    fn add_session(&self, token: &str, user_id: u32) -> Result<()>;
+    fn get_session_user(&self, token: &str) -> Result<Option<User>>;
+    fn revoke_session(&self, token: &str) -> Result<()>;
+    fn has_sessions(&self, user_id: u32) -> Result<bool>;
+}
+
+impl SessionOps for Connection {
+    const ADD_SESSION: &'static str = "
+INSERT INTO session (token, user_id, expires_at)
+VALUES(?1, ?2, strftime('%s', 'now') + ?3)";
+    const GET_SESSION: &'static str =
+        "SELECT user_id, expires_at > strftime('%s', 'now') FROM session WHERE token = ?1";
+    const REFRESH_SESSION: &'static str =
+        "UPDATE session SET expires_at = strftime('%s', 'now') + ?2 WHERE token = ?1";
+    const REVOKE_SESSION: &'static str = "DELETE FROM session WHERE token = ?1";
+    const COUNT_SESSIONS: &'static str = "SELECT COUNT(*) FROM session WHERE user_id = ?1";
+
+    fn add_session(&self, token: &str, user_id: u32) -> Result<()> {
+        let mut stmt = self.prepare(Self::ADD_SESSION)?;
+
+        stmt.execute(params![token, user_id, SESSION_TTL])?;
+
+        Ok(())
+    }
+
+    // unknown tokens return QueryReturnedNoRows, expired ones are revoked and return None
+    fn get_session_user(&self, token: &str) -> Result<Option<User>> {
+        let mut stmt = self.prepare(Self::GET_SESSION)?;
+
+        let (user_id, valid): (u32, bool) =
+            stmt.query_row([token], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        if !valid {
+            self.revoke_session(token)?;
+            return Ok(None);
+        }
+
+        let mut stmt = self.prepare(Self::REFRESH_SESSION)?;
+
+        stmt.execute(params![token, SESSION_TTL])?;
+
+        Ok(Some(self.get_user_by_id(user_id)?))
+    }
+
+    fn revoke_session(&self, token: &str) -> Result<()> {
+        let mut stmt = self.prepare(Self::REVOKE_SESSION)?;
+
+        stmt.execute(params![token])?;
+
+        Ok(())
+    }
+
+    fn has_sessions(&self, user_id: u32) -> Result<bool> {
+        let mut stmt = self.prepare(Self::COUNT_SESSIONS)?;
+
+        let count: u32 = stmt.query_row(params![user_id], |row| row.get(0))?;
+
+        Ok(count > 0)
+    }
+}
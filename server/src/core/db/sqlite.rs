@@ -0,0 +1,187 @@
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rules::{EndReason, GameSettings, GameState, MatchState, Role};
+
+use super::{
+    games::GameOps, lobbies::LobbyOps, matches::MatchOps, messages::MessageOps, migrations,
+    profiles::ProfileOps, ratings::RatingOps, sessions::SessionOps, users::UserOps, ChatMessage,
+    GameRecord, GameSummary, Leaderboard, LobbyRecord, MatchRecord, ProfileStats, Result, Storage,
+    User,
+};
+
+pub struct SqliteStorage {
+    pool: Pool<SqliteConnectionManager>,
+}
+
+impl SqliteStorage {
+    pub fn open(path: &str) -> anyhow::Result<SqliteStorage> {
+        SqliteStorage::init(Pool::new(SqliteConnectionManager::file(path))?)
+    }
+
+    // every connection to an in-memory database sees a different database, so the pool can only
+    // ever hold one
+    #[cfg(test)]
+    pub fn open_in_memory() -> anyhow::Result<SqliteStorage> {
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(SqliteConnectionManager::memory())?;
+
+        SqliteStorage::init(pool)
+    }
+
+    fn init(pool: Pool<SqliteConnectionManager>) -> anyhow::Result<SqliteStorage> {
+        {
+            let mut conn = pool.get()?;
+
+            migrations::migrate(&mut conn)?;
+
+            // nobody can be connected while the server is starting
+            conn.execute("UPDATE user SET connected = 0", ())?;
+            conn.execute("DELETE FROM session", ())?;
+        }
+
+        Ok(SqliteStorage { pool })
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn get_user_by_id(&self, id: u32) -> Result<User> {
+        Ok(self.pool.get()?.get_user_by_id(id)?)
+    }
+
+    fn get_user_by_key(&self, name: &str, addr: &str) -> Result<User> {
+        Ok(self.pool.get()?.get_user_by_key(name, addr)?)
+    }
+
+    fn add_user(&self, name: &str, addr: &str) -> Result<()> {
+        Ok(self.pool.get()?.add_user(name, addr)?)
+    }
+
+    fn change_user_name(&self, id: u32, name: &str) -> Result<()> {
+        Ok(self.pool.get()?.change_user_name(id, name)?)
+    }
+
+    fn toggle_connected(&self, id: u32) -> Result<()> {
+        Ok(self.pool.get()?.toggle_connected(id)?)
+    }
+
+    fn name_taken(&self, id: u32, name: &str) -> Result<bool> {
+        Ok(self.pool.get()?.name_taken(id, name)?)
+    }
+
+    fn get_account(&self, name: &str) -> Result<User> {
+        Ok(self.pool.get()?.get_account(name)?)
+    }
+
+    fn add_account(&self, name: &str, addr: &str, password: &str) -> Result<u32> {
+        Ok(self.pool.get()?.add_account(name, addr, password)?)
+    }
+
+    fn log_in(&self, id: u32, addr: &str) -> Result<()> {
+        Ok(self.pool.get()?.log_in(id, addr)?)
+    }
+
+    fn add_session(&self, token: &str, user_id: u32) -> Result<()> {
+        Ok(self.pool.get()?.add_session(token, user_id)?)
+    }
+
+    fn get_session_user(&self, token: &str) -> Result<Option<User>> {
+        Ok(self.pool.get()?.get_session_user(token)?)
+    }
+
+    fn revoke_session(&self, token: &str) -> Result<()> {
+        Ok(self.pool.get()?.revoke_session(token)?)
+    }
+
+    fn has_sessions(&self, user_id: u32) -> Result<bool> {
+        Ok(self.pool.get()?.has_sessions(user_id)?)
+    }
+
+    fn add_game(
+        &self,
+        lobby: (u16, &str),
+        game: &GameState,
+        winner: Option<Role>,
+        reason: EndReason,
+        rate: bool,
+    ) -> Result<u32> {
+        Ok(self
+            .pool
+            .get()?
+            .add_game(lobby, game, winner, reason, rate)?)
+    }
+
+    fn get_game(&self, id: u32) -> Result<GameRecord> {
+        Ok(self.pool.get()?.get_game(id)?)
+    }
+
+    fn get_user_games(&self, user_id: u32, start: u32, count: u32) -> Result<Vec<GameSummary>> {
+        Ok(self.pool.get()?.get_user_games(user_id, start, count)?)
+    }
+
+    fn get_leaderboard(
+        &self,
+        role: Role,
+        user_id: u32,
+        start: u32,
+        count: u32,
+    ) -> Result<Leaderboard> {
+        Ok(self
+            .pool
+            .get()?
+            .get_leaderboard(role, user_id, start, count)?)
+    }
+
+    fn get_last_game_id(&self, user_id: u32) -> Result<Option<u32>> {
+        Ok(self.pool.get()?.get_last_game_id(user_id)?)
+    }
+
+    fn get_profile_stats(&self, user_id: u32) -> Result<ProfileStats> {
+        Ok(self.pool.get()?.get_profile_stats(user_id)?)
+    }
+
+    fn add_match(&self, lobby: (u16, &str), series: &MatchState) -> Result<u32> {
+        Ok(self.pool.get()?.add_match(lobby, series)?)
+    }
+
+    fn get_match(&self, id: u32) -> Result<MatchRecord> {
+        Ok(self.pool.get()?.get_match(id)?)
+    }
+
+    fn add_lobby(&self, id: u16, name: &str, settings: &GameSettings) -> Result<()> {
+        self.pool.get()?.add_lobby(id, name, settings)
+    }
+
+    fn set_lobby_settings(&self, id: u16, settings: &GameSettings) -> Result<()> {
+        self.pool.get()?.set_lobby_settings(id, settings)
+    }
+
+    fn set_lobby_game(&self, id: u16, game: Option<&GameState>) -> Result<()> {
+        self.pool.get()?.set_lobby_game(id, game)
+    }
+
+    fn set_lobby_match(&self, id: u16, series: Option<&MatchState>) -> Result<()> {
+        self.pool.get()?.set_lobby_match(id, series)
+    }
+
+    fn remove_lobby(&self, id: u16) -> Result<()> {
+        self.pool.get()?.remove_lobby(id)
+    }
+
+    fn get_lobbies(&self) -> Result<Vec<LobbyRecord>> {
+        self.pool.get()?.get_lobbies()
+    }
+
+    fn add_message(&self, lobby_id: u16, user_id: u32, text: &str) -> Result<ChatMessage> {
+        Ok(self.pool.get()?.add_message(lobby_id, user_id, text)?)
+    }
+
+    fn get_messages(
+        &self,
+        lobby_id: u16,
+        before: Option<u32>,
+        count: u32,
+    ) -> Result<Vec<ChatMessage>> {
+        Ok(self.pool.get()?.get_messages(lobby_id, before, count)?)
+    }
+}
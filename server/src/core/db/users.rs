@@ -0,0 +1,160 @@
+use rusqlite::{params, Connection, Result};
+
+use super::User;
+
+pub trait UserOps {
+    const GET_USER_BY_ID: &'static str;
+    const GET_USER_BY_KEY: &'static str;
+    const ADD_USER: &'static str;
+    const CHANGE_USER_NAME: &'static str;
+    #[allow(dead_code)]
+    const REMOVE_USER: &'static str;
+    const TOGGLE_CONNECTED: &'static str;
+    const NAME_TAKEN: &'static str;
+    const GET_ACCOUNT: &'static str;
+    const ADD_ACCOUNT: &'static str;
+    const LOG_IN: &'static str;
+
+    fn get_user_by_id(&self, id: u32) -> Result<User>;
+    fn get_user_by_key(&self, name: &str, addr: &str) -> Result<User>;
+    fn add_user(&self, name: &str, addr: &str) -> Result<()>;
+    fn change_user_name(&self, id: u32, name: &str) -> Result<()>;
+    #[allow(dead_code)]
+    fn remove_user(&self, id: u32) -> Result<()>;
+    fn toggle_connected(&self, id: u32) -> Result<()>;
+    fn name_taken(&self, id: u32, name: &str) -> Result<bool>;
+    fn get_account(&self, name: &str) -> Result<User>;
+    fn add_account(&self, name: &str, addr: &str, password: &str) -> Result<u32>;
+    fn log_in(&self, id: u32, addr: &str) -> Result<()>;
+}
+
+impl UserOps for Connection {
+    const GET_USER_BY_ID: &'static str = "SELECT * FROM user WHERE id = ?1";
+    const GET_USER_BY_KEY: &'static str =
+        "SELECT * FROM user WHERE (name, addr) = (?1, ?2) AND password IS NULL";
+    const ADD_USER: &'static str = "INSERT INTO user (name, addr, connected) VALUES(?1, ?2, 1)";
+    const CHANGE_USER_NAME: &'static str = "UPDATE user SET name = ?2 WHERE id = ?1";
+    const REMOVE_USER: &'static str = "DELETE FROM user WHERE id = ?1";
+    const TOGGLE_CONNECTED: &'static str =
+        "UPDATE user SET connected = NOT connected WHERE id = (?1)";
+    // connected isn't cleared when a client goes away without disconnecting, a live session is
+    const NAME_TAKEN: &'static str = "
+SELECT COUNT(*) FROM user
+WHERE id != ?1 AND name = ?2 COLLATE NOCASE AND (password IS NOT NULL OR id IN (
+    SELECT user_id FROM session WHERE expires_at > strftime('%s', 'now')))";
+    const GET_ACCOUNT: &'static str =
+        "SELECT * FROM user WHERE name = ?1 COLLATE NOCASE AND password IS NOT NULL";
+    const ADD_ACCOUNT: &'static str =
+        "INSERT INTO user (name, addr, connected, password) VALUES(?1, ?2, 1, ?3)";
+    const LOG_IN: &'static str = "UPDATE user SET addr = ?2, connected = 1 WHERE id = ?1";
+
+    fn get_user_by_id(&self, id: u32) -> Result<User> {
+        let mut stmt = self.prepare(Self::GET_USER_BY_ID)?;
+
+        let user = stmt.query_row([id], |row| {
+            Ok(User {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                addr: row.get(2)?,
+                angel_rating: row.get(3)?,
+                connected: row.get(4)?,
+                password: row.get(5)?,
+                devil_rating: row.get(6)?,
+            })
+        })?;
+
+        Ok(user)
+    }
+
+    fn get_user_by_key(&self, name: &str, addr: &str) -> Result<User> {
+        let mut stmt = self.prepare(Self::GET_USER_BY_KEY)?;
+
+        let user = stmt.query_row([name, addr], |row| {
+            Ok(User {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                addr: row.get(2)?,
+                angel_rating: row.get(3)?,
+                connected: row.get(4)?,
+                password: row.get(5)?,
+                devil_rating: row.get(6)?,
+            })
+        })?;
+
+        Ok(user)
+    }
+
+    fn add_user(&self, name: &str, addr: &str) -> Result<()> {
+        let mut stmt = self.prepare(Self::ADD_USER)?;
+
+        stmt.execute(params![name, addr])?;
+
+        Ok(())
+    }
+
+    fn change_user_name(&self, id: u32, name: &str) -> Result<()> {
+        let mut stmt = self.prepare(Self::CHANGE_USER_NAME)?;
+
+        stmt.execute(params![id, name])?;
+
+        Ok(())
+    }
+
+    fn remove_user(&self, id: u32) -> Result<()> {
+        let mut stmt = self.prepare(Self::REMOVE_USER)?;
+
+        stmt.execute(params![id])?;
+
+        Ok(())
+    }
+
+    fn toggle_connected(&self, id: u32) -> Result<()> {
+        let mut stmt = self.prepare(Self::TOGGLE_CONNECTED)?;
+
+        stmt.execute(params![id])?;
+
+        Ok(())
+    }
+
+    fn name_taken(&self, id: u32, name: &str) -> Result<bool> {
+        let mut stmt = self.prepare(Self::NAME_TAKEN)?;
+
+        let count: u32 = stmt.query_row(params![id, name], |row| row.get(0))?;
+
+        Ok(count > 0)
+    }
+
+    fn get_account(&self, name: &str) -> Result<User> {
+        let mut stmt = self.prepare(Self::GET_ACCOUNT)?;
+
+        let user = stmt.query_row([name], |row| {
+            Ok(User {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                addr: row.get(2)?,
+                angel_rating: row.get(3)?,
+                connected: row.get(4)?,
+                password: row.get(5)?,
+                devil_rating: row.get(6)?,
+            })
+        })?;
+
+        Ok(user)
+    }
+
+    fn add_account(&self, name: &str, addr: &str, password: &str) -> Result<u32> {
+        let mut stmt = self.prepare(Self::ADD_ACCOUNT)?;
+
+        stmt.execute(params![name, addr, password])?;
+
+        Ok(self.last_insert_rowid() as u32)
+    }
+
+    fn log_in(&self, id: u32, addr: &str) -> Result<()> {
+        let mut stmt = self.prepare(Self::LOG_IN)?;
+
+        stmt.execute(params![id, addr])?;
+
+        Ok(())
+    }
+}
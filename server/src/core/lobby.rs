@@ -1,14 +1,22 @@
 use std::net::{SocketAddr, TcpStream};
 use std::sync::{Arc, Mutex};
+use std::thread;
 
 use anyhow::{anyhow, Result};
 
+use super::db::{Db, LobbyRecord};
+use super::registry::LobbyHandle;
 use super::request_handlers::{
-    BecomeRoleRequest, ChangedNameRequest, CloseLobbyRequest, GetLobbyStateRequest, InvalidRequest,
-    JoinLobbyRequest, LeaveLobbyRequest, MakeHostRequest, MakeMoveRequest, PingRequest,
-    StartGameRequest, SendMessageRequest,
+    AcceptDrawRequest, AnswerTakebackRequest, BecomeRoleRequest, ChangeSettingsRequest,
+    CloseLobbyRequest, GetChatHistoryRequest, GetLobbyStateRequest, InvalidRequest,
+    JoinLobbyRequest, LeaveLobbyRequest, MakeHostRequest, MakeMoveRequest, OfferDrawRequest,
+    PingRequest, RematchRequest, RequestTakebackRequest, ResignRequest, SendMessageRequest,
+    StartGameRequest, SwitchLobbyRequest,
+};
+use super::status::LobbyStatus;
+use super::types::{
+    BoolMutex, LobbyAddr, LobbyId, LobbyName, LobbyVec, Registry, Settings, Status, UsersVec,
 };
-use super::types::{Game, LobbyId, LobbyName, UsersVec};
 use super::{RequestHandler, RequestQueueItem, ServerCore};
 use network::{request, SendRecv, Type};
 
@@ -17,7 +25,10 @@ pub struct Lobby {
     pub id: LobbyId,
     pub name: LobbyName,
     pub users: UsersVec,
-    pub game: Game,
+    pub settings: Settings,
+    pub status: Status,
+    pub registry: Registry,
+    server_running: BoolMutex, // the main server's, false while it shuts down
 }
 
 impl RequestHandler for Lobby {
@@ -38,9 +49,12 @@ impl RequestHandler for Lobby {
                 Ok(buf) => Box::new(GetLobbyStateRequest::new(
                     stream,
                     buf,
+                    Arc::clone(&self.id),
                     Arc::clone(&self.name),
                     Arc::clone(&self.users),
-                    Arc::clone(&self.game),
+                    Arc::clone(&self.settings),
+                    Arc::clone(&self.status),
+                    Arc::clone(&self.server.db),
                 )),
                 Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
             },
@@ -48,21 +62,40 @@ impl RequestHandler for Lobby {
                 Ok(buf) => Box::new(JoinLobbyRequest::new(
                     stream,
                     buf,
+                    Arc::clone(&self.id),
                     Arc::clone(&self.name),
                     Arc::clone(&self.users),
-                    Arc::clone(&self.game),
+                    Arc::clone(&self.settings),
+                    Arc::clone(&self.status),
                     Arc::clone(&self.server.running),
-                    self.server.db_pool.clone(),
+                    Arc::clone(&self.registry),
+                    Arc::clone(&self.server.db),
                 )),
                 Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
             },
-            Type::LeaveLobby => match bincode::deserialize(&buf) {
-                Ok(buf) => Box::new(LeaveLobbyRequest::new(
+            Type::SwitchLobby => match bincode::deserialize(&buf) {
+                Ok(buf) => Box::new(SwitchLobbyRequest::new(
                     stream,
                     buf,
+                    Arc::clone(&self.id),
+                    Arc::clone(&self.name),
                     Arc::clone(&self.users),
+                    Arc::clone(&self.settings),
+                    Arc::clone(&self.status),
                     Arc::clone(&self.server.running),
-                    self.server.db_pool.clone(),
+                    Arc::clone(&self.registry),
+                    Arc::clone(&self.server.db),
+                )),
+                Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
+            },
+            Type::LeaveLobby => match bincode::deserialize(&buf) {
+                Ok(buf) => Box::new(LeaveLobbyRequest::new(
+                    stream,
+                    buf,
+                    Arc::clone(&self.id),
+                    self.handle(),
+                    Arc::clone(&self.registry),
+                    Arc::clone(&self.server.db),
                 )),
                 Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
             },
@@ -72,7 +105,7 @@ impl RequestHandler for Lobby {
                     buf,
                     Arc::clone(&self.users),
                     Arc::clone(&self.server.running),
-                    self.server.db_pool.clone(),
+                    Arc::clone(&self.server.db),
                 )),
                 Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
             },
@@ -80,10 +113,12 @@ impl RequestHandler for Lobby {
                 Ok(buf) => Box::new(MakeHostRequest::new(
                     stream,
                     buf,
+                    Arc::clone(&self.id),
+                    Arc::clone(&self.name),
                     Arc::clone(&self.users),
-                    Arc::clone(&self.game),
+                    Arc::clone(&self.status),
                     Arc::clone(&self.server.running),
-                    self.server.db_pool.clone(),
+                    Arc::clone(&self.server.db),
                 )),
                 Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
             },
@@ -91,10 +126,26 @@ impl RequestHandler for Lobby {
                 Ok(buf) => Box::new(BecomeRoleRequest::new(
                     stream,
                     buf,
+                    Arc::clone(&self.id),
+                    Arc::clone(&self.name),
                     Arc::clone(&self.users),
-                    Arc::clone(&self.game),
+                    Arc::clone(&self.status),
                     Arc::clone(&self.server.running),
-                    self.server.db_pool.clone(),
+                    Arc::clone(&self.server.db),
+                )),
+                Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
+            },
+            Type::ChangeSettings => match bincode::deserialize(&buf) {
+                Ok(buf) => Box::new(ChangeSettingsRequest::new(
+                    stream,
+                    buf,
+                    Arc::clone(&self.id),
+                    Arc::clone(&self.name),
+                    Arc::clone(&self.users),
+                    Arc::clone(&self.settings),
+                    Arc::clone(&self.status),
+                    Arc::clone(&self.server.running),
+                    Arc::clone(&self.server.db),
                 )),
                 Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
             },
@@ -102,19 +153,20 @@ impl RequestHandler for Lobby {
                 Ok(buf) => Box::new(SendMessageRequest::new(
                     stream,
                     buf,
+                    Arc::clone(&self.id),
                     Arc::clone(&self.users),
                     Arc::clone(&self.server.running),
-                    self.server.db_pool.clone(),
+                    Arc::clone(&self.server.db),
                 )),
                 Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
             },
-            Type::ChangedName => match bincode::deserialize(&buf) {
-                Ok(buf) => Box::new(ChangedNameRequest::new(
+            Type::GetChatHistory => match bincode::deserialize(&buf) {
+                Ok(buf) => Box::new(GetChatHistoryRequest::new(
                     stream,
                     buf,
+                    Arc::clone(&self.id),
                     Arc::clone(&self.users),
-                    Arc::clone(&self.server.running),
-                    self.server.db_pool.clone(),
+                    Arc::clone(&self.server.db),
                 )),
                 Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
             },
@@ -122,21 +174,100 @@ impl RequestHandler for Lobby {
                 Ok(buf) => Box::new(StartGameRequest::new(
                     stream,
                     buf,
+                    Arc::clone(&self.id),
+                    Arc::clone(&self.name),
                     Arc::clone(&self.users),
-                    Arc::clone(&self.game),
+                    Arc::clone(&self.settings),
+                    Arc::clone(&self.status),
                     Arc::clone(&self.server.running),
-                    self.server.db_pool.clone(),
+                    Arc::clone(&self.server.db),
                 )),
                 Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
             },
             Type::MakeMove => match bincode::deserialize(&buf) {
                 Ok(buf) => Box::new(MakeMoveRequest::new(
+                    stream,
+                    buf,
+                    Arc::clone(&self.id),
+                    Arc::clone(&self.name),
+                    Arc::clone(&self.users),
+                    Arc::clone(&self.status),
+                    Arc::clone(&self.server.running),
+                    Arc::clone(&self.server.db),
+                )),
+                Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
+            },
+            Type::Resign => match bincode::deserialize(&buf) {
+                Ok(buf) => Box::new(ResignRequest::new(
+                    stream,
+                    buf,
+                    Arc::clone(&self.id),
+                    Arc::clone(&self.name),
+                    Arc::clone(&self.users),
+                    Arc::clone(&self.status),
+                    Arc::clone(&self.server.running),
+                    Arc::clone(&self.server.db),
+                )),
+                Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
+            },
+            Type::OfferDraw => match bincode::deserialize(&buf) {
+                Ok(buf) => Box::new(OfferDrawRequest::new(
+                    stream,
+                    buf,
+                    Arc::clone(&self.users),
+                    Arc::clone(&self.status),
+                    Arc::clone(&self.server.running),
+                    Arc::clone(&self.server.db),
+                )),
+                Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
+            },
+            Type::AcceptDraw => match bincode::deserialize(&buf) {
+                Ok(buf) => Box::new(AcceptDrawRequest::new(
+                    stream,
+                    buf,
+                    Arc::clone(&self.id),
+                    Arc::clone(&self.name),
+                    Arc::clone(&self.users),
+                    Arc::clone(&self.status),
+                    Arc::clone(&self.server.running),
+                    Arc::clone(&self.server.db),
+                )),
+                Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
+            },
+            Type::RequestTakeback => match bincode::deserialize(&buf) {
+                Ok(buf) => Box::new(RequestTakebackRequest::new(
                     stream,
                     buf,
                     Arc::clone(&self.users),
-                    Arc::clone(&self.game),
+                    Arc::clone(&self.status),
                     Arc::clone(&self.server.running),
-                    self.server.db_pool.clone(),
+                    Arc::clone(&self.server.db),
+                )),
+                Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
+            },
+            Type::AnswerTakeback => match bincode::deserialize(&buf) {
+                Ok(buf) => Box::new(AnswerTakebackRequest::new(
+                    stream,
+                    buf,
+                    Arc::clone(&self.id),
+                    Arc::clone(&self.users),
+                    Arc::clone(&self.status),
+                    Arc::clone(&self.server.running),
+                    Arc::clone(&self.server.db),
+                )),
+                Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
+            },
+            Type::Rematch => match bincode::deserialize(&buf) {
+                Ok(buf) => Box::new(RematchRequest::new(
+                    stream,
+                    buf,
+                    Arc::clone(&self.id),
+                    Arc::clone(&self.name),
+                    Arc::clone(&self.users),
+                    Arc::clone(&self.settings),
+                    Arc::clone(&self.status),
+                    Arc::clone(&self.server.running),
+                    Arc::clone(&self.server.db),
                 )),
                 Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
             },
@@ -146,15 +277,24 @@ impl RequestHandler for Lobby {
 }
 
 impl Lobby {
-    pub fn new(addr: &str, id: u16, name: String) -> Result<Lobby> {
-        let server = ServerCore::new(addr)?;
+    pub fn new(
+        addr: &str,
+        record: LobbyRecord,
+        registry: Registry,
+        db: Db,
+        server_running: BoolMutex,
+    ) -> Result<Lobby> {
+        let server = ServerCore::new(addr, db)?;
 
         Ok(Lobby {
             server,
-            id: Arc::new(Mutex::new(id)),
-            name: Arc::new(Mutex::new(name)),
+            id: Arc::new(Mutex::new(record.id)),
+            name: Arc::new(Mutex::new(record.name)),
             users: Arc::new(Mutex::new(vec![])),
-            game: Arc::new(Mutex::new(None)),
+            settings: Arc::new(Mutex::new(record.settings)),
+            status: Arc::new(Mutex::new(LobbyStatus::restore(record.game, record.series))),
+            registry,
+            server_running,
         })
     }
 
@@ -167,10 +307,58 @@ impl Lobby {
         let addr = self.server.get_addr()?;
         Ok(addr)
     }
+
+    pub fn handle(&self) -> LobbyHandle {
+        LobbyHandle {
+            name: Arc::clone(&self.name),
+            users: Arc::clone(&self.users),
+            status: Arc::clone(&self.status),
+            running: Arc::clone(&self.server.running),
+        }
+    }
+}
+
+// runs the lobby on its own thread and makes it known to the server
+pub fn start_lobby(lobby: Lobby, lobbies: &LobbyVec, registry: &Registry) -> Result<LobbyAddr> {
+    let id = { *lobby.id.lock().unwrap() };
+    let (addr, running) = (lobby.get_addr()?, Arc::clone(&lobby.server.running));
+
+    {
+        let mut registry = registry.lock().unwrap();
+        registry.add_lobby(id, lobby.handle());
+    }
+
+    let handle = thread::spawn(move || {
+        lobby.start().unwrap();
+    });
+
+    println!("lobby {} started", id);
+
+    {
+        let mut lobbies = lobbies.lock().unwrap();
+        lobbies.push((id, addr, running, handle));
+    }
+
+    Ok(LobbyAddr { id, addr })
 }
 
 impl Drop for Lobby {
     fn drop(&mut self) {
+        let id = { *self.id.lock().unwrap() };
+
+        {
+            let mut registry = self.registry.lock().unwrap();
+            registry.remove_lobby(id);
+        }
+
+        // lobbies stopped by a server shutdown are kept so they can be restored, the others were
+        // closed for good
+        if *self.server_running.lock().unwrap() {
+            if let Err(e) = self.server.db.remove_lobby(id) {
+                println!("couldn't remove lobby {id}: {e:?}");
+            }
+        }
+
         let mut users = self.users.lock().unwrap();
 
         while let Some(user) = users.pop() {
@@ -1,8 +1,11 @@
+mod auth;
 pub mod db;
-mod game;
 mod lobby;
+mod rating;
+mod registry;
 mod request_handlers;
 mod server;
+mod status;
 mod types;
 
 pub use server::*;
@@ -16,9 +19,7 @@ use std::thread;
 
 use anyhow::Result;
 
-use r2d2::Pool;
-use r2d2_sqlite::SqliteConnectionManager;
-
+use db::Db;
 use request_handlers::ExitRequest;
 
 use types::{BoolMutex, HandleVec, RequestQueue, RequestQueueItem};
@@ -31,21 +32,17 @@ pub trait RequestHandler {
 
 pub struct ServerCore {
     pub running: BoolMutex,
-    pub db_pool: Pool<SqliteConnectionManager>,
+    pub db: Db,
     requests: RequestQueue,
     server: TcpListener,
     handles: HandleVec,
 }
 
 impl ServerCore {
-    pub fn new(addr: &str) -> Result<ServerCore> {
+    pub fn new(addr: &str, db: Db) -> Result<ServerCore> {
         // bool used to indicate to all threads if server should stop
         let running = Arc::new(Mutex::new(true));
 
-        // db connection pool
-        let manager = SqliteConnectionManager::file("db.db");
-        let db_pool = Pool::new(manager)?;
-
         // requests queue
         let requests = Arc::new((Mutex::new(vec![]), Condvar::new()));
 
@@ -75,7 +72,7 @@ impl ServerCore {
                     let mut req: RequestQueueItem = {
                         let mut requests = lock.lock().unwrap();
 
-                        while requests.len() == 0 {
+                        while requests.is_empty() {
                             requests = cond.wait(requests).unwrap();
                         }
 
@@ -95,7 +92,7 @@ impl ServerCore {
         // return newly created server
         Ok(ServerCore {
             running,
-            db_pool,
+            db,
             requests,
             server,
             handles: RefCell::new(handles),
@@ -0,0 +1,37 @@
+// elo ratings, angels and devils are rated separately because the two roles aren't symmetric so a
+// player's strength as one says little about the other
+
+pub const INITIAL_RATING: u32 = 1500;
+
+const K: f64 = 32.0;
+
+// angel_score is 1 if the angel won and 0 if the devil won, returns the new (angel, devil) ratings
+pub fn update(angel: u32, devil: u32, angel_score: f64) -> (u32, u32) {
+    let expected = 1.0 / (1.0 + 10f64.powf((devil as f64 - angel as f64) / 400.0));
+    let delta = (K * (angel_score - expected)).round() as i64;
+
+    (
+        (angel as i64 + delta).max(0) as u32,
+        (devil as i64 - delta).max(0) as u32,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_ratings() {
+        assert_eq!(update(1500, 1500, 1.0), (1516, 1484));
+        assert_eq!(update(1500, 1500, 0.0), (1484, 1516));
+
+        // beating a much stronger opponent is worth more than beating a weaker one
+        let (upset, _) = update(1200, 1800, 1.0);
+        let (expected, _) = update(1800, 1200, 1.0);
+        assert!(upset - 1200 > expected - 1800);
+
+        // points are only moved between the players
+        let (angel, devil) = update(1620, 1475, 0.0);
+        assert_eq!(angel + devil, 1620 + 1475);
+    }
+}
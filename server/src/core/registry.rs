@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use super::types::{BoolMutex, LobbyName, Status, UsersVec};
+
+// the parts of a lobby that requests handled by other lobbies and the main server need
+#[derive(Clone)]
+pub struct LobbyHandle {
+    pub name: LobbyName,
+    pub users: UsersVec,
+    pub status: Status,
+    pub running: BoolMutex,
+}
+
+// keeps track of the lobby each user is in, a user can be a member of at most one lobby
+//
+// lock order: the registry must always be locked before the users of any lobby, otherwise two
+// lobbies could end up waiting on each other
+#[derive(Default)]
+pub struct LobbyRegistry {
+    lobbies: HashMap<u16, LobbyHandle>,
+    members: HashMap<u32, u16>,
+}
+
+impl LobbyRegistry {
+    pub fn add_lobby(&mut self, id: u16, lobby: LobbyHandle) {
+        self.lobbies.insert(id, lobby);
+    }
+
+    pub fn remove_lobby(&mut self, id: u16) {
+        self.lobbies.remove(&id);
+        self.members.retain(|_, lobby_id| *lobby_id != id);
+    }
+
+    pub fn get_lobby(&self, id: u16) -> Option<LobbyHandle> {
+        self.lobbies.get(&id).cloned()
+    }
+
+    // returns the lobby the user is in, entries are checked against the lobby's users because
+    // users that lose connection are removed from lobbies without going through the registry
+    pub fn lobby_of(&mut self, user_id: u32) -> Option<u16> {
+        let lobby_id = *self.members.get(&user_id)?;
+
+        let member = match self.lobbies.get(&lobby_id) {
+            Some(lobby) => {
+                *lobby.running.lock().unwrap()
+                    && lobby
+                        .users
+                        .lock()
+                        .unwrap()
+                        .iter()
+                        .any(|user| user.id == user_id)
+            }
+            None => false,
+        };
+
+        if !member {
+            self.members.remove(&user_id);
+            return None;
+        }
+
+        Some(lobby_id)
+    }
+
+    pub fn join(&mut self, user_id: u32, lobby_id: u16) {
+        self.members.insert(user_id, lobby_id);
+    }
+
+    pub fn leave(&mut self, user_id: u32) {
+        self.members.remove(&user_id);
+    }
+}
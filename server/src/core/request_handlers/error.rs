@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::core::db::StorageError;
+
 #[derive(Error, Debug)]
 pub enum ServerError {
     #[error("internal error")]
@@ -9,10 +11,7 @@ pub enum ServerError {
     InternalIo(#[from] std::io::Error),
 
     #[error("internal error")]
-    InternalR2d2(#[from] r2d2::Error),
-
-    #[error("internal error")]
-    InternalRusqlite(#[from] rusqlite::Error),
+    InternalStorage(#[from] StorageError),
 
     #[error("internal error")]
     InternalAddrParseError(#[from] std::net::AddrParseError),
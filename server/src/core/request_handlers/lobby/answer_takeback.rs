@@ -0,0 +1,132 @@
+use std::net::TcpStream;
+
+use anyhow::{anyhow, Result};
+use network::{SendRecv, Type};
+
+use crate::core::{
+    db::{Db, StorageError},
+    request_handlers::{dispatch, error_check},
+    status::Action,
+    types::{BoolMutex, LobbyId, Status, UsersVec},
+};
+
+use super::{error::ServerError, Request};
+
+pub struct AnswerTakebackRequest {
+    stream: TcpStream,
+    token: String,
+    accept: bool,
+    lobby_id: LobbyId,
+    users: UsersVec,
+    status: Status,
+    running: BoolMutex,
+    db: Db,
+}
+
+impl AnswerTakebackRequest {
+    pub fn new(
+        stream: TcpStream,
+        (token, accept): (String, bool),
+        lobby_id: LobbyId,
+        users: UsersVec,
+        status: Status,
+        running: BoolMutex,
+        db: Db,
+    ) -> AnswerTakebackRequest {
+        AnswerTakebackRequest {
+            stream,
+            token,
+            accept,
+            lobby_id,
+            users,
+            status,
+            running,
+            db,
+        }
+    }
+
+    fn handler(&self) -> Result<(), ServerError> {
+        let db_user = match self.db.get_session_user(&self.token) {
+            Ok(Some(db_user)) => db_user,
+            Ok(None) => return Err(ServerError::ApiNotConnected),
+            Err(StorageError::NotFound) => {
+                return Err(ServerError::Api {
+                    message: "invalid session token".to_string(),
+                })
+            }
+            Err(e) => return Err(ServerError::InternalStorage(e)),
+        };
+
+        let mut status = self.status.lock().unwrap();
+
+        if let Err(message) = status.check(&Action::MakeMove) {
+            return Err(ServerError::Api {
+                message: message.to_string(),
+            });
+        }
+
+        let game = status.game_mut().unwrap();
+
+        let role = match game.role_of(db_user.id) {
+            Some(role) => role,
+            None => {
+                return Err(ServerError::Api {
+                    message: "you are not playing".to_string(),
+                })
+            }
+        };
+
+        // only the opponent of the player that asked can answer
+        let requester = role.opponent();
+
+        if game.takeback_request != Some(requester) {
+            return Err(ServerError::Api {
+                message: "no takeback was requested".to_string(),
+            });
+        }
+
+        game.takeback_request = None;
+
+        let mut users = self.users.lock().unwrap();
+
+        if !self.accept {
+            if let Err(ServerError::InternalShutDown) =
+                dispatch(&mut users, vec![(Type::TakebackDeclined, &role)], |_| {})
+            {
+                let mut running = self.running.lock().unwrap();
+                *running = false;
+            }
+
+            return Ok(());
+        }
+
+        // the time spent until now is charged to the player that was to move
+        game.stop_clock();
+        game.take_back(requester);
+
+        // everyone in the lobby gets the whole rewound game, spectators included
+        if let Err(ServerError::InternalShutDown) =
+            dispatch(&mut users, vec![(Type::GameRewound, &*game)], |_| {})
+        {
+            let mut running = self.running.lock().unwrap();
+            *running = false;
+        }
+
+        let lobby_id = { *self.lobby_id.lock().unwrap() };
+        self.db.set_lobby_game(lobby_id, status.game())?;
+
+        Ok(())
+    }
+}
+
+impl Request for AnswerTakebackRequest {
+    fn execute(&mut self) -> Result<()> {
+        let (res_type, res) = error_check(self.handler())?;
+
+        if let Err(e) = self.stream.send(res_type, &res) {
+            return Err(anyhow!(format!("couldn't send: {e:?}")));
+        }
+
+        Ok(())
+    }
+}
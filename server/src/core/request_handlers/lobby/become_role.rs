@@ -2,64 +2,69 @@ use std::net::TcpStream;
 
 use anyhow::{anyhow, Result};
 use network::{SendRecv, Type};
-use r2d2::Pool;
-use r2d2_sqlite::SqliteConnectionManager;
 
 use crate::core::{
-    db::UserOps,
-    request_handlers::{dispatch, error_check},
-    types::{BoolMutex, Game, UserInfoShort, UserType, UsersVec},
+    db::{Db, StorageError},
+    request_handlers::{dispatch, dispatch_phases, error_check},
+    status::Action,
+    types::{BoolMutex, LobbyId, LobbyName, Status, UserInfoShort, UserType, UsersVec},
 };
 
-use super::{error::ServerError, Request};
+use super::{error::ServerError, make_move::abandon_match, Request};
 
 pub struct BecomeRoleRequest {
     stream: TcpStream,
-    user_id: u32,
+    token: String,
     new_role: UserType,
+    lobby_id: LobbyId,
+    lobby_name: LobbyName,
     users: UsersVec,
-    game: Game,
+    status: Status,
     running: BoolMutex,
-    db_pool: Pool<SqliteConnectionManager>,
+    db: Db,
 }
 
 impl BecomeRoleRequest {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         stream: TcpStream,
-        data: (u32, UserType),
+        data: (String, UserType),
+        lobby_id: LobbyId,
+        lobby_name: LobbyName,
         users: UsersVec,
-        game: Game,
+        status: Status,
         running: BoolMutex,
-        db_pool: Pool<SqliteConnectionManager>,
+        db: Db,
     ) -> BecomeRoleRequest {
         BecomeRoleRequest {
             stream,
-            user_id: data.0,
+            token: data.0,
             new_role: data.1,
+            lobby_id,
+            lobby_name,
             users,
-            game,
+            status,
             running,
-            db_pool,
+            db,
         }
     }
 
     fn handler(&self) -> Result<(), ServerError> {
-        let conn = self.db_pool.get()?;
-
-        let _ = match conn.is_connected(self.user_id) {
+        let db_user = match self.db.get_session_user(&self.token) {
             Ok(Some(db_user)) => db_user,
             Ok(None) => return Err(ServerError::ApiNotConnected),
-            Err(rusqlite::Error::QueryReturnedNoRows) => {
+            Err(StorageError::NotFound) => {
                 return Err(ServerError::Api {
-                    message: "invalid id".to_string(),
+                    message: "invalid session token".to_string(),
                 })
             }
-            Err(e) => return Err(ServerError::InternalRusqlite(e)),
+            Err(e) => return Err(ServerError::InternalStorage(e)),
         };
 
+        let mut status = self.status.lock().unwrap();
         let mut users = self.users.lock().unwrap();
 
-        let mut new_user = match users.iter().find(|user| user.id == self.user_id) {
+        let mut new_user = match users.iter().find(|user| user.id == db_user.id) {
             Some(user) => UserInfoShort::from(user),
             None => {
                 return Err(ServerError::Api {
@@ -68,12 +73,10 @@ impl BecomeRoleRequest {
             }
         };
 
-        {
-            if self.game.lock().unwrap().is_some() {
-                return Err(ServerError::Api {
-                    message: "cannot change roles while a game is going on".to_string(),
-                });
-            }
+        if let Err(message) = status.check(&Action::ChangeRoles) {
+            return Err(ServerError::Api {
+                message: message.to_string(),
+            });
         }
 
         if new_user.user_type == UserType::Host {
@@ -106,6 +109,28 @@ impl BecomeRoleRequest {
 
         new_user.user_type = self.new_role;
 
+        let lobby_id = { *self.lobby_id.lock().unwrap() };
+        let lobby_name = { self.lobby_name.lock().unwrap().clone() };
+        abandon_match(
+            &status,
+            &Action::ChangeRoles,
+            &mut users,
+            &self.running,
+            &self.db,
+            (lobby_id, &lobby_name),
+        )?;
+
+        let changes = status
+            .apply(Action::ChangeRoles)
+            .map_err(|message| ServerError::Api {
+                message: message.to_string(),
+            })?;
+
+        if let Err(ServerError::InternalShutDown) = dispatch_phases(&mut users, &changes) {
+            let mut running = self.running.lock().unwrap();
+            *running = false;
+        }
+
         if let Err(ServerError::InternalShutDown) = dispatch(
             &mut users,
             vec![(Type::PlayerUpdated, &new_user)],
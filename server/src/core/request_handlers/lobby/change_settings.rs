@@ -0,0 +1,240 @@
+use std::net::TcpStream;
+
+use anyhow::{anyhow, Result};
+use network::{SendRecv, Type};
+use rules::{
+    Board, GameSettings, Outline, TimeControl, Topology, MAX_ANGEL_POWER, MAX_GRID_SIZE,
+    MAX_INCREMENT, MAX_MATCH_GAMES, MAX_TIME_CONTROL, MAX_WALLS_PER_TURN, MAX_WALL_DENSITY,
+    MIN_GRID_SIZE, MIN_TIME_CONTROL,
+};
+
+use crate::core::{
+    db::{Db, StorageError},
+    request_handlers::{dispatch, dispatch_phases, error_check},
+    status::Action,
+    types::{BoolMutex, LobbyId, LobbyName, Settings, Status, UserType, UsersVec},
+};
+
+use super::{error::ServerError, make_move::abandon_match, Request};
+
+pub struct ChangeSettingsRequest {
+    stream: TcpStream,
+    token: String,
+    new_settings: GameSettings,
+    lobby_id: LobbyId,
+    lobby_name: LobbyName,
+    users: UsersVec,
+    settings: Settings,
+    status: Status,
+    running: BoolMutex,
+    db: Db,
+}
+
+impl ChangeSettingsRequest {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        stream: TcpStream,
+        data: (String, GameSettings),
+        lobby_id: LobbyId,
+        lobby_name: LobbyName,
+        users: UsersVec,
+        settings: Settings,
+        status: Status,
+        running: BoolMutex,
+        db: Db,
+    ) -> ChangeSettingsRequest {
+        ChangeSettingsRequest {
+            stream,
+            token: data.0,
+            new_settings: data.1,
+            lobby_id,
+            lobby_name,
+            users,
+            settings,
+            status,
+            running,
+            db,
+        }
+    }
+
+    fn handler(&self) -> Result<(), ServerError> {
+        let db_user = match self.db.get_session_user(&self.token) {
+            Ok(Some(db_user)) => db_user,
+            Ok(None) => return Err(ServerError::ApiNotConnected),
+            Err(StorageError::NotFound) => {
+                return Err(ServerError::Api {
+                    message: "invalid session token".to_string(),
+                })
+            }
+            Err(e) => return Err(ServerError::InternalStorage(e)),
+        };
+
+        let mut status = self.status.lock().unwrap();
+        let mut users = self.users.lock().unwrap();
+
+        match users.iter().find(|user| user.id == db_user.id) {
+            Some(user) if user.user_type == UserType::Host => {}
+            Some(_) => {
+                return Err(ServerError::Api {
+                    message: "you are not the host".to_string(),
+                })
+            }
+            None => {
+                return Err(ServerError::Api {
+                    message: "you are not connected to this lobby".to_string(),
+                })
+            }
+        }
+
+        if let Err(message) = status.check(&Action::ChangeSettings) {
+            return Err(ServerError::Api {
+                message: message.to_string(),
+            });
+        }
+
+        let grid_size = self.new_settings.grid_size;
+        if !(MIN_GRID_SIZE..=MAX_GRID_SIZE).contains(&grid_size) {
+            return Err(ServerError::Api {
+                message: format!("grid size should be between {MIN_GRID_SIZE} and {MAX_GRID_SIZE}"),
+            });
+        }
+
+        if self.new_settings.wall_density > MAX_WALL_DENSITY {
+            return Err(ServerError::Api {
+                message: format!("wall density can be at most {MAX_WALL_DENSITY}"),
+            });
+        }
+
+        if !(1..=MAX_ANGEL_POWER).contains(&self.new_settings.angel_power) {
+            return Err(ServerError::Api {
+                message: format!("angel power should be between 1 and {MAX_ANGEL_POWER}"),
+            });
+        }
+
+        if !(1..=MAX_WALLS_PER_TURN).contains(&self.new_settings.walls_per_turn) {
+            return Err(ServerError::Api {
+                message: format!("walls per turn should be between 1 and {MAX_WALLS_PER_TURN}"),
+            });
+        }
+
+        if self.new_settings.wall_budget == Some(0) {
+            return Err(ServerError::Api {
+                message: "wall budget should be at least 1".to_string(),
+            });
+        }
+
+        if self.new_settings.outline == Outline::Hexagon
+            && self.new_settings.topology != Topology::Hex
+        {
+            return Err(ServerError::Api {
+                message: "a hexagonal outline needs a hex topology".to_string(),
+            });
+        }
+
+        // the left and right edges of a cylinder are joined, so they can't be exits
+        let exits = self.new_settings.exits;
+        let open = match self.new_settings.outline {
+            Outline::Cylinder => exits.top || exits.bottom,
+            _ => exits.top || exits.bottom || exits.left || exits.right,
+        };
+        if !open {
+            return Err(ServerError::Api {
+                message: "the board needs at least one exit".to_string(),
+            });
+        }
+
+        // the start of a board has a set number of neighbours, so only so many ways out
+        let board = Board::new(
+            grid_size,
+            self.new_settings.topology,
+            self.new_settings.outline,
+            exits,
+        );
+        let difficulty = self.new_settings.difficulty;
+        if !difficulty.reachable(&board, self.new_settings.walls_per_turn) {
+            return Err(ServerError::Api {
+                message: format!(
+                    "a {} board can't be made against {} walls per turn",
+                    difficulty.as_str(),
+                    self.new_settings.walls_per_turn
+                ),
+            });
+        }
+
+        let (seconds, increment) = match self.new_settings.time_control {
+            TimeControl::Unlimited => (MIN_TIME_CONTROL, 0),
+            TimeControl::Total { seconds, increment } => (seconds, increment),
+            TimeControl::PerMove { seconds } => (seconds, 0),
+        };
+        if !(MIN_TIME_CONTROL..=MAX_TIME_CONTROL).contains(&seconds) {
+            return Err(ServerError::Api {
+                message: format!(
+                    "time should be between {MIN_TIME_CONTROL} and {MAX_TIME_CONTROL} seconds"
+                ),
+            });
+        }
+        if increment > MAX_INCREMENT {
+            return Err(ServerError::Api {
+                message: format!("increment can be at most {MAX_INCREMENT} seconds"),
+            });
+        }
+
+        if !(1..=MAX_MATCH_GAMES).contains(&self.new_settings.match_games) {
+            return Err(ServerError::Api {
+                message: format!("a match should have between 1 and {MAX_MATCH_GAMES} games"),
+            });
+        }
+
+        let lobby_id = { *self.lobby_id.lock().unwrap() };
+        let lobby_name = { self.lobby_name.lock().unwrap().clone() };
+        abandon_match(
+            &status,
+            &Action::ChangeSettings,
+            &mut users,
+            &self.running,
+            &self.db,
+            (lobby_id, &lobby_name),
+        )?;
+
+        let changes = status
+            .apply(Action::ChangeSettings)
+            .map_err(|message| ServerError::Api {
+                message: message.to_string(),
+            })?;
+
+        if let Err(ServerError::InternalShutDown) = dispatch_phases(&mut users, &changes) {
+            let mut running = self.running.lock().unwrap();
+            *running = false;
+        }
+
+        self.db.set_lobby_settings(lobby_id, &self.new_settings)?;
+
+        {
+            let mut settings = self.settings.lock().unwrap();
+            *settings = self.new_settings.clone();
+        }
+
+        if let Err(ServerError::InternalShutDown) = dispatch(
+            &mut users,
+            vec![(Type::SettingsChanged, &self.new_settings)],
+            |_| {},
+        ) {
+            let mut running = self.running.lock().unwrap();
+            *running = false;
+        }
+
+        Ok(())
+    }
+}
+
+impl Request for ChangeSettingsRequest {
+    fn execute(&mut self) -> Result<()> {
+        let (res_type, res) = error_check(self.handler())?;
+
+        if let Err(e) = self.stream.send(res_type, &res) {
+            return Err(anyhow!(format!("couldn't send: {e:?}")));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+use std::{sync::Arc, thread, time::Duration};
+
+use rules::{EndReason, Role};
+
+use crate::core::{
+    db::Db,
+    types::{BoolMutex, LobbyId, LobbyName, Status, UsersVec},
+};
+
+use super::{error::ServerError, make_move::finish_game};
+
+// the longest the watcher sleeps, so it notices the lobby closing
+const CLOCK_TICK: u64 = 1000;
+
+// ends the game once the player to move runs out of time, the clocks are only checked on moves
+// otherwise, so every game with a time control gets a watcher
+pub fn watch_clock(
+    users: &UsersVec,
+    status: &Status,
+    running: &BoolMutex,
+    db: &Db,
+    lobby_id: &LobbyId,
+    lobby_name: &LobbyName,
+) {
+    // the game being watched, the next one in the lobby is started at least a countdown later
+    let started_at = match status.lock().unwrap().game() {
+        Some(game) if game.clocks.is_some() => game.started_at,
+        _ => return,
+    };
+
+    let (users, status, running, db, lobby_id, lobby_name) = (
+        Arc::clone(users),
+        Arc::clone(status),
+        Arc::clone(running),
+        Arc::clone(db),
+        Arc::clone(lobby_id),
+        Arc::clone(lobby_name),
+    );
+
+    thread::spawn(move || loop {
+        if !*running.lock().unwrap() {
+            return;
+        }
+
+        let left = match status.lock().unwrap().game() {
+            Some(game) if game.started_at == started_at => game.time_left(),
+            _ => None,
+        };
+
+        match left {
+            Some(0) => {}
+            Some(left) => {
+                thread::sleep(Duration::from_millis(left.min(CLOCK_TICK)));
+                continue;
+            }
+            None => return,
+        }
+
+        let lobby_id = { *lobby_id.lock().unwrap() };
+        let lobby_name = { lobby_name.lock().unwrap().clone() };
+
+        if let Err(e) = time_out(
+            &users,
+            &status,
+            &running,
+            &db,
+            (lobby_id, &lobby_name),
+            started_at,
+        ) {
+            println!("couldn't end the game of lobby {lobby_id} on time: {e:?}");
+        }
+
+        return;
+    });
+}
+
+// a game restored after a restart starts again once both of its players are back in the lobby, the
+// time the server was down isn't taken off the clocks
+pub fn resume_clock(
+    users: &UsersVec,
+    status: &Status,
+    running: &BoolMutex,
+    db: &Db,
+    lobby_id: &LobbyId,
+    lobby_name: &LobbyName,
+) {
+    {
+        let mut status = status.lock().unwrap();
+        let users = users.lock().unwrap();
+
+        let game = match status.game_mut() {
+            Some(game) if game.turn_started.is_none() => game,
+            _ => return,
+        };
+
+        // the computer is always there
+        let back = |id| id == 0 || users.iter().any(|user| user.id == id);
+        if !back(game.angel) || !back(game.devil) {
+            return;
+        }
+
+        game.start_clock();
+    }
+
+    watch_clock(users, status, running, db, lobby_id, lobby_name);
+}
+
+fn time_out(
+    users: &UsersVec,
+    status: &Status,
+    running: &BoolMutex,
+    db: &Db,
+    lobby: (u16, &str),
+    started_at: u64,
+) -> Result<(), ServerError> {
+    let mut status = status.lock().unwrap();
+    let mut users = users.lock().unwrap();
+
+    // a move could have come in since the clock was read, otherwise the player to move loses
+    let winner = match status.game() {
+        Some(game) if game.started_at == started_at && game.time_left() == Some(0) => {
+            if game.turn {
+                Role::Angel
+            } else {
+                Role::Devil
+            }
+        }
+        _ => return Ok(()),
+    };
+
+    finish_game(
+        &mut status,
+        &mut users,
+        running,
+        db,
+        lobby,
+        Some(winner),
+        EndReason::Timeout,
+    )
+}
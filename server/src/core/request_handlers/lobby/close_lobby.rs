@@ -2,11 +2,9 @@ use std::net::TcpStream;
 
 use anyhow::{anyhow, Result};
 use network::SendRecv;
-use r2d2::Pool;
-use r2d2_sqlite::SqliteConnectionManager;
 
 use crate::core::{
-    db::UserOps,
+    db::{Db, StorageError},
     request_handlers::error_check,
     types::{BoolMutex, UserType, UsersVec},
 };
@@ -15,41 +13,39 @@ use super::{error::ServerError, Request};
 
 pub struct CloseLobbyRequest {
     stream: TcpStream,
-    user_id: u32,
+    token: String,
     users: UsersVec,
     running: BoolMutex,
-    db_pool: Pool<SqliteConnectionManager>,
+    db: Db,
 }
 
 impl CloseLobbyRequest {
     pub fn new(
         stream: TcpStream,
-        user_id: u32,
+        token: String,
         users: UsersVec,
         running: BoolMutex,
-        db_pool: Pool<SqliteConnectionManager>,
+        db: Db,
     ) -> CloseLobbyRequest {
         CloseLobbyRequest {
             stream,
-            user_id,
+            token,
             users,
             running,
-            db_pool,
+            db,
         }
     }
 
     fn handler(&self) -> Result<(), ServerError> {
-        let conn = self.db_pool.get()?;
-
-        let _ = match conn.is_connected(self.user_id) {
+        let db_user = match self.db.get_session_user(&self.token) {
             Ok(Some(db_user)) => db_user,
             Ok(None) => return Err(ServerError::ApiNotConnected),
-            Err(rusqlite::Error::QueryReturnedNoRows) => {
+            Err(StorageError::NotFound) => {
                 return Err(ServerError::Api {
-                    message: "invalid id".to_string(),
+                    message: "invalid session token".to_string(),
                 })
             }
-            Err(e) => return Err(ServerError::InternalRusqlite(e)),
+            Err(e) => return Err(ServerError::InternalStorage(e)),
         };
 
         match self
@@ -57,7 +53,7 @@ impl CloseLobbyRequest {
             .lock()
             .unwrap()
             .iter()
-            .find(|u| u.id == self.user_id)
+            .find(|u| u.id == db_user.id)
         {
             Some(user) => {
                 if user.user_type != UserType::Host {
@@ -0,0 +1,88 @@
+use std::net::TcpStream;
+
+use anyhow::{anyhow, Result};
+use network::SendRecv;
+
+use crate::core::{
+    db::{ChatMessage, Db, StorageError},
+    request_handlers::error_check,
+    types::{LobbyId, UsersVec},
+};
+
+use super::{error::ServerError, Request, CHAT_BACKLOG};
+
+pub struct GetChatHistoryRequest {
+    stream: TcpStream,
+    token: String,
+    before: Option<u32>,
+    offset: u32,
+    lobby_id: LobbyId,
+    users: UsersVec,
+    db: Db,
+}
+
+impl GetChatHistoryRequest {
+    pub fn new(
+        stream: TcpStream,
+        data: (String, Option<u32>, u32),
+        lobby_id: LobbyId,
+        users: UsersVec,
+        db: Db,
+    ) -> GetChatHistoryRequest {
+        GetChatHistoryRequest {
+            stream,
+            token: data.0,
+            before: data.1,
+            offset: data.2,
+            lobby_id,
+            users,
+            db,
+        }
+    }
+
+    // the messages sent before the given message id, or the latest ones, oldest first
+    fn handler(&self) -> Result<Vec<ChatMessage>, ServerError> {
+        let db_user = match self.db.get_session_user(&self.token) {
+            Ok(Some(db_user)) => db_user,
+            Ok(None) => return Err(ServerError::ApiNotConnected),
+            Err(StorageError::NotFound) => {
+                return Err(ServerError::Api {
+                    message: "invalid session token".to_string(),
+                })
+            }
+            Err(e) => return Err(ServerError::InternalStorage(e)),
+        };
+
+        if self.offset > CHAT_BACKLOG {
+            return Err(ServerError::Api {
+                message: format!("offset can be at most {CHAT_BACKLOG}"),
+            });
+        }
+
+        {
+            let users = self.users.lock().unwrap();
+
+            if !users.iter().any(|user| user.id == db_user.id) {
+                return Err(ServerError::Api {
+                    message: "you are not connected to this lobby".to_string(),
+                });
+            }
+        }
+
+        let lobby_id = { *self.lobby_id.lock().unwrap() };
+
+        Ok(self.db.get_messages(lobby_id, self.before, self.offset)?)
+    }
+}
+
+impl Request for GetChatHistoryRequest {
+    fn execute(&mut self) -> Result<()> {
+        let (res_type, res) = error_check(self.handler())?;
+
+        if let Err(e) = self.stream.send(res_type, &res) {
+            return Err(anyhow!(format!("couldn't send: {e:?}")));
+        }
+
+        Ok(())
+    }
+}
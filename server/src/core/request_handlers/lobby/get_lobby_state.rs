@@ -4,40 +4,58 @@ use anyhow::{anyhow, Result};
 use network::SendRecv;
 
 use crate::core::{
+    db::Db,
     request_handlers::error_check,
-    types::{Game, LobbyName, LobbyStateShort, UsersVec},
+    status::Phase,
+    types::{LobbyId, LobbyName, LobbyStateShort, Settings, Status, UsersVec},
 };
 
-use super::{error::ServerError, Request};
+use super::{error::ServerError, Request, CHAT_BACKLOG};
 
 pub struct GetLobbyStateRequest {
     stream: TcpStream,
+    id: LobbyId,
     name: LobbyName,
     users: UsersVec,
-    game: Game,
+    settings: Settings,
+    status: Status,
+    db: Db,
 }
 
 impl GetLobbyStateRequest {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         stream: TcpStream,
         _: (),
+        id: LobbyId,
         name: LobbyName,
         users: UsersVec,
-        game: Game,
+        settings: Settings,
+        status: Status,
+        db: Db,
     ) -> GetLobbyStateRequest {
         GetLobbyStateRequest {
             stream,
+            id,
             name,
             users,
-            game,
+            settings,
+            status,
+            db,
         }
     }
 
     fn handler(&self) -> Result<LobbyStateShort, ServerError> {
+        let id = { *self.id.lock().unwrap() };
+        let phase = { self.status.lock().unwrap().phase() };
+
         Ok(LobbyStateShort {
             name: { self.name.lock().unwrap().clone() },
             users: { self.users.lock().unwrap().len() as u32 },
-            game_going: { self.game.lock().unwrap().is_some() },
+            game_going: phase == Phase::InGame,
+            chat: self.db.get_messages(id, None, CHAT_BACKLOG)?,
+            settings: { self.settings.lock().unwrap().clone() },
+            phase,
         })
     }
 }
@@ -1,102 +1,165 @@
-use std::net::TcpStream;
+use std::net::{SocketAddr, TcpStream};
 
 use anyhow::{anyhow, Result};
 use network::{SendRecv, Type};
-use r2d2::Pool;
-use r2d2_sqlite::SqliteConnectionManager;
 
 use crate::core::{
-    db::UserOps,
+    db::{ChatMessage, Db, StorageError},
     request_handlers::{dispatch, error_check},
-    types::{BoolMutex, Game, LobbyName, LobbyState, UserInfo, UserInfoShort, UserType, UsersVec},
+    types::{
+        BoolMutex, LobbyId, LobbyName, LobbyState, Registry, Settings, Status, UserInfo,
+        UserInfoShort, UserType, UsersVec,
+    },
 };
 
-use super::{error::ServerError, Request};
+use super::{clock::resume_clock, error::ServerError, Request, CHAT_BACKLOG};
 
 pub struct JoinLobbyRequest {
     stream: TcpStream,
-    user_id: u32,
+    token: String,
+    lobby_id: LobbyId,
     lobby_name: LobbyName,
     users: UsersVec,
-    game: Game,
+    settings: Settings,
+    status: Status,
     running: BoolMutex,
-    db_pool: Pool<SqliteConnectionManager>,
+    registry: Registry,
+    db: Db,
 }
 
 impl JoinLobbyRequest {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         stream: TcpStream,
-        user_id: u32,
+        token: String,
+        lobby_id: LobbyId,
         lobby_name: LobbyName,
         users: UsersVec,
-        game: Game,
+        settings: Settings,
+        status: Status,
         running: BoolMutex,
-        db_pool: Pool<SqliteConnectionManager>,
+        registry: Registry,
+        db: Db,
     ) -> JoinLobbyRequest {
         JoinLobbyRequest {
             stream,
-            user_id,
+            token,
+            lobby_id,
             lobby_name,
             users,
-            game,
+            settings,
+            status,
             running,
-            db_pool,
+            registry,
+            db,
         }
     }
 
     fn handler(&self) -> Result<LobbyState, ServerError> {
-        let conn = self.db_pool.get()?;
-
-        let db_user = match conn.is_connected(self.user_id) {
+        let db_user = match self.db.get_session_user(&self.token) {
             Ok(Some(db_user)) => db_user,
             Ok(None) => return Err(ServerError::ApiNotConnected),
-            Err(rusqlite::Error::QueryReturnedNoRows) => {
+            Err(StorageError::NotFound) => {
                 return Err(ServerError::Api {
-                    message: "invalid id".to_string(),
+                    message: "invalid session token".to_string(),
                 })
             }
-            Err(e) => return Err(ServerError::InternalRusqlite(e)),
+            Err(e) => return Err(ServerError::InternalStorage(e)),
         };
 
-        let mut users = self.users.lock().unwrap();
+        let lobby_id = { *self.lobby_id.lock().unwrap() };
 
-        if users.iter().any(|user| user.id == self.user_id) {
-            return Err(ServerError::Api {
-                message: "you are already connected to this lobby".to_string(),
-            });
-        }
+        // the registry is locked for the whole request so the user can't join two lobbies at once
+        let mut registry = self.registry.lock().unwrap();
 
-        let new_user: UserInfo = UserInfo {
-            id: db_user.id,
-            user_type: match users.len() {
-                0 => UserType::Host,
-                1 => UserType::Player,
-                _ => UserType::Spectator,
-            },
-            name: db_user.name.clone(),
-            addr: db_user.addr.parse()?,
-        };
+        match registry.lobby_of(db_user.id) {
+            Some(id) if id == lobby_id => {
+                return Err(ServerError::Api {
+                    message: "you are already connected to this lobby".to_string(),
+                })
+            }
+            Some(_) => {
+                return Err(ServerError::Api {
+                    message: "you are already connected to a lobby".to_string(),
+                })
+            }
+            None => {}
+        }
 
-        let new_user_short = UserInfoShort::from(&new_user);
+        let chat = self.db.get_messages(lobby_id, None, CHAT_BACKLOG)?;
+
+        let lobby_state = join_lobby(
+            (db_user.id, db_user.name, db_user.addr.parse()?),
+            &self.lobby_name,
+            &self.users,
+            &self.settings,
+            &self.status,
+            &self.running,
+            chat,
+        );
+
+        registry.join(db_user.id, lobby_id);
+
+        resume_clock(
+            &self.users,
+            &self.status,
+            &self.running,
+            &self.db,
+            &self.lobby_id,
+            &self.lobby_name,
+        );
+
+        Ok(lobby_state)
+    }
+}
 
-        if !users.is_empty() {
-            if let Err(ServerError::InternalShutDown) = dispatch(
-                &mut users,
-                vec![(Type::PlayerJoined, &new_user_short)],
-                |_| {},
-            ) {
-                let mut running = self.running.lock().unwrap();
-                *running = false;
-            }
+// adds the user to the lobby and announces the other users, the caller must hold the registry
+pub fn join_lobby(
+    user: (u32, String, SocketAddr),
+    lobby_name: &LobbyName,
+    users: &UsersVec,
+    settings: &Settings,
+    status: &Status,
+    running: &BoolMutex,
+    chat: Vec<ChatMessage>,
+) -> LobbyState {
+    let status = status.lock().unwrap();
+    let mut users = users.lock().unwrap();
+
+    let new_user: UserInfo = UserInfo {
+        id: user.0,
+        user_type: match users.len() {
+            0 => UserType::Host,
+            1 => UserType::Player,
+            _ => UserType::Spectator,
+        },
+        name: user.1,
+        addr: user.2,
+    };
+
+    let new_user_short = UserInfoShort::from(&new_user);
+
+    if !users.is_empty() {
+        if let Err(ServerError::InternalShutDown) = dispatch(
+            &mut users,
+            vec![(Type::PlayerJoined, &new_user_short)],
+            |_| {},
+        ) {
+            let mut running = running.lock().unwrap();
+            *running = false;
         }
+    }
 
-        users.push(new_user);
+    users.push(new_user);
 
-        Ok(LobbyState {
-            name: { self.lobby_name.lock().unwrap().clone() },
-            users: users.iter().map(UserInfoShort::from).collect(),
-            game: { self.game.lock().unwrap().clone() },
-        })
+    LobbyState {
+        name: { lobby_name.lock().unwrap().clone() },
+        users: users.iter().map(UserInfoShort::from).collect(),
+        chat,
+        settings: { settings.lock().unwrap().clone() },
+        game: status.game().cloned(),
+        series: status.series().cloned(),
+        phase: status.phase(),
     }
 }
 
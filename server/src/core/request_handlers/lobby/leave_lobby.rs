@@ -2,80 +2,132 @@ use std::net::TcpStream;
 
 use anyhow::{anyhow, Result};
 use network::{SendRecv, Type};
-use r2d2::Pool;
-use r2d2_sqlite::SqliteConnectionManager;
+use rules::EndReason;
 
 use crate::core::{
-    db::UserOps,
+    db::{Db, StorageError},
+    registry::LobbyHandle,
     request_handlers::{dispatch, error_check},
-    types::{BoolMutex, UsersVec},
+    types::{LobbyId, Registry},
 };
 
-use super::{error::ServerError, Request};
+use super::{
+    error::ServerError,
+    make_move::{finish_game, update_match},
+    Request,
+};
 
 pub struct LeaveLobbyRequest {
     stream: TcpStream,
-    user_id: u32,
-    users: UsersVec,
-    running: BoolMutex,
-    db_pool: Pool<SqliteConnectionManager>,
+    token: String,
+    lobby_id: LobbyId,
+    lobby: LobbyHandle,
+    registry: Registry,
+    db: Db,
 }
 
 impl LeaveLobbyRequest {
     pub fn new(
         stream: TcpStream,
-        user_id: u32,
-        users: UsersVec,
-        running: BoolMutex,
-        db_pool: Pool<SqliteConnectionManager>,
+        token: String,
+        lobby_id: LobbyId,
+        lobby: LobbyHandle,
+        registry: Registry,
+        db: Db,
     ) -> LeaveLobbyRequest {
         LeaveLobbyRequest {
             stream,
-            user_id,
-            users,
-            running,
-            db_pool,
+            token,
+            lobby_id,
+            lobby,
+            registry,
+            db,
         }
     }
 
     fn handler(&self) -> Result<(), ServerError> {
-        let conn = self.db_pool.get()?;
-
-        let db_user = match conn.is_connected(self.user_id) {
+        let db_user = match self.db.get_session_user(&self.token) {
             Ok(Some(db_user)) => db_user,
             Ok(None) => return Err(ServerError::ApiNotConnected),
-            Err(rusqlite::Error::QueryReturnedNoRows) => {
+            Err(StorageError::NotFound) => {
                 return Err(ServerError::Api {
-                    message: "invalid id".to_string(),
+                    message: "invalid session token".to_string(),
                 })
             }
-            Err(e) => return Err(ServerError::InternalRusqlite(e)),
+            Err(e) => return Err(ServerError::InternalStorage(e)),
         };
 
-        let mut users = self.users.lock().unwrap();
+        let mut registry = self.registry.lock().unwrap();
 
-        let index = match users.iter().position(|user| user.id == self.user_id) {
-            Some(index) => index,
-            None => {
-                return Err(ServerError::Api {
-                    message: "you are not connected to this lobby".to_string(),
-                })
-            }
-        };
+        let lobby_id = { *self.lobby_id.lock().unwrap() };
+        leave_lobby(db_user.id, lobby_id, &self.lobby, &self.db)?;
 
-        users.remove(index);
+        registry.leave(db_user.id);
 
-        if let Err(ServerError::InternalShutDown) = dispatch(
-            &mut users,
-            vec![(Type::PlayerLeft, &db_user.id)],
-            |_| {},
-        ) {
-            let mut running = self.running.lock().unwrap();
-            *running = false;
+        Ok(())
+    }
+}
+
+// removes the user from the lobby and announces the other users, a player leaving in the middle
+// of a game loses it, the caller must hold the registry
+pub fn leave_lobby(
+    user_id: u32,
+    lobby_id: u16,
+    lobby: &LobbyHandle,
+    db: &Db,
+) -> Result<(), ServerError> {
+    let mut status = lobby.status.lock().unwrap();
+    let mut users = lobby.users.lock().unwrap();
+
+    let index = match users.iter().position(|user| user.id == user_id) {
+        Some(index) => index,
+        None => {
+            return Err(ServerError::Api {
+                message: "you are not connected to this lobby".to_string(),
+            })
         }
+    };
 
-        Ok(())
+    users.remove(index);
+
+    if let Err(ServerError::InternalShutDown) =
+        dispatch(&mut users, vec![(Type::PlayerLeft, &user_id)], |_| {})
+    {
+        let mut running = lobby.running.lock().unwrap();
+        *running = false;
+    }
+
+    if let Some(role) = status.game().and_then(|game| game.role_of(user_id)) {
+        let lobby_name = { lobby.name.lock().unwrap().clone() };
+
+        finish_game(
+            &mut status,
+            &mut users,
+            &lobby.running,
+            db,
+            (lobby_id, &lobby_name),
+            Some(role.opponent()),
+            EndReason::Disconnected,
+        )?;
+    }
+
+    // leaving between the games of a match gives it away as well
+    if let Some(series) = status.series_mut() {
+        if !series.is_over() && series.has_player(user_id) {
+            series.forfeit = Some(user_id);
+
+            let lobby_name = { lobby.name.lock().unwrap().clone() };
+            update_match(
+                series,
+                &mut users,
+                &lobby.running,
+                db,
+                (lobby_id, &lobby_name),
+            )?;
+        }
     }
+
+    Ok(())
 }
 
 impl Request for LeaveLobbyRequest {
@@ -2,65 +2,70 @@ use std::net::TcpStream;
 
 use anyhow::{anyhow, Result};
 use network::{SendRecv, Type};
-use r2d2::Pool;
-use r2d2_sqlite::SqliteConnectionManager;
 
 use crate::core::{
-    db::UserOps,
-    request_handlers::{dispatch, error_check},
-    types::{BoolMutex, Game, UserInfoShort, UserType, UsersVec},
+    db::{Db, StorageError},
+    request_handlers::{dispatch, dispatch_phases, error_check},
+    status::Action,
+    types::{BoolMutex, LobbyId, LobbyName, Status, UserInfoShort, UserType, UsersVec},
 };
 
-use super::{error::ServerError, Request};
+use super::{error::ServerError, make_move::abandon_match, Request};
 
 pub struct MakeHostRequest {
     stream: TcpStream,
-    user_id: u32,
+    token: String,
     new_host_id: u32,
+    lobby_id: LobbyId,
+    lobby_name: LobbyName,
     users: UsersVec,
-    game: Game,
+    status: Status,
     running: BoolMutex,
-    db_pool: Pool<SqliteConnectionManager>,
+    db: Db,
 }
 
 impl MakeHostRequest {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         stream: TcpStream,
-        data: (u32, u32),
+        data: (String, u32),
+        lobby_id: LobbyId,
+        lobby_name: LobbyName,
         users: UsersVec,
-        game: Game,
+        status: Status,
         running: BoolMutex,
-        db_pool: Pool<SqliteConnectionManager>,
+        db: Db,
     ) -> MakeHostRequest {
         MakeHostRequest {
             stream,
-            user_id: data.0,
+            token: data.0,
             new_host_id: data.1,
+            lobby_id,
+            lobby_name,
             users,
-            game,
+            status,
             running,
-            db_pool,
+            db,
         }
     }
 
     fn handler(&self) -> Result<(), ServerError> {
-        let conn = self.db_pool.get()?;
-
-        let _ = match conn.is_connected(self.user_id) {
+        let db_user = match self.db.get_session_user(&self.token) {
             Ok(Some(db_user)) => db_user,
             Ok(None) => return Err(ServerError::ApiNotConnected),
-            Err(rusqlite::Error::QueryReturnedNoRows) => {
+            Err(StorageError::NotFound) => {
                 return Err(ServerError::Api {
-                    message: "invalid id".to_string(),
+                    message: "invalid session token".to_string(),
                 })
             }
-            Err(e) => return Err(ServerError::InternalRusqlite(e)),
+            Err(e) => return Err(ServerError::InternalStorage(e)),
         };
 
+        let mut status = self.status.lock().unwrap();
         let mut users = self.users.lock().unwrap();
 
         let (mut new_host, mut old_host) = {
-            let host = match users.iter().find(|user| user.id == self.user_id) {
+            let host = match users.iter().find(|user| user.id == db_user.id) {
                 Some(index) => index,
                 None => {
                     return Err(ServerError::Api {
@@ -69,12 +74,10 @@ impl MakeHostRequest {
                 }
             };
 
-            {
-                if self.game.lock().unwrap().is_some() {
-                    return Err(ServerError::Api {
-                        message: "cannot change roles while a game is going on".to_string(),
-                    });
-                }
+            if let Err(message) = status.check(&Action::ChangeRoles) {
+                return Err(ServerError::Api {
+                    message: message.to_string(),
+                });
             }
 
             if host.user_type != UserType::Host {
@@ -98,6 +101,28 @@ impl MakeHostRequest {
         old_host.user_type = new_host.user_type;
         new_host.user_type = UserType::Host;
 
+        let lobby_id = { *self.lobby_id.lock().unwrap() };
+        let lobby_name = { self.lobby_name.lock().unwrap().clone() };
+        abandon_match(
+            &status,
+            &Action::ChangeRoles,
+            &mut users,
+            &self.running,
+            &self.db,
+            (lobby_id, &lobby_name),
+        )?;
+
+        let changes = status
+            .apply(Action::ChangeRoles)
+            .map_err(|message| ServerError::Api {
+                message: message.to_string(),
+            })?;
+
+        if let Err(ServerError::InternalShutDown) = dispatch_phases(&mut users, &changes) {
+            let mut running = self.running.lock().unwrap();
+            *running = false;
+        }
+
         if let Err(ServerError::InternalShutDown) = dispatch(
             &mut users,
             vec![
@@ -2,123 +2,133 @@ use std::net::TcpStream;
 
 use anyhow::{anyhow, Result};
 use network::{SendRecv, Type};
-use r2d2::Pool;
-use r2d2_sqlite::SqliteConnectionManager;
+use rules::{EndReason, GameState, GameUpdate, MatchState, Role};
 
 use crate::core::{
-    db::UserOps,
-    game::GameUpdate,
-    request_handlers::{dispatch, error_check},
-    types::{BoolMutex, Game, UsersVec},
+    db::{Db, StorageError},
+    request_handlers::{dispatch, dispatch_phases, error_check},
+    status::{Action, LobbyStatus},
+    types::{BoolMutex, LobbyId, LobbyName, Status, UserInfo, UsersVec},
 };
 
 use super::{error::ServerError, Request};
 
 pub struct MakeMoveRequest {
     stream: TcpStream,
-    user_id: u32,
+    token: String,
     user_move: (i32, i32),
+    lobby_id: LobbyId,
+    lobby_name: LobbyName,
     users: UsersVec,
-    game: Game,
+    status: Status,
     running: BoolMutex,
-    db_pool: Pool<SqliteConnectionManager>,
+    db: Db,
 }
 
 impl MakeMoveRequest {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         stream: TcpStream,
-        data: (u32, i32, i32),
+        data: (String, i32, i32),
+        lobby_id: LobbyId,
+        lobby_name: LobbyName,
         users: UsersVec,
-        game: Game,
+        status: Status,
         running: BoolMutex,
-        db_pool: Pool<SqliteConnectionManager>,
+        db: Db,
     ) -> MakeMoveRequest {
         MakeMoveRequest {
             stream,
-            user_id: data.0,
+            token: data.0,
             user_move: (data.1, data.2),
+            lobby_id,
+            lobby_name,
             users,
-            game,
+            status,
             running,
-            db_pool,
+            db,
         }
     }
 
     fn handler(&self) -> Result<(), ServerError> {
-        let conn = self.db_pool.get()?;
-
-        let _ = match conn.is_connected(self.user_id) {
+        let db_user = match self.db.get_session_user(&self.token) {
             Ok(Some(db_user)) => db_user,
             Ok(None) => return Err(ServerError::ApiNotConnected),
-            Err(rusqlite::Error::QueryReturnedNoRows) => {
+            Err(StorageError::NotFound) => {
                 return Err(ServerError::Api {
-                    message: "invalid id".to_string(),
+                    message: "invalid session token".to_string(),
                 })
             }
-            Err(e) => return Err(ServerError::InternalRusqlite(e)),
+            Err(e) => return Err(ServerError::InternalStorage(e)),
         };
 
-        let mut game_state = self.game.lock().unwrap();
+        let mut status = self.status.lock().unwrap();
 
-        if game_state.is_none() {
+        if let Err(message) = status.check(&Action::MakeMove) {
             return Err(ServerError::Api {
-                message: "game is not started yet".to_string(),
+                message: message.to_string(),
             });
         }
 
-        let game = game_state.as_mut().unwrap();
+        let game = status.game_mut().unwrap();
 
-        if self.user_id != game.angel && self.user_id != game.devil {
+        if db_user.id != game.angel && db_user.id != game.devil {
             return Err(ServerError::Api {
                 message: "you are not playing".to_string(),
             });
         }
 
+        // a game restored after a restart waits for both of its players to be back
+        if game.turn_started.is_none() {
+            return Err(ServerError::Api {
+                message: "the game resumes once both players are back".to_string(),
+            });
+        }
+
         // devil player move
-        let user_move = if game.turn {
-            if self.user_id != game.devil {
+        let valid = if game.turn {
+            if db_user.id != game.devil {
                 return Err(ServerError::Api {
                     message: "it's not your turn".to_string(),
                 });
             }
 
-            if game.grid[self.user_move.0 as usize][self.user_move.1 as usize]
-                || game.angel_pos == self.user_move
-            {
-                return Err(ServerError::Api {
-                    message: "invalid move".to_string(),
-                });
-            }
-
-            game.grid[self.user_move.0 as usize][self.user_move.1 as usize] = true;
-
-            self.user_move
+            game.valid_devil_move(self.user_move)
         }
         // angel player move
         else {
-            if self.user_id != game.angel {
+            if db_user.id != game.angel {
                 return Err(ServerError::Api {
                     message: "it's not your turn".to_string(),
                 });
             }
 
-            if !game.valid_angel_move(self.user_move) {
-                return Err(ServerError::Api {
-                    message: "invalid move".to_string(),
-                });
-            }
+            game.valid_angel_move(self.user_move)
+        };
 
-            game.angel_pos = self.user_move;
+        if game.time_left() == Some(0) {
+            return Err(ServerError::Api {
+                message: "your time ran out".to_string(),
+            });
+        }
 
-            self.user_move
-        };
+        if !valid {
+            return Err(ServerError::Api {
+                message: "invalid move".to_string(),
+            });
+        }
 
+        let turn = game.turn;
+        game.stop_clock();
+        game.apply_move(self.user_move);
         let path = game.find_path();
 
         let update = GameUpdate {
             win: (path.is_none(), game.angel_won()),
-            turn: game.turn,
-            user_move,
+            turn,
+            user_move: self.user_move,
+            walls_left: game.walls_left,
+            clocks: game.clocks,
         };
 
         let mut users = self.users.lock().unwrap();
@@ -130,46 +140,201 @@ impl MakeMoveRequest {
             *running = false;
         }
 
-        game.turn = !game.turn;
+        if let Some(end) = game_over(&update) {
+            return self.finish_game(&mut status, &mut users, end);
+        }
 
-        if update.win.0 || update.win.1 {
-            *game_state = None;
-            return Ok(());
+        if let Some(end) = computer_moves(game, &mut users, &self.running) {
+            return self.finish_game(&mut status, &mut users, end);
         }
 
-        // angel computer move
-        if !game.turn && game.angel == 0 {
-            let update = if let Some(next_move) = path {
-                game.angel_pos = next_move;
+        // the lobby keeps the game up to date so it can be resumed after a restart
+        let lobby_id = { *self.lobby_id.lock().unwrap() };
+        self.db.set_lobby_game(lobby_id, status.game())?;
+
+        Ok(())
+    }
+
+    fn finish_game(
+        &self,
+        status: &mut LobbyStatus,
+        users: &mut Vec<UserInfo>,
+        (winner, reason): (Role, EndReason),
+    ) -> Result<(), ServerError> {
+        let lobby_id = { *self.lobby_id.lock().unwrap() };
+        let lobby_name = { self.lobby_name.lock().unwrap().clone() };
+
+        finish_game(
+            status,
+            users,
+            &self.running,
+            &self.db,
+            (lobby_id, &lobby_name),
+            Some(winner),
+            reason,
+        )
+    }
+}
+
+// the winner and how the game ended if the move ended it
+fn game_over(update: &GameUpdate) -> Option<(Role, EndReason)> {
+    if update.win.1 {
+        Some((Role::Angel, EndReason::Escaped))
+    } else if update.win.0 {
+        Some((Role::Devil, EndReason::Trapped))
+    } else {
+        None
+    }
+}
+
+// the computer plays its side until a human is to move again, the angel keeps moving once the
+// devil has no walls left, returns the winner and how the game ended if the computer ended it
+pub fn computer_moves(
+    game: &mut GameState,
+    users: &mut Vec<UserInfo>,
+    running: &BoolMutex,
+) -> Option<(Role, EndReason)> {
+    while game.computer_turn() {
+        let turn = game.turn;
+        let next_move = if turn {
+            game.devil_move(&mut rand::thread_rng())
+        } else {
+            game.find_path()
+        };
+
+        let update = match next_move {
+            Some(next_move) => {
+                game.stop_clock();
+                game.apply_move(next_move);
 
                 GameUpdate {
-                    win: (false, game.angel_won()),
-                    turn: game.turn,
-                    user_move: game.angel_pos,
-                }
-            } else {
-                GameUpdate {
-                    win: (true, false),
-                    turn: game.turn,
-                    user_move: game.angel_pos,
+                    win: (game.find_path().is_none(), game.angel_won()),
+                    turn,
+                    user_move: next_move,
+                    walls_left: game.walls_left,
+                    clocks: game.clocks,
                 }
-            };
-
-            if let Err(ServerError::InternalShutDown) =
-                dispatch(&mut users, vec![(Type::GameUpdated, &update)], |_| {})
-            {
-                let mut running = self.running.lock().unwrap();
-                *running = false;
             }
+            // the game passes the devil's turn as soon as it has nowhere left to build
+            None if turn => break,
+            None => GameUpdate {
+                win: (true, false),
+                turn,
+                user_move: game.angel_pos,
+                walls_left: game.walls_left,
+                clocks: game.clocks,
+            },
+        };
+
+        if let Err(ServerError::InternalShutDown) =
+            dispatch(users, vec![(Type::GameUpdated, &update)], |_| {})
+        {
+            let mut running = running.lock().unwrap();
+            *running = false;
+        }
+
+        if let Some(end) = game_over(&update) {
+            return Some(end);
+        }
+    }
+
+    None
+}
+
+// stores the game that just ended, tells the lobby how it ended and moves it on to showing the
+// results, winner is None for a draw, the game counts towards the match it's part of
+pub fn finish_game(
+    status: &mut LobbyStatus,
+    users: &mut Vec<UserInfo>,
+    running: &BoolMutex,
+    db: &Db,
+    lobby: (u16, &str),
+    winner: Option<Role>,
+    reason: EndReason,
+) -> Result<(), ServerError> {
+    let rate = !status.series().is_some_and(|series| series.rated);
+
+    if let Some(game) = status.game() {
+        let game_id = db.add_game(lobby, game, winner, reason, rate)?;
+        db.set_lobby_game(lobby.0, None)?;
+
+        let result = game.result(game_id, winner, reason);
 
-            game.turn = !game.turn;
+        if let Err(ServerError::InternalShutDown) =
+            dispatch(users, vec![(Type::GameEnded, &result)], |_| {})
+        {
+            let mut running = running.lock().unwrap();
+            *running = false;
+        }
 
-            if update.win.0 || update.win.1 {
-                *game_state = None;
+        let (angel, devil) = (game.angel, game.devil);
+
+        if let Some(series) = status.series_mut() {
+            series.record(game_id, angel, devil, winner);
+
+            // leaving in the middle of a game gives the whole match away
+            if reason == EndReason::Disconnected {
+                series.forfeit = match winner {
+                    Some(Role::Angel) => Some(devil),
+                    _ => Some(angel),
+                };
             }
+
+            update_match(series, users, running, db, lobby)?;
         }
+    }
 
-        Ok(())
+    let changes = status
+        .apply(Action::EndGame)
+        .map_err(|message| ServerError::Api {
+            message: message.to_string(),
+        })?;
+
+    if let Err(ServerError::InternalShutDown) = dispatch_phases(users, &changes) {
+        let mut running = running.lock().unwrap();
+        *running = false;
+    }
+
+    Ok(())
+}
+
+// tells the lobby the score of the match, which is stored once it's over
+pub fn update_match(
+    series: &MatchState,
+    users: &mut Vec<UserInfo>,
+    running: &BoolMutex,
+    db: &Db,
+    lobby: (u16, &str),
+) -> Result<(), ServerError> {
+    if series.is_over() {
+        db.add_match(lobby, series)?;
+    }
+
+    // a match that isn't over goes on after a restart
+    db.set_lobby_match(lobby.0, Some(series).filter(|series| !series.is_over()))?;
+
+    if let Err(ServerError::InternalShutDown) =
+        dispatch(users, vec![(Type::MatchUpdated, series)], |_| {})
+    {
+        let mut running = running.lock().unwrap();
+        *running = false;
+    }
+
+    Ok(())
+}
+
+// stores the match the action leaves unfinished, before the lobby is set up for other games
+pub fn abandon_match(
+    status: &LobbyStatus,
+    action: &Action,
+    users: &mut Vec<UserInfo>,
+    running: &BoolMutex,
+    db: &Db,
+    lobby: (u16, &str),
+) -> Result<(), ServerError> {
+    match status.abandons(action) {
+        Some(series) => update_match(&series, users, running, db, lobby),
+        None => Ok(()),
     }
 }
 
@@ -2,26 +2,46 @@ mod close_lobby;
 mod get_lobby_state;
 mod join_lobby;
 mod leave_lobby;
+mod switch_lobby;
 
 mod become_role;
-mod changed_name;
+mod change_settings;
+mod get_chat_history;
 mod make_host;
 mod send_message;
 
+mod accept_draw;
+mod answer_takeback;
+mod clock;
 mod make_move;
+mod offer_draw;
+mod rematch;
+mod request_takeback;
+mod resign;
 mod start_game;
 
 pub use close_lobby::CloseLobbyRequest;
 pub use get_lobby_state::GetLobbyStateRequest;
 pub use join_lobby::JoinLobbyRequest;
 pub use leave_lobby::LeaveLobbyRequest;
+pub use switch_lobby::SwitchLobbyRequest;
 
 pub use become_role::BecomeRoleRequest;
-pub use changed_name::ChangedNameRequest;
+pub use change_settings::ChangeSettingsRequest;
+pub use get_chat_history::GetChatHistoryRequest;
 pub use make_host::MakeHostRequest;
 pub use send_message::SendMessageRequest;
 
+pub use accept_draw::AcceptDrawRequest;
+pub use answer_takeback::AnswerTakebackRequest;
 pub use make_move::MakeMoveRequest;
+pub use offer_draw::OfferDrawRequest;
+pub use rematch::RematchRequest;
+pub use request_takeback::RequestTakebackRequest;
+pub use resign::ResignRequest;
 pub use start_game::StartGameRequest;
 
 use super::{error, Request};
+
+// number of messages sent along with the lobby state, older ones are requested with GetChatHistory
+pub const CHAT_BACKLOG: u32 = 50;
@@ -0,0 +1,119 @@
+use std::net::TcpStream;
+
+use anyhow::{anyhow, Result};
+use network::{SendRecv, Type};
+
+use crate::core::{
+    db::{Db, StorageError},
+    request_handlers::{dispatch, error_check},
+    status::Action,
+    types::{BoolMutex, Status, UsersVec},
+};
+
+use super::{error::ServerError, Request};
+
+pub struct OfferDrawRequest {
+    stream: TcpStream,
+    token: String,
+    users: UsersVec,
+    status: Status,
+    running: BoolMutex,
+    db: Db,
+}
+
+impl OfferDrawRequest {
+    pub fn new(
+        stream: TcpStream,
+        token: String,
+        users: UsersVec,
+        status: Status,
+        running: BoolMutex,
+        db: Db,
+    ) -> OfferDrawRequest {
+        OfferDrawRequest {
+            stream,
+            token,
+            users,
+            status,
+            running,
+            db,
+        }
+    }
+
+    fn handler(&self) -> Result<(), ServerError> {
+        let db_user = match self.db.get_session_user(&self.token) {
+            Ok(Some(db_user)) => db_user,
+            Ok(None) => return Err(ServerError::ApiNotConnected),
+            Err(StorageError::NotFound) => {
+                return Err(ServerError::Api {
+                    message: "invalid session token".to_string(),
+                })
+            }
+            Err(e) => return Err(ServerError::InternalStorage(e)),
+        };
+
+        let mut status = self.status.lock().unwrap();
+
+        if let Err(message) = status.check(&Action::EndGame) {
+            return Err(ServerError::Api {
+                message: message.to_string(),
+            });
+        }
+
+        let game = status.game_mut().unwrap();
+
+        let role = match game.role_of(db_user.id) {
+            Some(role) => role,
+            None => {
+                return Err(ServerError::Api {
+                    message: "you are not playing".to_string(),
+                })
+            }
+        };
+
+        if game.angel == 0 || game.devil == 0 {
+            return Err(ServerError::Api {
+                message: "the computer doesn't accept draws".to_string(),
+            });
+        }
+
+        match game.draw_offer {
+            Some(offer) if offer == role => {
+                return Err(ServerError::Api {
+                    message: "you already offered a draw".to_string(),
+                })
+            }
+            Some(_) => {
+                return Err(ServerError::Api {
+                    message: "your opponent already offered a draw".to_string(),
+                })
+            }
+            None => {}
+        }
+
+        game.draw_offer = Some(role);
+
+        let mut users = self.users.lock().unwrap();
+
+        if let Err(ServerError::InternalShutDown) =
+            dispatch(&mut users, vec![(Type::DrawOffered, &role)], |_| {})
+        {
+            let mut running = self.running.lock().unwrap();
+            *running = false;
+        }
+
+        Ok(())
+    }
+}
+
+impl Request for OfferDrawRequest {
+    fn execute(&mut self) -> Result<()> {
+        let (res_type, res) = error_check(self.handler())?;
+
+        if let Err(e) = self.stream.send(res_type, &res) {
+            return Err(anyhow!(format!("couldn't send: {e:?}")));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+use std::net::TcpStream;
+
+use anyhow::{anyhow, Result};
+use network::{SendRecv, Type};
+use rules::GameState;
+
+use crate::core::{
+    db::{Db, StorageError},
+    request_handlers::{dispatch, dispatch_phases, error_check},
+    status::Action,
+    types::{BoolMutex, LobbyId, LobbyName, Settings, Status, UserType, UsersVec},
+};
+
+use super::{
+    error::ServerError,
+    make_move::abandon_match,
+    start_game::{count_down, next_game},
+    Request,
+};
+
+pub struct RematchRequest {
+    stream: TcpStream,
+    token: String,
+    lobby_id: LobbyId,
+    lobby_name: LobbyName,
+    users: UsersVec,
+    settings: Settings,
+    status: Status,
+    running: BoolMutex,
+    db: Db,
+}
+
+impl RematchRequest {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        stream: TcpStream,
+        token: String,
+        lobby_id: LobbyId,
+        lobby_name: LobbyName,
+        users: UsersVec,
+        settings: Settings,
+        status: Status,
+        running: BoolMutex,
+        db: Db,
+    ) -> RematchRequest {
+        RematchRequest {
+            stream,
+            token,
+            lobby_id,
+            lobby_name,
+            users,
+            settings,
+            status,
+            running,
+            db,
+        }
+    }
+
+    fn handler(&self) -> Result<(), ServerError> {
+        let db_user = match self.db.get_session_user(&self.token) {
+            Ok(Some(db_user)) => db_user,
+            Ok(None) => return Err(ServerError::ApiNotConnected),
+            Err(StorageError::NotFound) => {
+                return Err(ServerError::Api {
+                    message: "invalid session token".to_string(),
+                })
+            }
+            Err(e) => return Err(ServerError::InternalStorage(e)),
+        };
+
+        let mut status = self.status.lock().unwrap();
+        let mut users = self.users.lock().unwrap();
+
+        if let Err(message) = status.check(&Action::VoteRematch) {
+            return Err(ServerError::Api {
+                message: message.to_string(),
+            });
+        }
+
+        // the host and the player play the next game, whoever played the last one
+        let players: Vec<u32> = users
+            .iter()
+            .filter(|user| user.user_type != UserType::Spectator)
+            .map(|user| user.id)
+            .collect();
+
+        if !players.contains(&db_user.id) {
+            return Err(ServerError::Api {
+                message: "you are not playing".to_string(),
+            });
+        }
+
+        let rematch = status.rematch_mut().unwrap();
+
+        if rematch.votes.contains(&db_user.id) {
+            return Err(ServerError::Api {
+                message: "you already voted for a rematch".to_string(),
+            });
+        }
+
+        rematch.votes.push(db_user.id);
+        let agreed = players.iter().all(|id| rematch.votes.contains(id));
+
+        if let Err(ServerError::InternalShutDown) =
+            dispatch(&mut users, vec![(Type::RematchVoted, &db_user.id)], |_| {})
+        {
+            let mut running = self.running.lock().unwrap();
+            *running = false;
+        }
+
+        if !agreed {
+            return Ok(());
+        }
+
+        let settings = { self.settings.lock().unwrap().clone() };
+        let (angel, devil, series) = next_game(&users, &settings, &status);
+
+        let game = GameState::new(angel, devil, &settings);
+
+        let action = Action::StartGame(Box::new(game), series);
+
+        let lobby_id = { *self.lobby_id.lock().unwrap() };
+        let lobby_name = { self.lobby_name.lock().unwrap().clone() };
+        abandon_match(
+            &status,
+            &action,
+            &mut users,
+            &self.running,
+            &self.db,
+            (lobby_id, &lobby_name),
+        )?;
+
+        let changes = status.apply(action).map_err(|message| ServerError::Api {
+            message: message.to_string(),
+        })?;
+
+        if let Err(ServerError::InternalShutDown) = dispatch_phases(&mut users, &changes) {
+            let mut running = self.running.lock().unwrap();
+            *running = false;
+        }
+
+        count_down(
+            &self.users,
+            &self.status,
+            &self.running,
+            &self.db,
+            &self.lobby_id,
+            &self.lobby_name,
+        );
+
+        Ok(())
+    }
+}
+
+impl Request for RematchRequest {
+    fn execute(&mut self) -> Result<()> {
+        let (res_type, res) = error_check(self.handler())?;
+
+        if let Err(e) = self.stream.send(res_type, &res) {
+            return Err(anyhow!(format!("couldn't send: {e:?}")));
+        }
+
+        Ok(())
+    }
+}
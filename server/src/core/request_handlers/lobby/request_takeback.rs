@@ -0,0 +1,135 @@
+use std::net::TcpStream;
+
+use anyhow::{anyhow, Result};
+use network::{SendRecv, Type};
+
+use crate::core::{
+    db::{is_rated, Db, StorageError},
+    request_handlers::{dispatch, error_check},
+    status::Action,
+    types::{BoolMutex, Status, UsersVec},
+};
+
+use super::{error::ServerError, Request};
+
+pub struct RequestTakebackRequest {
+    stream: TcpStream,
+    token: String,
+    users: UsersVec,
+    status: Status,
+    running: BoolMutex,
+    db: Db,
+}
+
+impl RequestTakebackRequest {
+    pub fn new(
+        stream: TcpStream,
+        token: String,
+        users: UsersVec,
+        status: Status,
+        running: BoolMutex,
+        db: Db,
+    ) -> RequestTakebackRequest {
+        RequestTakebackRequest {
+            stream,
+            token,
+            users,
+            status,
+            running,
+            db,
+        }
+    }
+
+    fn handler(&self) -> Result<(), ServerError> {
+        let db_user = match self.db.get_session_user(&self.token) {
+            Ok(Some(db_user)) => db_user,
+            Ok(None) => return Err(ServerError::ApiNotConnected),
+            Err(StorageError::NotFound) => {
+                return Err(ServerError::Api {
+                    message: "invalid session token".to_string(),
+                })
+            }
+            Err(e) => return Err(ServerError::InternalStorage(e)),
+        };
+
+        let mut status = self.status.lock().unwrap();
+
+        if let Err(message) = status.check(&Action::MakeMove) {
+            return Err(ServerError::Api {
+                message: message.to_string(),
+            });
+        }
+
+        let game = status.game_mut().unwrap();
+
+        let role = match game.role_of(db_user.id) {
+            Some(role) => role,
+            None => {
+                return Err(ServerError::Api {
+                    message: "you are not playing".to_string(),
+                })
+            }
+        };
+
+        if game.angel == 0 || game.devil == 0 {
+            return Err(ServerError::Api {
+                message: "the computer doesn't accept takebacks".to_string(),
+            });
+        }
+
+        // a takeback could hand a player the rating points of a lost game
+        let angel = self.db.get_user_by_id(game.angel)?;
+        let devil = self.db.get_user_by_id(game.devil)?;
+
+        if game.angel != game.devil && is_rated(&angel, &devil) {
+            return Err(ServerError::Api {
+                message: "takebacks aren't allowed in rated games".to_string(),
+            });
+        }
+
+        match game.takeback_request {
+            Some(request) if request == role => {
+                return Err(ServerError::Api {
+                    message: "you already asked for a takeback".to_string(),
+                })
+            }
+            Some(_) => {
+                return Err(ServerError::Api {
+                    message: "your opponent already asked for a takeback".to_string(),
+                })
+            }
+            None => {}
+        }
+
+        if !game.clone().take_back(role) {
+            return Err(ServerError::Api {
+                message: "you have nothing to take back".to_string(),
+            });
+        }
+
+        game.takeback_request = Some(role);
+
+        let mut users = self.users.lock().unwrap();
+
+        if let Err(ServerError::InternalShutDown) =
+            dispatch(&mut users, vec![(Type::TakebackRequested, &role)], |_| {})
+        {
+            let mut running = self.running.lock().unwrap();
+            *running = false;
+        }
+
+        Ok(())
+    }
+}
+
+impl Request for RequestTakebackRequest {
+    fn execute(&mut self) -> Result<()> {
+        let (res_type, res) = error_check(self.handler())?;
+
+        if let Err(e) = self.stream.send(res_type, &res) {
+            return Err(anyhow!(format!("couldn't send: {e:?}")));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+use std::net::TcpStream;
+
+use anyhow::{anyhow, Result};
+use network::SendRecv;
+use rules::EndReason;
+
+use crate::core::{
+    db::{Db, StorageError},
+    request_handlers::error_check,
+    status::Action,
+    types::{BoolMutex, LobbyId, LobbyName, Status, UsersVec},
+};
+
+use super::{error::ServerError, make_move::finish_game, Request};
+
+pub struct ResignRequest {
+    stream: TcpStream,
+    token: String,
+    lobby_id: LobbyId,
+    lobby_name: LobbyName,
+    users: UsersVec,
+    status: Status,
+    running: BoolMutex,
+    db: Db,
+}
+
+impl ResignRequest {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        stream: TcpStream,
+        token: String,
+        lobby_id: LobbyId,
+        lobby_name: LobbyName,
+        users: UsersVec,
+        status: Status,
+        running: BoolMutex,
+        db: Db,
+    ) -> ResignRequest {
+        ResignRequest {
+            stream,
+            token,
+            lobby_id,
+            lobby_name,
+            users,
+            status,
+            running,
+            db,
+        }
+    }
+
+    fn handler(&self) -> Result<(), ServerError> {
+        let db_user = match self.db.get_session_user(&self.token) {
+            Ok(Some(db_user)) => db_user,
+            Ok(None) => return Err(ServerError::ApiNotConnected),
+            Err(StorageError::NotFound) => {
+                return Err(ServerError::Api {
+                    message: "invalid session token".to_string(),
+                })
+            }
+            Err(e) => return Err(ServerError::InternalStorage(e)),
+        };
+
+        let mut status = self.status.lock().unwrap();
+
+        if let Err(message) = status.check(&Action::EndGame) {
+            return Err(ServerError::Api {
+                message: message.to_string(),
+            });
+        }
+
+        let role = match status.game().unwrap().role_of(db_user.id) {
+            Some(role) => role,
+            None => {
+                return Err(ServerError::Api {
+                    message: "you are not playing".to_string(),
+                })
+            }
+        };
+
+        let mut users = self.users.lock().unwrap();
+
+        let lobby_id = { *self.lobby_id.lock().unwrap() };
+        let lobby_name = { self.lobby_name.lock().unwrap().clone() };
+
+        finish_game(
+            &mut status,
+            &mut users,
+            &self.running,
+            &self.db,
+            (lobby_id, &lobby_name),
+            Some(role.opponent()),
+            EndReason::Resigned,
+        )
+    }
+}
+
+impl Request for ResignRequest {
+    fn execute(&mut self) -> Result<()> {
+        let (res_type, res) = error_check(self.handler())?;
+
+        if let Err(e) = self.stream.send(res_type, &res) {
+            return Err(anyhow!(format!("couldn't send: {e:?}")));
+        }
+
+        Ok(())
+    }
+}
@@ -2,56 +2,55 @@ use std::net::TcpStream;
 
 use anyhow::{anyhow, Result};
 use network::{SendRecv, Type};
-use r2d2::Pool;
-use r2d2_sqlite::SqliteConnectionManager;
 
 use crate::core::{
-    db::UserOps,
+    db::{Db, StorageError},
     request_handlers::{dispatch, error_check},
-    types::{BoolMutex, UsersVec},
+    types::{BoolMutex, LobbyId, UsersVec},
 };
 
 use super::{error::ServerError, Request};
 
 pub struct SendMessageRequest {
     stream: TcpStream,
-    user_id: u32,
+    token: String,
     message: String,
+    lobby_id: LobbyId,
     users: UsersVec,
     running: BoolMutex,
-    db_pool: Pool<SqliteConnectionManager>,
+    db: Db,
 }
 
 impl SendMessageRequest {
     pub fn new(
         stream: TcpStream,
-        data: (u32, String),
+        data: (String, String),
+        lobby_id: LobbyId,
         users: UsersVec,
         running: BoolMutex,
-        db_pool: Pool<SqliteConnectionManager>,
+        db: Db,
     ) -> SendMessageRequest {
         SendMessageRequest {
             stream,
-            user_id: data.0,
+            token: data.0,
             message: data.1,
+            lobby_id,
             users,
             running,
-            db_pool,
+            db,
         }
     }
 
     fn handler(&self) -> Result<(), ServerError> {
-        let conn = self.db_pool.get()?;
-
-        let db_user = match conn.is_connected(self.user_id) {
+        let db_user = match self.db.get_session_user(&self.token) {
             Ok(Some(db_user)) => db_user,
             Ok(None) => return Err(ServerError::ApiNotConnected),
-            Err(rusqlite::Error::QueryReturnedNoRows) => {
+            Err(StorageError::NotFound) => {
                 return Err(ServerError::Api {
-                    message: "invalid id".to_string(),
+                    message: "invalid session token".to_string(),
                 })
             }
-            Err(e) => return Err(ServerError::InternalRusqlite(e)),
+            Err(e) => return Err(ServerError::InternalStorage(e)),
         };
 
         if self.message.is_empty() || self.message.len() > 256 {
@@ -60,8 +59,19 @@ impl SendMessageRequest {
             });
         }
 
+        let lobby_id = { *self.lobby_id.lock().unwrap() };
+
         let mut users = self.users.lock().unwrap();
 
+        if !users.iter().any(|user| user.id == db_user.id) {
+            return Err(ServerError::Api {
+                message: "you are not connected to this lobby".to_string(),
+            });
+        }
+
+        // stored while the users are locked so the history has the same order as the live messages
+        self.db.add_message(lobby_id, db_user.id, &self.message)?;
+
         if let Err(ServerError::InternalShutDown) = dispatch(
             &mut users,
             vec![(Type::Message, &(db_user.name, self.message.clone()))],
@@ -1,101 +1,281 @@
-use std::net::TcpStream;
+use std::{net::TcpStream, sync::Arc, thread};
 
 use anyhow::{anyhow, Result};
 use network::{SendRecv, Type};
-use r2d2::Pool;
-use r2d2_sqlite::SqliteConnectionManager;
+use rules::{GameSettings, GameState, MatchState};
 
 use crate::core::{
-    db::UserOps,
-    game::GameState,
-    request_handlers::{dispatch, error_check},
-    types::{BoolMutex, Game, UserType, UsersVec},
+    db::{Db, StorageError},
+    request_handlers::{dispatch, dispatch_phases, error_check},
+    status::{Action, LobbyStatus, COUNTDOWN},
+    types::{BoolMutex, LobbyId, LobbyName, Settings, Status, UserInfo, UserType, UsersVec},
 };
 
-use super::{error::ServerError, Request};
+use super::{
+    clock::watch_clock,
+    error::ServerError,
+    make_move::{abandon_match, computer_moves, finish_game},
+    Request,
+};
 
 pub struct StartGameRequest {
     stream: TcpStream,
-    user_id: u32,
+    token: String,
+    lobby_id: LobbyId,
+    lobby_name: LobbyName,
     users: UsersVec,
-    game: Game,
+    settings: Settings,
+    status: Status,
     running: BoolMutex,
-    db_pool: Pool<SqliteConnectionManager>,
+    db: Db,
 }
 
 impl StartGameRequest {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         stream: TcpStream,
-        user_id: u32,
+        token: String,
+        lobby_id: LobbyId,
+        lobby_name: LobbyName,
         users: UsersVec,
-        game: Game,
+        settings: Settings,
+        status: Status,
         running: BoolMutex,
-        db_pool: Pool<SqliteConnectionManager>,
+        db: Db,
     ) -> StartGameRequest {
         StartGameRequest {
             stream,
-            user_id,
+            token,
+            lobby_id,
+            lobby_name,
             users,
-            game,
+            settings,
+            status,
             running,
-            db_pool,
+            db,
         }
     }
 
     fn handler(&self) -> Result<(), ServerError> {
-        let conn = self.db_pool.get()?;
-
-        let _ = match conn.is_connected(self.user_id) {
+        let db_user = match self.db.get_session_user(&self.token) {
             Ok(Some(db_user)) => db_user,
             Ok(None) => return Err(ServerError::ApiNotConnected),
-            Err(rusqlite::Error::QueryReturnedNoRows) => {
+            Err(StorageError::NotFound) => {
                 return Err(ServerError::Api {
-                    message: "invalid id".to_string(),
+                    message: "invalid session token".to_string(),
                 })
             }
-            Err(e) => return Err(ServerError::InternalRusqlite(e)),
+            Err(e) => return Err(ServerError::InternalStorage(e)),
         };
 
-        let mut game = self.game.lock().unwrap();
+        // the players are picked under the locks but the board is built outside of them, a rated
+        // board can take a few hundred tries
+        let (players, settings, (angel, devil, series)) = {
+            let status = self.status.lock().unwrap();
+            let users = self.users.lock().unwrap();
 
-        if game.is_some() {
-            return Err(ServerError::Api {
-                message: "game is already started".to_string(),
-            });
-        }
+            if !users
+                .iter()
+                .any(|user| user.id == db_user.id && user.user_type == UserType::Host)
+            {
+                return Err(ServerError::Api {
+                    message: "you are not the host".to_string(),
+                });
+            }
 
-        let mut users = self.users.lock().unwrap();
+            status.can_start().map_err(|message| ServerError::Api {
+                message: message.to_string(),
+            })?;
+
+            let settings = { self.settings.lock().unwrap().clone() };
+            let next = next_game(&users, &settings, &status);
+
+            (host_and_player(&users), settings, next)
+        };
+
+        let game = GameState::new(angel, devil, &settings);
 
-        let (mut angel, mut devil) = (0, 0);
-        users.iter().for_each(|u| match u.user_type {
-            UserType::Host => devil = u.id,
-            UserType::Player => angel = u.id,
-            _ => {}
-        });
+        let mut status = self.status.lock().unwrap();
+        let mut users = self.users.lock().unwrap();
 
-        if devil != self.user_id {
+        if host_and_player(&users) != players || *self.settings.lock().unwrap() != settings {
             return Err(ServerError::Api {
-                message: "you are not the host".to_string(),
+                message: "the lobby changed while the game was set up".to_string(),
             });
         }
 
-        let game_state = GameState::new(angel, devil);
+        // another request may have started a game while the board was built
+        let action = Action::StartGame(Box::new(game), series);
+        status.check(&action).map_err(|message| ServerError::Api {
+            message: message.to_string(),
+        })?;
 
-        if let Err(ServerError::InternalShutDown) = dispatch(
+        let lobby_id = { *self.lobby_id.lock().unwrap() };
+        let lobby_name = { self.lobby_name.lock().unwrap().clone() };
+        abandon_match(
+            &status,
+            &action,
             &mut users,
-            vec![(Type::GameStarted, &game_state)],
-            |_| {},
-        ) {
+            &self.running,
+            &self.db,
+            (lobby_id, &lobby_name),
+        )?;
+
+        let changes = status.apply(action).map_err(|message| ServerError::Api {
+            message: message.to_string(),
+        })?;
+
+        if let Err(ServerError::InternalShutDown) = dispatch_phases(&mut users, &changes) {
             let mut running = self.running.lock().unwrap();
             *running = false;
         }
 
-        *game = Some(game_state);
+        count_down(
+            &self.users,
+            &self.status,
+            &self.running,
+            &self.db,
+            &self.lobby_id,
+            &self.lobby_name,
+        );
 
         Ok(())
     }
 }
 
+// the (angel, devil) of the next game between the host and the player and the match it's part of,
+// without a player the computer takes the side the host doesn't and never plays matches
+pub fn next_game(
+    users: &[UserInfo],
+    settings: &GameSettings,
+    status: &LobbyStatus,
+) -> (u32, u32, Option<MatchState>) {
+    let (host, player) = host_and_player(users);
+
+    // a match that isn't decided yet goes on while the same two are playing
+    if let Some(series) = status.series() {
+        if !series.is_over() && series.has_player(host) && series.has_player(player) {
+            let (angel, devil) = series.next_roles();
+            return (angel, devil, Some(series.clone()));
+        }
+    }
+
+    // a rematch of the same two swaps the sides of the last game if the lobby wants it
+    let (angel, devil) = match status.rematch() {
+        Some(last)
+            if settings.swap_roles
+                && ((last.angel, last.devil) == (host, player)
+                    || (last.angel, last.devil) == (player, host)) =>
+        {
+            (last.devil, last.angel)
+        }
+        _ => settings.roles.assign(host, player),
+    };
+
+    let series = (settings.match_games > 1 && player != 0)
+        .then(|| MatchState::new(settings.match_games, devil, angel, settings.match_rated));
+
+    (angel, devil, series)
+}
+
+// the ids of the host and the player, 0 if there is none
+fn host_and_player(users: &[UserInfo]) -> (u32, u32) {
+    let (mut host, mut player) = (0, 0);
+    users.iter().for_each(|u| match u.user_type {
+        UserType::Host => host = u.id,
+        UserType::Player => player = u.id,
+        _ => {}
+    });
+
+    (host, player)
+}
+
+// begins the game that was just set up once the countdown is over
+pub fn count_down(
+    users: &UsersVec,
+    status: &Status,
+    running: &BoolMutex,
+    db: &Db,
+    lobby_id: &LobbyId,
+    lobby_name: &LobbyName,
+) {
+    let (users, status, running, db, lobby_id, lobby_name) = (
+        Arc::clone(users),
+        Arc::clone(status),
+        Arc::clone(running),
+        Arc::clone(db),
+        Arc::clone(lobby_id),
+        Arc::clone(lobby_name),
+    );
+
+    thread::spawn(move || {
+        thread::sleep(COUNTDOWN);
+
+        if let Err(e) = begin_game(&users, &status, &running, &db, &lobby_id, &lobby_name) {
+            let lobby_id = { *lobby_id.lock().unwrap() };
+            println!("couldn't begin the game of lobby {lobby_id}: {e:?}");
+            return;
+        }
+
+        watch_clock(&users, &status, &running, &db, &lobby_id, &lobby_name);
+    });
+}
+
+// called once the countdown is over, the players get the board and the first move can be made,
+// by the computer if it plays the devil
+fn begin_game(
+    users: &UsersVec,
+    status: &Status,
+    running: &BoolMutex,
+    db: &Db,
+    lobby_id: &LobbyId,
+    lobby_name: &LobbyName,
+) -> Result<(), ServerError> {
+    let mut status = status.lock().unwrap();
+    let mut users = users.lock().unwrap();
+
+    let changes = status
+        .apply(Action::BeginGame)
+        .map_err(|message| ServerError::Api {
+            message: message.to_string(),
+        })?;
+
+    if let Err(ServerError::InternalShutDown) = dispatch_phases(&mut users, &changes) {
+        let mut running = running.lock().unwrap();
+        *running = false;
+    }
+
+    let lobby_id = { *lobby_id.lock().unwrap() };
+    db.set_lobby_match(lobby_id, status.series())?;
+
+    if let Some(game) = status.game_mut() {
+        if let Err(ServerError::InternalShutDown) =
+            dispatch(&mut users, vec![(Type::GameStarted, &*game)], |_| {})
+        {
+            let mut running = running.lock().unwrap();
+            *running = false;
+        }
+
+        if let Some((winner, reason)) = computer_moves(game, &mut users, running) {
+            let lobby_name = { lobby_name.lock().unwrap().clone() };
+
+            return finish_game(
+                &mut status,
+                &mut users,
+                running,
+                db,
+                (lobby_id, &lobby_name),
+                Some(winner),
+                reason,
+            );
+        }
+    }
+
+    db.set_lobby_game(lobby_id, status.game())?;
+
+    Ok(())
+}
+
 impl Request for StartGameRequest {
     fn execute(&mut self) -> Result<()> {
         let (res_type, res) = error_check(self.handler())?;
@@ -0,0 +1,135 @@
+use std::net::TcpStream;
+
+use anyhow::{anyhow, Result};
+use network::SendRecv;
+
+use crate::core::{
+    db::{Db, StorageError},
+    request_handlers::error_check,
+    types::{BoolMutex, LobbyId, LobbyName, LobbyState, Registry, Settings, Status, UsersVec},
+};
+
+use super::{
+    clock::resume_clock, error::ServerError, join_lobby::join_lobby, leave_lobby::leave_lobby,
+    Request, CHAT_BACKLOG,
+};
+
+// leaves the lobby the user is currently in and joins this one, both happen while holding the
+// registry so no other request can observe the user in between lobbies
+pub struct SwitchLobbyRequest {
+    stream: TcpStream,
+    token: String,
+    lobby_id: LobbyId,
+    lobby_name: LobbyName,
+    users: UsersVec,
+    settings: Settings,
+    status: Status,
+    running: BoolMutex,
+    registry: Registry,
+    db: Db,
+}
+
+impl SwitchLobbyRequest {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        stream: TcpStream,
+        token: String,
+        lobby_id: LobbyId,
+        lobby_name: LobbyName,
+        users: UsersVec,
+        settings: Settings,
+        status: Status,
+        running: BoolMutex,
+        registry: Registry,
+        db: Db,
+    ) -> SwitchLobbyRequest {
+        SwitchLobbyRequest {
+            stream,
+            token,
+            lobby_id,
+            lobby_name,
+            users,
+            settings,
+            status,
+            running,
+            registry,
+            db,
+        }
+    }
+
+    fn handler(&self) -> Result<LobbyState, ServerError> {
+        let db_user = match self.db.get_session_user(&self.token) {
+            Ok(Some(db_user)) => db_user,
+            Ok(None) => return Err(ServerError::ApiNotConnected),
+            Err(StorageError::NotFound) => {
+                return Err(ServerError::Api {
+                    message: "invalid session token".to_string(),
+                })
+            }
+            Err(e) => return Err(ServerError::InternalStorage(e)),
+        };
+
+        let addr = db_user.addr.parse()?;
+        let lobby_id = { *self.lobby_id.lock().unwrap() };
+
+        let mut registry = self.registry.lock().unwrap();
+
+        let old_lobby_id = match registry.lobby_of(db_user.id) {
+            Some(id) if id == lobby_id => {
+                return Err(ServerError::Api {
+                    message: "you are already connected to this lobby".to_string(),
+                })
+            }
+            Some(id) => id,
+            None => {
+                return Err(ServerError::Api {
+                    message: "you are not connected to a lobby".to_string(),
+                })
+            }
+        };
+
+        // read before leaving so a storage error doesn't leave the user without a lobby
+        let chat = self.db.get_messages(lobby_id, None, CHAT_BACKLOG)?;
+
+        // lobby_of only returns lobbies that are registered
+        let old_lobby = registry.get_lobby(old_lobby_id).unwrap();
+
+        leave_lobby(db_user.id, old_lobby_id, &old_lobby, &self.db)?;
+        registry.leave(db_user.id);
+
+        let lobby_state = join_lobby(
+            (db_user.id, db_user.name, addr),
+            &self.lobby_name,
+            &self.users,
+            &self.settings,
+            &self.status,
+            &self.running,
+            chat,
+        );
+
+        registry.join(db_user.id, lobby_id);
+
+        resume_clock(
+            &self.users,
+            &self.status,
+            &self.running,
+            &self.db,
+            &self.lobby_id,
+            &self.lobby_name,
+        );
+
+        Ok(lobby_state)
+    }
+}
+
+impl Request for SwitchLobbyRequest {
+    fn execute(&mut self) -> Result<()> {
+        let (res_type, res) = error_check(self.handler())?;
+
+        if let Err(e) = self.stream.send(res_type, &res) {
+            return Err(anyhow!(format!("couldn't send: {e:?}")));
+        }
+
+        Ok(())
+    }
+}
@@ -22,6 +22,7 @@ pub use server::*;
 
 use error::ServerError;
 
+use super::status::PhaseChange;
 use super::types::{UserInfo, UserInfoShort, UserType};
 
 pub trait Request {
@@ -113,3 +114,20 @@ pub fn dispatch<S: Serialize, F: Fn(&mut UserInfo)>(
 
     Ok(())
 }
+
+// lets the users know about every phase the lobby went through
+pub fn dispatch_phases(
+    users: &mut Vec<UserInfo>,
+    changes: &[PhaseChange],
+) -> Result<(), ServerError> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    let events = changes
+        .iter()
+        .map(|change| (Type::PhaseChanged, change))
+        .collect();
+
+    dispatch(users, events, |_| {})
+}
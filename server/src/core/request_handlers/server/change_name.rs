@@ -2,59 +2,95 @@ use std::net::TcpStream;
 
 use anyhow::{anyhow, Result};
 
-use r2d2::Pool;
-use r2d2_sqlite::SqliteConnectionManager;
+use network::{SendRecv, Type};
 
-use network::SendRecv;
+use crate::core::{
+    db::{Db, StorageError},
+    request_handlers::{dispatch, error_check},
+    types::{Registry, UserInfoShort},
+};
 
-use crate::core::{db::UserOps, request_handlers::error_check};
-
-use super::{error::ServerError, Request};
+use super::{error::ServerError, validate_name, Request};
 
 pub struct ChangeNameRequest {
     stream: TcpStream,
-    user_id: u32,
+    token: String,
     name: String,
-    db_pool: Pool<SqliteConnectionManager>,
+    registry: Registry,
+    db: Db,
 }
 
 impl ChangeNameRequest {
     pub fn new(
         stream: TcpStream,
-        data: (u32, String),
-        db_pool: Pool<SqliteConnectionManager>,
+        data: (String, String),
+        registry: Registry,
+        db: Db,
     ) -> ChangeNameRequest {
         ChangeNameRequest {
             stream,
-            user_id: data.0,
+            token: data.0,
             name: data.1,
-            db_pool,
+            registry,
+            db,
         }
     }
 
     fn handler(&self) -> Result<(), ServerError> {
-        let conn = self.db_pool.get()?;
-
-        let _ = match conn.is_connected(self.user_id) {
+        let db_user = match self.db.get_session_user(&self.token) {
             Ok(Some(db_user)) => db_user,
             Ok(None) => return Err(ServerError::ApiNotConnected),
-            Err(rusqlite::Error::QueryReturnedNoRows) => {
+            Err(StorageError::NotFound) => {
                 return Err(ServerError::Api {
-                    message: "invalid id".to_string(),
+                    message: "invalid session token".to_string(),
                 })
             }
-            Err(e) => return Err(ServerError::InternalRusqlite(e)),
+            Err(e) => return Err(ServerError::InternalStorage(e)),
         };
 
-        if !(2 <= self.name.len() && self.name.len() <= 32) {
+        validate_name(&self.name)?;
+
+        if self.db.name_taken(db_user.id, &self.name)? {
             return Err(ServerError::Api {
-                message: "username must be between 2 and 32 characters".to_string(),
+                message: "username is already taken".to_string(),
             });
         }
 
-        match conn.change_user_name(self.user_id, &self.name) {
+        // hold the registry while renaming so the user can't join or leave a lobby in between
+        let mut registry = self.registry.lock().unwrap();
+
+        match self.db.change_user_name(db_user.id, &self.name) {
             Ok(_) => {}
-            Err(e) => return Err(ServerError::InternalRusqlite(e)),
+            Err(e) => return Err(ServerError::InternalStorage(e)),
+        }
+
+        let lobby_id = match registry.lobby_of(db_user.id) {
+            Some(lobby_id) => lobby_id,
+            None => return Ok(()),
+        };
+
+        // lobby_of only returns lobbies that are registered
+        let lobby = registry.get_lobby(lobby_id).unwrap();
+        let mut users = lobby.users.lock().unwrap();
+
+        let mut new_user = match users.iter().find(|user| user.id == db_user.id) {
+            Some(user) => UserInfoShort::from(user),
+            None => return Ok(()),
+        };
+
+        new_user.name = self.name.clone();
+
+        if let Err(ServerError::InternalShutDown) = dispatch(
+            &mut users,
+            vec![(Type::PlayerUpdated, &new_user)],
+            |user| {
+                if user.id == db_user.id {
+                    user.name = self.name.clone();
+                }
+            },
+        ) {
+            let mut running = lobby.running.lock().unwrap();
+            *running = false;
         }
 
         Ok(())
@@ -2,69 +2,68 @@ use std::net::{SocketAddr, TcpStream};
 
 use anyhow::{anyhow, Result};
 
-use r2d2::Pool;
-use r2d2_sqlite::SqliteConnectionManager;
-
 use network::SendRecv;
 
-use crate::core::{db::UserOps, request_handlers::error_check};
+use crate::core::{
+    auth::generate_token,
+    db::{Db, StorageError},
+    request_handlers::error_check,
+};
 
-use super::{error::ServerError, Request};
+use super::{error::ServerError, validate_name, Request};
 
 pub struct ConnectRequest {
     stream: TcpStream,
     name: String,
     addr: SocketAddr,
-    db_pool: Pool<SqliteConnectionManager>,
+    db: Db,
 }
 
 impl ConnectRequest {
-    pub fn new(
-        stream: TcpStream,
-        data: (String, SocketAddr),
-        db_pool: Pool<SqliteConnectionManager>,
-    ) -> ConnectRequest {
+    pub fn new(stream: TcpStream, data: (String, SocketAddr), db: Db) -> ConnectRequest {
         ConnectRequest {
             stream,
             name: data.0,
             addr: data.1,
-            db_pool,
+            db,
         }
     }
 
-    fn handler(&self) -> Result<u32, ServerError> {
-        let conn = self.db_pool.get()?;
+    fn handler(&self) -> Result<(u32, String), ServerError> {
+        validate_name(&self.name)?;
+
+        // a guest coming back under its own name doesn't take it from itself, ids start at 1
+        let id = match self.db.get_user_by_key(&self.name, &self.addr.to_string()) {
+            Ok(db_user) => db_user.id,
+            Err(StorageError::NotFound) => 0,
+            Err(e) => return Err(ServerError::InternalStorage(e)),
+        };
 
-        if !(2 <= self.name.len() && self.name.len() <= 32) {
+        if self.db.name_taken(id, &self.name)? {
             return Err(ServerError::Api {
-                message: "username must be between 2 and 32 characters".to_string(),
+                message: "username is already taken".to_string(),
             });
         }
 
-        match conn.add_user(&self.name, &self.addr.to_string()) {
-            Ok(_) => {}
-            Err(rusqlite::Error::SqliteFailure(e, Some(m))) => {
-                if e.code != rusqlite::ErrorCode::ConstraintViolation {
-                    return Err(ServerError::InternalRusqlite(
-                        rusqlite::Error::SqliteFailure(e, Some(m)),
-                    ));
-                }
-
-                // user is already added to db, just continue the handler
-            }
-            Err(e) => return Err(ServerError::InternalRusqlite(e)),
+        match self.db.add_user(&self.name, &self.addr.to_string()) {
+            // user is already added to db, just continue the handler
+            Ok(_) | Err(StorageError::AlreadyExists) => {}
+            Err(e) => return Err(ServerError::InternalStorage(e)),
         }
 
-        let db_user = match conn.get_user_by_key(&self.name, &self.addr.to_string()) {
+        let db_user = match self.db.get_user_by_key(&self.name, &self.addr.to_string()) {
             Ok(db_user) => db_user,
-            Err(e) => return Err(ServerError::InternalRusqlite(e)),
+            Err(e) => return Err(ServerError::InternalStorage(e)),
         };
 
         if db_user.connected == 0 {
-            conn.toggle_connected(db_user.id)?;
+            self.db.toggle_connected(db_user.id)?;
         }
 
-        Ok(db_user.id)
+        let token = generate_token();
+        self.db.add_session(&token, db_user.id)?;
+
+        Ok((db_user.id, token))
     }
 }
 
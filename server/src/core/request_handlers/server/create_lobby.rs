@@ -1,58 +1,62 @@
-use std::{net::TcpStream, sync::Arc, thread};
+use std::{net::TcpStream, sync::Arc};
 
 use anyhow::{anyhow, Result};
 use network::SendRecv;
-use r2d2::Pool;
-use r2d2_sqlite::SqliteConnectionManager;
+use rules::GameSettings;
 
 use crate::core::{
-    db::UserOps,
-    lobby::Lobby,
+    db::{Db, LobbyRecord, StorageError},
+    lobby::{start_lobby, Lobby},
     request_handlers::error_check,
-    types::{LobbyAddr, LobbyId, LobbyVec},
+    types::{BoolMutex, LobbyAddr, LobbyId, LobbyVec, Registry},
 };
 
 use super::{error::ServerError, Request};
 
 pub struct CreateLobbyRequest {
     stream: TcpStream,
-    user_id: u32,
+    token: String,
     name: String,
     lobby_id: LobbyId,
     lobbies: LobbyVec,
-    db_pool: Pool<SqliteConnectionManager>,
+    registry: Registry,
+    server_running: BoolMutex,
+    db: Db,
 }
 
 impl CreateLobbyRequest {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         stream: TcpStream,
-        data: (u32, String),
+        data: (String, String),
         lobby_id: LobbyId,
         lobbies: LobbyVec,
-        db_pool: Pool<SqliteConnectionManager>,
+        registry: Registry,
+        server_running: BoolMutex,
+        db: Db,
     ) -> CreateLobbyRequest {
         CreateLobbyRequest {
             stream,
-            user_id: data.0,
+            token: data.0,
             name: data.1,
             lobby_id,
             lobbies,
-            db_pool,
+            registry,
+            server_running,
+            db,
         }
     }
 
     fn handler(&self) -> Result<LobbyAddr, ServerError> {
-        let conn = self.db_pool.get()?;
-
-        let _ = match conn.is_connected(self.user_id) {
+        let _ = match self.db.get_session_user(&self.token) {
             Ok(Some(db_user)) => db_user,
             Ok(None) => return Err(ServerError::ApiNotConnected),
-            Err(rusqlite::Error::QueryReturnedNoRows) => {
+            Err(StorageError::NotFound) => {
                 return Err(ServerError::Api {
-                    message: "invalid id".to_string(),
+                    message: "invalid session token".to_string(),
                 })
             }
-            Err(e) => return Err(ServerError::InternalRusqlite(e)),
+            Err(e) => return Err(ServerError::InternalStorage(e)),
         };
 
         let id = {
@@ -69,21 +73,25 @@ impl CreateLobbyRequest {
             format!("Lobby {id}")
         };
 
-        let lobby = Lobby::new("127.0.0.1:0", id, lobby_name).unwrap();
-        let (addr, running) = (lobby.get_addr()?, Arc::clone(&lobby.server.running));
-
-        let handle = thread::spawn(move || {
-            lobby.start().unwrap();
-        });
-
-        println!("lobby {} started", id);
-
-        {
-            let mut lobbies = self.lobbies.lock().unwrap();
-            lobbies.push((id, addr, running, handle));
-        }
-
-        Ok(LobbyAddr { id, addr })
+        let settings = GameSettings::default();
+
+        let lobby = Lobby::new(
+            "127.0.0.1:0",
+            LobbyRecord {
+                id,
+                name: lobby_name.clone(),
+                settings: settings.clone(),
+                game: None,
+                series: None,
+            },
+            Arc::clone(&self.registry),
+            Arc::clone(&self.db),
+            Arc::clone(&self.server_running),
+        )?;
+
+        self.db.add_lobby(id, &lobby_name, &settings)?;
+
+        Ok(start_lobby(lobby, &self.lobbies, &self.registry)?)
     }
 }
 
@@ -2,49 +2,44 @@ use std::net::TcpStream;
 
 use anyhow::{anyhow, Result};
 
-use r2d2::Pool;
-use r2d2_sqlite::SqliteConnectionManager;
-
 use network::SendRecv;
 
-use crate::core::{db::UserOps, request_handlers::error_check};
+use crate::core::{
+    db::{Db, StorageError},
+    request_handlers::error_check,
+};
 
 use super::{error::ServerError, Request};
 
 pub struct DisconnectRequest {
     stream: TcpStream,
-    user_id: u32,
-    db_pool: Pool<SqliteConnectionManager>,
+    token: String,
+    db: Db,
 }
 
 impl DisconnectRequest {
-    pub fn new(
-        stream: TcpStream,
-        user_id: u32,
-        db_pool: Pool<SqliteConnectionManager>,
-    ) -> DisconnectRequest {
-        DisconnectRequest {
-            stream,
-            user_id,
-            db_pool,
-        }
+    pub fn new(stream: TcpStream, token: String, db: Db) -> DisconnectRequest {
+        DisconnectRequest { stream, token, db }
     }
 
     fn handler(&self) -> Result<(), ServerError> {
-        let conn = self.db_pool.get()?;
-
-        let db_user = match conn.is_connected(self.user_id) {
+        let db_user = match self.db.get_session_user(&self.token) {
             Ok(Some(db_user)) => db_user,
             Ok(None) => return Err(ServerError::ApiNotConnected),
-            Err(rusqlite::Error::QueryReturnedNoRows) => {
+            Err(StorageError::NotFound) => {
                 return Err(ServerError::Api {
-                    message: "invalid id".to_string(),
+                    message: "invalid session token".to_string(),
                 })
             }
-            Err(e) => return Err(ServerError::InternalRusqlite(e)),
+            Err(e) => return Err(ServerError::InternalStorage(e)),
         };
 
-        conn.toggle_connected(db_user.id)?;
+        self.db.revoke_session(&self.token)?;
+
+        // accounts can be logged in from several clients, each with its own session
+        if !self.db.has_sessions(db_user.id)? {
+            self.db.toggle_connected(db_user.id)?;
+        }
 
         Ok(())
     }
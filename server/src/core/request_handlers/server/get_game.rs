@@ -0,0 +1,61 @@
+use std::net::TcpStream;
+
+use anyhow::{anyhow, Result};
+use network::SendRecv;
+
+use crate::core::db::{Db, GameRecord, StorageError};
+use crate::core::request_handlers::error_check;
+
+use super::error::ServerError;
+use super::Request;
+
+pub struct GetGameRequest {
+    stream: TcpStream,
+    token: String,
+    game_id: u32,
+    db: Db,
+}
+
+impl GetGameRequest {
+    pub fn new(stream: TcpStream, data: (String, u32), db: Db) -> GetGameRequest {
+        GetGameRequest {
+            stream,
+            token: data.0,
+            game_id: data.1,
+            db,
+        }
+    }
+
+    fn handler(&self) -> Result<GameRecord, ServerError> {
+        let _ = match self.db.get_session_user(&self.token) {
+            Ok(Some(db_user)) => db_user,
+            Ok(None) => return Err(ServerError::ApiNotConnected),
+            Err(StorageError::NotFound) => {
+                return Err(ServerError::Api {
+                    message: "invalid session token".to_string(),
+                })
+            }
+            Err(e) => return Err(ServerError::InternalStorage(e)),
+        };
+
+        match self.db.get_game(self.game_id) {
+            Ok(game) => Ok(game),
+            Err(StorageError::NotFound) => Err(ServerError::Api {
+                message: "game not found".to_string(),
+            }),
+            Err(e) => Err(ServerError::InternalStorage(e)),
+        }
+    }
+}
+
+impl Request for GetGameRequest {
+    fn execute(&mut self) -> Result<()> {
+        let (res_type, res) = error_check(self.handler())?;
+
+        if let Err(e) = self.stream.send(res_type, &res) {
+            return Err(anyhow!(format!("couldn't send: {e:?}")));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+use std::net::TcpStream;
+
+use anyhow::{anyhow, Result};
+use network::SendRecv;
+
+use crate::core::db::{Db, GameSummary, StorageError};
+use crate::core::request_handlers::error_check;
+
+use super::error::ServerError;
+use super::Request;
+
+pub struct GetGameHistoryRequest {
+    stream: TcpStream,
+    token: String,
+    user_id: u32,
+    start: u32,
+    offset: u32,
+    db: Db,
+}
+
+impl GetGameHistoryRequest {
+    pub fn new(stream: TcpStream, data: (String, u32, u32, u32), db: Db) -> GetGameHistoryRequest {
+        GetGameHistoryRequest {
+            stream,
+            token: data.0,
+            user_id: data.1,
+            start: data.2,
+            offset: data.3,
+            db,
+        }
+    }
+
+    // games of the given user, most recent first
+    fn handler(&self) -> Result<Vec<GameSummary>, ServerError> {
+        let _ = match self.db.get_session_user(&self.token) {
+            Ok(Some(db_user)) => db_user,
+            Ok(None) => return Err(ServerError::ApiNotConnected),
+            Err(StorageError::NotFound) => {
+                return Err(ServerError::Api {
+                    message: "invalid session token".to_string(),
+                })
+            }
+            Err(e) => return Err(ServerError::InternalStorage(e)),
+        };
+
+        if self.offset > 20 {
+            return Err(ServerError::Api {
+                message: "offset can be at most 20".to_string(),
+            });
+        }
+
+        Ok(self
+            .db
+            .get_user_games(self.user_id, self.start, self.offset)?)
+    }
+}
+
+impl Request for GetGameHistoryRequest {
+    fn execute(&mut self) -> Result<()> {
+        let (res_type, res) = error_check(self.handler())?;
+
+        if let Err(e) = self.stream.send(res_type, &res) {
+            return Err(anyhow!(format!("couldn't send: {e:?}")));
+        }
+
+        Ok(())
+    }
+}
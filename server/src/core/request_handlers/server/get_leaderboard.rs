@@ -0,0 +1,69 @@
+use std::net::TcpStream;
+
+use anyhow::{anyhow, Result};
+use network::SendRecv;
+use rules::Role;
+
+use crate::core::db::{Db, Leaderboard, StorageError};
+use crate::core::request_handlers::error_check;
+
+use super::error::ServerError;
+use super::Request;
+
+pub struct GetLeaderboardRequest {
+    stream: TcpStream,
+    token: String,
+    role: Role,
+    start: u32,
+    offset: u32,
+    db: Db,
+}
+
+impl GetLeaderboardRequest {
+    pub fn new(stream: TcpStream, data: (String, Role, u32, u32), db: Db) -> GetLeaderboardRequest {
+        GetLeaderboardRequest {
+            stream,
+            token: data.0,
+            role: data.1,
+            start: data.2,
+            offset: data.3,
+            db,
+        }
+    }
+
+    // one page of the leaderboard for the given role along with the requesting user's rank
+    fn handler(&self) -> Result<Leaderboard, ServerError> {
+        let db_user = match self.db.get_session_user(&self.token) {
+            Ok(Some(db_user)) => db_user,
+            Ok(None) => return Err(ServerError::ApiNotConnected),
+            Err(StorageError::NotFound) => {
+                return Err(ServerError::Api {
+                    message: "invalid session token".to_string(),
+                })
+            }
+            Err(e) => return Err(ServerError::InternalStorage(e)),
+        };
+
+        if self.offset > 20 {
+            return Err(ServerError::Api {
+                message: "offset can be at most 20".to_string(),
+            });
+        }
+
+        Ok(self
+            .db
+            .get_leaderboard(self.role, db_user.id, self.start, self.offset)?)
+    }
+}
+
+impl Request for GetLeaderboardRequest {
+    fn execute(&mut self) -> Result<()> {
+        let (res_type, res) = error_check(self.handler())?;
+
+        if let Err(e) = self.stream.send(res_type, &res) {
+            return Err(anyhow!(format!("couldn't send: {e:?}")));
+        }
+
+        Ok(())
+    }
+}
@@ -2,10 +2,8 @@ use std::net::TcpStream;
 
 use anyhow::{anyhow, Result};
 use network::SendRecv;
-use r2d2::Pool;
-use r2d2_sqlite::SqliteConnectionManager;
 
-use crate::core::db::UserOps;
+use crate::core::db::{Db, StorageError};
 use crate::core::request_handlers::error_check;
 use crate::core::types::{LobbyAddr, LobbyVec};
 
@@ -14,42 +12,40 @@ use super::Request;
 
 pub struct GetLobbiesRequest {
     stream: TcpStream,
-    user_id: u32,
+    token: String,
     start: u32,
     offset: u32,
     lobbies: LobbyVec,
-    db_pool: Pool<SqliteConnectionManager>,
+    db: Db,
 }
 
 impl GetLobbiesRequest {
     pub fn new(
         stream: TcpStream,
-        data: (u32, u32, u32),
+        data: (String, u32, u32),
         lobbies: LobbyVec,
-        db_pool: Pool<SqliteConnectionManager>,
+        db: Db,
     ) -> GetLobbiesRequest {
         GetLobbiesRequest {
             stream,
-            user_id: data.0,
+            token: data.0,
             start: data.1,
             offset: data.2,
             lobbies,
-            db_pool,
+            db,
         }
     }
 
     fn handler(&self) -> Result<Vec<LobbyAddr>, ServerError> {
-        let conn = self.db_pool.get()?;
-
-        let _ = match conn.is_connected(self.user_id) {
+        let _ = match self.db.get_session_user(&self.token) {
             Ok(Some(db_user)) => db_user,
             Ok(None) => return Err(ServerError::ApiNotConnected),
-            Err(rusqlite::Error::QueryReturnedNoRows) => {
+            Err(StorageError::NotFound) => {
                 return Err(ServerError::Api {
-                    message: "invalid id".to_string(),
+                    message: "invalid session token".to_string(),
                 })
             }
-            Err(e) => return Err(ServerError::InternalRusqlite(e)),
+            Err(e) => return Err(ServerError::InternalStorage(e)),
         };
 
         if self.offset > 10 {
@@ -0,0 +1,61 @@
+use std::net::TcpStream;
+
+use anyhow::{anyhow, Result};
+use network::SendRecv;
+
+use crate::core::db::{Db, MatchRecord, StorageError};
+use crate::core::request_handlers::error_check;
+
+use super::error::ServerError;
+use super::Request;
+
+pub struct GetMatchRequest {
+    stream: TcpStream,
+    token: String,
+    match_id: u32,
+    db: Db,
+}
+
+impl GetMatchRequest {
+    pub fn new(stream: TcpStream, data: (String, u32), db: Db) -> GetMatchRequest {
+        GetMatchRequest {
+            stream,
+            token: data.0,
+            match_id: data.1,
+            db,
+        }
+    }
+
+    fn handler(&self) -> Result<MatchRecord, ServerError> {
+        let _ = match self.db.get_session_user(&self.token) {
+            Ok(Some(db_user)) => db_user,
+            Ok(None) => return Err(ServerError::ApiNotConnected),
+            Err(StorageError::NotFound) => {
+                return Err(ServerError::Api {
+                    message: "invalid session token".to_string(),
+                })
+            }
+            Err(e) => return Err(ServerError::InternalStorage(e)),
+        };
+
+        match self.db.get_match(self.match_id) {
+            Ok(series) => Ok(series),
+            Err(StorageError::NotFound) => Err(ServerError::Api {
+                message: "match not found".to_string(),
+            }),
+            Err(e) => Err(ServerError::InternalStorage(e)),
+        }
+    }
+}
+
+impl Request for GetMatchRequest {
+    fn execute(&mut self) -> Result<()> {
+        let (res_type, res) = error_check(self.handler())?;
+
+        if let Err(e) = self.stream.send(res_type, &res) {
+            return Err(anyhow!(format!("couldn't send: {e:?}")));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+use std::net::TcpStream;
+use std::time::Instant;
+
+use anyhow::{anyhow, Result};
+use network::SendRecv;
+
+use crate::core::db::{Db, Profile, ProfileStats, StorageError, RECENT_GAMES};
+use crate::core::request_handlers::error_check;
+use crate::core::types::{CachedProfile, ProfileCache};
+
+use super::error::ServerError;
+use super::Request;
+
+pub struct GetProfileRequest {
+    stream: TcpStream,
+    token: String,
+    user_id: u32,
+    profiles: ProfileCache,
+    db: Db,
+}
+
+impl GetProfileRequest {
+    pub fn new(
+        stream: TcpStream,
+        data: (String, u32),
+        profiles: ProfileCache,
+        db: Db,
+    ) -> GetProfileRequest {
+        GetProfileRequest {
+            stream,
+            token: data.0,
+            user_id: data.1,
+            profiles,
+            db,
+        }
+    }
+
+    fn handler(&self) -> Result<Profile, ServerError> {
+        let _ = match self.db.get_session_user(&self.token) {
+            Ok(Some(db_user)) => db_user,
+            Ok(None) => return Err(ServerError::ApiNotConnected),
+            Err(StorageError::NotFound) => {
+                return Err(ServerError::Api {
+                    message: "invalid session token".to_string(),
+                })
+            }
+            Err(e) => return Err(ServerError::InternalStorage(e)),
+        };
+
+        let user = match self.db.get_user_by_id(self.user_id) {
+            Ok(user) => user,
+            Err(StorageError::NotFound) => {
+                return Err(ServerError::Api {
+                    message: "user not found".to_string(),
+                })
+            }
+            Err(e) => return Err(ServerError::InternalStorage(e)),
+        };
+
+        let mut stats = profile_stats(&self.db, &self.profiles, user.id)?;
+        stats.recent = self.db.get_user_games(user.id, 0, RECENT_GAMES)?;
+
+        Ok(Profile {
+            id: user.id,
+            name: user.name.clone(),
+            guest: user.is_guest(),
+            angel_rating: user.angel_rating,
+            devil_rating: user.devil_rating,
+            stats,
+        })
+    }
+}
+
+// the most profiles kept in the cache, the one viewed the longest ago makes room for the next
+const MAX_CACHED_PROFILES: usize = 1000;
+
+// stats only change when a game ends, so they are computed again only if the user's last game
+// isn't the one the cached stats were computed from, the recent games are left out since the
+// names and matches they show change after they are played
+pub fn profile_stats(
+    db: &Db,
+    profiles: &ProfileCache,
+    user_id: u32,
+) -> Result<ProfileStats, ServerError> {
+    let last_game = db.get_last_game_id(user_id)?;
+
+    if let Some(cached) = profiles.lock().unwrap().get_mut(&user_id) {
+        if cached.last_game == last_game {
+            cached.viewed = Instant::now();
+            return Ok(cached.stats.clone());
+        }
+    }
+
+    let mut stats = db.get_profile_stats(user_id)?;
+    stats.recent.clear();
+
+    let mut profiles = profiles.lock().unwrap();
+
+    if profiles.len() >= MAX_CACHED_PROFILES && !profiles.contains_key(&user_id) {
+        let oldest = profiles
+            .iter()
+            .min_by_key(|(_, cached)| cached.viewed)
+            .map(|(&id, _)| id);
+
+        if let Some(id) = oldest {
+            profiles.remove(&id);
+        }
+    }
+
+    profiles.insert(
+        user_id,
+        CachedProfile {
+            last_game,
+            stats: stats.clone(),
+            viewed: Instant::now(),
+        },
+    );
+
+    Ok(stats)
+}
+
+impl Request for GetProfileRequest {
+    fn execute(&mut self) -> Result<()> {
+        let (res_type, res) = error_check(self.handler())?;
+
+        if let Err(e) = self.stream.send(res_type, &res) {
+            return Err(anyhow!(format!("couldn't send: {e:?}")));
+        }
+
+        Ok(())
+    }
+}
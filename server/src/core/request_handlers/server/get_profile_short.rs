@@ -0,0 +1,83 @@
+use std::net::TcpStream;
+
+use anyhow::{anyhow, Result};
+use network::SendRecv;
+
+use crate::core::db::{Db, ProfileShort, StorageError};
+use crate::core::request_handlers::error_check;
+use crate::core::types::ProfileCache;
+
+use super::error::ServerError;
+use super::get_profile::profile_stats;
+use super::Request;
+
+pub struct GetProfileShortRequest {
+    stream: TcpStream,
+    token: String,
+    user_id: u32,
+    profiles: ProfileCache,
+    db: Db,
+}
+
+impl GetProfileShortRequest {
+    pub fn new(
+        stream: TcpStream,
+        data: (String, u32),
+        profiles: ProfileCache,
+        db: Db,
+    ) -> GetProfileShortRequest {
+        GetProfileShortRequest {
+            stream,
+            token: data.0,
+            user_id: data.1,
+            profiles,
+            db,
+        }
+    }
+
+    fn handler(&self) -> Result<ProfileShort, ServerError> {
+        let _ = match self.db.get_session_user(&self.token) {
+            Ok(Some(db_user)) => db_user,
+            Ok(None) => return Err(ServerError::ApiNotConnected),
+            Err(StorageError::NotFound) => {
+                return Err(ServerError::Api {
+                    message: "invalid session token".to_string(),
+                })
+            }
+            Err(e) => return Err(ServerError::InternalStorage(e)),
+        };
+
+        let user = match self.db.get_user_by_id(self.user_id) {
+            Ok(user) => user,
+            Err(StorageError::NotFound) => {
+                return Err(ServerError::Api {
+                    message: "user not found".to_string(),
+                })
+            }
+            Err(e) => return Err(ServerError::InternalStorage(e)),
+        };
+
+        let stats = profile_stats(&self.db, &self.profiles, user.id)?;
+
+        Ok(ProfileShort {
+            id: user.id,
+            name: user.name,
+            angel_rating: user.angel_rating,
+            devil_rating: user.devil_rating,
+            games: stats.games,
+            wins: stats.angel.wins + stats.devil.wins,
+        })
+    }
+}
+
+impl Request for GetProfileShortRequest {
+    fn execute(&mut self) -> Result<()> {
+        let (res_type, res) = error_check(self.handler())?;
+
+        if let Err(e) = self.stream.send(res_type, &res) {
+            return Err(anyhow!(format!("couldn't send: {e:?}")));
+        }
+
+        Ok(())
+    }
+}
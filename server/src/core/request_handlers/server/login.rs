@@ -0,0 +1,71 @@
+use std::net::{SocketAddr, TcpStream};
+
+use anyhow::{anyhow, Result};
+
+use network::SendRecv;
+
+use crate::core::{
+    auth::{generate_token, verify_password},
+    db::{Db, StorageError},
+    request_handlers::error_check,
+};
+
+use super::{error::ServerError, Request};
+
+pub struct LoginRequest {
+    stream: TcpStream,
+    name: String,
+    password: String,
+    addr: SocketAddr,
+    db: Db,
+}
+
+impl LoginRequest {
+    pub fn new(stream: TcpStream, data: (String, String, SocketAddr), db: Db) -> LoginRequest {
+        LoginRequest {
+            stream,
+            name: data.0,
+            password: data.1,
+            addr: data.2,
+            db,
+        }
+    }
+
+    fn handler(&self) -> Result<(u32, String), ServerError> {
+        // the same message is used for both cases so names can't be probed
+        let invalid = ServerError::Api {
+            message: "invalid username or password".to_string(),
+        };
+
+        let db_user = match self.db.get_account(&self.name) {
+            Ok(db_user) => db_user,
+            Err(StorageError::NotFound) => return Err(invalid),
+            Err(e) => return Err(ServerError::InternalStorage(e)),
+        };
+
+        // get_account only returns users with a password
+        if !verify_password(&self.password, db_user.password.as_ref().unwrap()) {
+            return Err(invalid);
+        }
+
+        // events are sent to the address of the client that logged in last
+        self.db.log_in(db_user.id, &self.addr.to_string())?;
+
+        let token = generate_token();
+        self.db.add_session(&token, db_user.id)?;
+
+        Ok((db_user.id, token))
+    }
+}
+
+impl Request for LoginRequest {
+    fn execute(&mut self) -> Result<()> {
+        let (res_type, res) = error_check(self.handler())?;
+
+        if let Err(e) = self.stream.send(res_type, &res) {
+            return Err(anyhow!(format!("couldn't send: {e:?}")));
+        }
+
+        Ok(())
+    }
+}
@@ -1,14 +1,71 @@
 mod connect;
 mod disconnect;
+mod login;
+mod register;
 
 mod change_name;
 mod create_lobby;
 mod get_lobbies;
 
+mod get_game;
+mod get_game_history;
+mod get_leaderboard;
+mod get_match;
+mod get_profile;
+mod get_profile_short;
+
 pub use change_name::ChangeNameRequest;
 pub use connect::ConnectRequest;
 pub use create_lobby::CreateLobbyRequest;
 pub use disconnect::DisconnectRequest;
+pub use get_game::GetGameRequest;
+pub use get_game_history::GetGameHistoryRequest;
+pub use get_leaderboard::GetLeaderboardRequest;
+pub use get_match::GetMatchRequest;
+pub use get_profile::GetProfileRequest;
+pub use get_profile_short::GetProfileShortRequest;
 pub use get_lobbies::GetLobbiesRequest;
+pub use login::LoginRequest;
+pub use register::RegisterRequest;
 
 use super::{error, Request};
+
+use error::ServerError;
+
+// names that would be confused with the ones the server and the clients show
+const RESERVED_NAMES: [&str; 4] = ["computer", "server", "admin", "host"];
+
+fn validate_name(name: &str) -> Result<(), ServerError> {
+    if !(2 <= name.len() && name.len() <= 32) {
+        return Err(ServerError::Api {
+            message: "username must be between 2 and 32 characters".to_string(),
+        });
+    }
+
+    if name.trim() != name {
+        return Err(ServerError::Api {
+            message: "username cannot start or end with spaces".to_string(),
+        });
+    }
+
+    if RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(name))
+    {
+        return Err(ServerError::Api {
+            message: "this username is reserved".to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+fn validate_password(password: &str) -> Result<(), ServerError> {
+    if !(8 <= password.len() && password.len() <= 128) {
+        return Err(ServerError::Api {
+            message: "password must be between 8 and 128 characters".to_string(),
+        });
+    }
+
+    Ok(())
+}
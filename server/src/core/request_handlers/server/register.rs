@@ -0,0 +1,77 @@
+use std::net::{SocketAddr, TcpStream};
+
+use anyhow::{anyhow, Result};
+
+use network::SendRecv;
+
+use crate::core::{
+    auth::{generate_token, hash_password},
+    db::{Db, StorageError},
+    request_handlers::error_check,
+};
+
+use super::{error::ServerError, validate_name, validate_password, Request};
+
+pub struct RegisterRequest {
+    stream: TcpStream,
+    name: String,
+    password: String,
+    addr: SocketAddr,
+    db: Db,
+}
+
+impl RegisterRequest {
+    pub fn new(stream: TcpStream, data: (String, String, SocketAddr), db: Db) -> RegisterRequest {
+        RegisterRequest {
+            stream,
+            name: data.0,
+            password: data.1,
+            addr: data.2,
+            db,
+        }
+    }
+
+    fn handler(&self) -> Result<(u32, String), ServerError> {
+        validate_name(&self.name)?;
+        validate_password(&self.password)?;
+
+        if self.db.name_taken(0, &self.name)? {
+            return Err(ServerError::Api {
+                message: "username is already taken".to_string(),
+            });
+        }
+
+        let hash = hash_password(&self.password)?;
+
+        // registering logs the user in as well
+        let id = match self
+            .db
+            .add_account(&self.name, &self.addr.to_string(), &hash)
+        {
+            Ok(id) => id,
+            Err(StorageError::AlreadyExists) => {
+                return Err(ServerError::Api {
+                    message: "username is already taken".to_string(),
+                })
+            }
+            Err(e) => return Err(ServerError::InternalStorage(e)),
+        };
+
+        let token = generate_token();
+        self.db.add_session(&token, id)?;
+
+        Ok((id, token))
+    }
+}
+
+impl Request for RegisterRequest {
+    fn execute(&mut self) -> Result<()> {
+        let (res_type, res) = error_check(self.handler())?;
+
+        if let Err(e) = self.stream.send(res_type, &res) {
+            return Err(anyhow!(format!("couldn't send: {e:?}")));
+        }
+
+        Ok(())
+    }
+}
@@ -1,13 +1,19 @@
+use std::collections::HashMap;
 use std::net::TcpStream;
 use std::sync::{Arc, Mutex};
 
 use anyhow::{anyhow, Result};
 
+use super::db::Db;
+use super::lobby::{start_lobby, Lobby};
+use super::registry::LobbyRegistry;
 use super::request_handlers::{
-    ChangeNameRequest, ConnectRequest, CreateLobbyRequest, DisconnectRequest, GetLobbiesRequest,
-    InvalidRequest, PingRequest,
+    ChangeNameRequest, ConnectRequest, CreateLobbyRequest, DisconnectRequest,
+    GetGameHistoryRequest, GetGameRequest, GetLeaderboardRequest, GetLobbiesRequest,
+    GetMatchRequest, GetProfileRequest, GetProfileShortRequest, InvalidRequest, LoginRequest,
+    PingRequest, RegisterRequest,
 };
-use super::types::{LobbyId, LobbyVec};
+use super::types::{LobbyId, LobbyVec, ProfileCache, Registry};
 use super::{RequestHandler, RequestQueueItem, ServerCore};
 use network::{SendRecv, Type};
 
@@ -15,6 +21,8 @@ pub struct Server {
     server: ServerCore,
     lobby_id: LobbyId,
     lobbies: LobbyVec,
+    registry: Registry,
+    profiles: ProfileCache,
 }
 
 impl RequestHandler for Server {
@@ -35,15 +43,27 @@ impl RequestHandler for Server {
                 Ok(buf) => Box::new(ConnectRequest::new(
                     stream,
                     buf,
-                    self.server.db_pool.clone(),
+                    Arc::clone(&self.server.db),
                 )),
                 Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
             },
+            Type::Register => match bincode::deserialize(&buf) {
+                Ok(buf) => Box::new(RegisterRequest::new(
+                    stream,
+                    buf,
+                    Arc::clone(&self.server.db),
+                )),
+                Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
+            },
+            Type::Login => match bincode::deserialize(&buf) {
+                Ok(buf) => Box::new(LoginRequest::new(stream, buf, Arc::clone(&self.server.db))),
+                Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
+            },
             Type::Disconnect => match bincode::deserialize(&buf) {
                 Ok(buf) => Box::new(DisconnectRequest::new(
                     stream,
                     buf,
-                    self.server.db_pool.clone(),
+                    Arc::clone(&self.server.db),
                 )),
                 Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
             },
@@ -53,7 +73,9 @@ impl RequestHandler for Server {
                     buf,
                     Arc::clone(&self.lobby_id),
                     Arc::clone(&self.lobbies),
-                    self.server.db_pool.clone(),
+                    Arc::clone(&self.registry),
+                    Arc::clone(&self.server.running),
+                    Arc::clone(&self.server.db),
                 )),
                 Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
             },
@@ -62,7 +84,7 @@ impl RequestHandler for Server {
                     stream,
                     buf,
                     Arc::clone(&self.lobbies),
-                    self.server.db_pool.clone(),
+                    Arc::clone(&self.server.db),
                 )),
                 Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
             },
@@ -70,7 +92,58 @@ impl RequestHandler for Server {
                 Ok(buf) => Box::new(ChangeNameRequest::new(
                     stream,
                     buf,
-                    self.server.db_pool.clone(),
+                    Arc::clone(&self.registry),
+                    Arc::clone(&self.server.db),
+                )),
+                Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
+            },
+            Type::GetGameHistory => match bincode::deserialize(&buf) {
+                Ok(buf) => Box::new(GetGameHistoryRequest::new(
+                    stream,
+                    buf,
+                    Arc::clone(&self.server.db),
+                )),
+                Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
+            },
+            Type::GetGame => match bincode::deserialize(&buf) {
+                Ok(buf) => Box::new(GetGameRequest::new(
+                    stream,
+                    buf,
+                    Arc::clone(&self.server.db),
+                )),
+                Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
+            },
+            Type::GetMatch => match bincode::deserialize(&buf) {
+                Ok(buf) => Box::new(GetMatchRequest::new(
+                    stream,
+                    buf,
+                    Arc::clone(&self.server.db),
+                )),
+                Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
+            },
+            Type::GetLeaderboard => match bincode::deserialize(&buf) {
+                Ok(buf) => Box::new(GetLeaderboardRequest::new(
+                    stream,
+                    buf,
+                    Arc::clone(&self.server.db),
+                )),
+                Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
+            },
+            Type::GetProfile => match bincode::deserialize(&buf) {
+                Ok(buf) => Box::new(GetProfileRequest::new(
+                    stream,
+                    buf,
+                    Arc::clone(&self.profiles),
+                    Arc::clone(&self.server.db),
+                )),
+                Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
+            },
+            Type::GetProfileShort => match bincode::deserialize(&buf) {
+                Ok(buf) => Box::new(GetProfileShortRequest::new(
+                    stream,
+                    buf,
+                    Arc::clone(&self.profiles),
+                    Arc::clone(&self.server.db),
                 )),
                 Err(_) => Box::new(InvalidRequest::new(stream, "invalid data")),
             },
@@ -80,8 +153,8 @@ impl RequestHandler for Server {
 }
 
 impl Server {
-    pub fn new(addr: &str) -> Result<Server> {
-        let server = ServerCore::new(addr)?;
+    pub fn new(addr: &str, db: Db) -> Result<Server> {
+        let server = ServerCore::new(addr, db)?;
 
         // create sighandler that sets it to false to signal that the server should stop
         {
@@ -96,11 +169,39 @@ impl Server {
             })?;
         }
 
-        Ok(Server {
+        let server = Server {
             server,
             lobby_id: Arc::new(Mutex::new(0)),
             lobbies: Arc::new(Mutex::new(vec![])),
-        })
+            registry: Arc::new(Mutex::new(LobbyRegistry::default())),
+            profiles: Arc::new(Mutex::new(HashMap::new())),
+        };
+
+        server.restore_lobbies()?;
+
+        Ok(server)
+    }
+
+    // restarts the lobbies that were open when the server stopped, under the same ids
+    fn restore_lobbies(&self) -> Result<()> {
+        for record in self.server.db.get_lobbies()? {
+            {
+                let mut lobby_id = self.lobby_id.lock().unwrap();
+                *lobby_id = (*lobby_id).max(record.id.saturating_add(1));
+            }
+
+            let lobby = Lobby::new(
+                "127.0.0.1:0",
+                record,
+                Arc::clone(&self.registry),
+                Arc::clone(&self.server.db),
+                Arc::clone(&self.server.running),
+            )?;
+
+            start_lobby(lobby, &self.lobbies, &self.registry)?;
+        }
+
+        Ok(())
     }
 
     pub fn start(&self) -> Result<()> {
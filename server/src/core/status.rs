@@ -0,0 +1,351 @@
+use std::time::Duration;
+
+use rules::{GameState, MatchState};
+use serde_derive::{Deserialize, Serialize};
+
+// time the players get between the host starting the game and the first move
+pub const COUNTDOWN: Duration = Duration::from_secs(3);
+
+// the phases a lobby goes through, in order, before going back to waiting for the next game
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Phase {
+    Waiting,
+    Countdown,
+    InGame,
+    Finished,
+}
+
+// sent to the users of the lobby on every transition
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct PhaseChange {
+    pub from: Phase,
+    pub to: Phase,
+}
+
+// what a request wants to do in the lobby, each phase allows only some of them
+pub enum Action {
+    ChangeRoles,
+    ChangeSettings,
+    // along with the match the game is part of, if any
+    StartGame(Box<GameState>, Option<MatchState>),
+    BeginGame, // the countdown is over
+    MakeMove,
+    EndGame,
+    VoteRematch,
+}
+
+pub enum LobbyStatus {
+    Waiting,
+    // the game is set up and begins once the countdown is over, along with the match it's part of
+    Countdown(GameState, Option<MatchState>),
+    InGame(GameState, Option<MatchState>),
+    Finished(Rematch), // the results stay up until the lobby is set up for the next game
+}
+
+// the players of the game that just ended and the ones that want to play again, setting the lobby
+// up differently forgets about it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Rematch {
+    pub angel: u32,
+    pub devil: u32,
+    pub votes: Vec<u32>,
+    pub series: Option<MatchState>, // the match the game was part of, it goes on with the rematch
+}
+
+impl LobbyStatus {
+    // a lobby restored with a game goes on with it, its clocks stay stopped until both players are
+    // back, one restored between the games of a match waits for its players to start the next one
+    pub fn restore(game: Option<GameState>, series: Option<MatchState>) -> LobbyStatus {
+        let series = series.filter(|series| !series.is_over());
+
+        match (game, series) {
+            (Some(game), series) => LobbyStatus::InGame(game, series),
+            (None, Some(series)) => {
+                // the sides swap after every game
+                let (devil, angel) = series.next_roles();
+                LobbyStatus::Finished(Rematch {
+                    angel,
+                    devil,
+                    votes: vec![],
+                    series: Some(series),
+                })
+            }
+            (None, None) => LobbyStatus::Waiting,
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        match self {
+            LobbyStatus::Waiting => Phase::Waiting,
+            LobbyStatus::Countdown(..) => Phase::Countdown,
+            LobbyStatus::InGame(..) => Phase::InGame,
+            LobbyStatus::Finished(_) => Phase::Finished,
+        }
+    }
+
+    // the game being played, if any
+    pub fn game(&self) -> Option<&GameState> {
+        match self {
+            LobbyStatus::InGame(game, _) => Some(game),
+            _ => None,
+        }
+    }
+
+    pub fn game_mut(&mut self) -> Option<&mut GameState> {
+        match self {
+            LobbyStatus::InGame(game, _) => Some(game),
+            _ => None,
+        }
+    }
+
+    // the match being played, it stays up with the results of its last game
+    pub fn series(&self) -> Option<&MatchState> {
+        match self {
+            LobbyStatus::Waiting => None,
+            LobbyStatus::Countdown(_, series) | LobbyStatus::InGame(_, series) => series.as_ref(),
+            LobbyStatus::Finished(rematch) => rematch.series.as_ref(),
+        }
+    }
+
+    pub fn series_mut(&mut self) -> Option<&mut MatchState> {
+        match self {
+            LobbyStatus::Waiting => None,
+            LobbyStatus::Countdown(_, series) | LobbyStatus::InGame(_, series) => series.as_mut(),
+            LobbyStatus::Finished(rematch) => rematch.series.as_mut(),
+        }
+    }
+
+    // the last game, while its players can still vote for a rematch
+    pub fn rematch(&self) -> Option<&Rematch> {
+        match self {
+            LobbyStatus::Finished(rematch) => Some(rematch),
+            _ => None,
+        }
+    }
+
+    pub fn rematch_mut(&mut self) -> Option<&mut Rematch> {
+        match self {
+            LobbyStatus::Finished(rematch) => Some(rematch),
+            _ => None,
+        }
+    }
+
+    // the unfinished match setting the lobby up with the action leaves behind, marked as abandoned
+    // so it can be stored
+    pub fn abandons(&self, action: &Action) -> Option<MatchState> {
+        let series = self.series().filter(|series| !series.is_over())?;
+
+        if self.phase() != Phase::Finished || !action.sets_up() || self.check(action).is_err() {
+            return None;
+        }
+
+        // starting the next game of the match carries it on
+        if let Action::StartGame(_, Some(next)) = action {
+            if next == series {
+                return None;
+            }
+        }
+
+        let mut series = series.clone();
+        series.abandoned = true;
+
+        Some(series)
+    }
+
+    // whether a game can be set up, lets the board be built only once it's worth it
+    pub fn can_start(&self) -> Result<(), &'static str> {
+        match self.phase() {
+            Phase::Countdown | Phase::InGame => Err("game is already started"),
+            Phase::Waiting | Phase::Finished => Ok(()),
+        }
+    }
+
+    // whether the action is allowed in the current phase, without doing it
+    pub fn check(&self, action: &Action) -> Result<(), &'static str> {
+        let phase = self.phase();
+        let between_games = phase == Phase::Waiting || phase == Phase::Finished;
+
+        match action {
+            Action::ChangeRoles if !between_games => {
+                Err("cannot change roles while a game is going on")
+            }
+            Action::ChangeSettings if !between_games => {
+                Err("cannot change settings while a game is going on")
+            }
+            Action::StartGame(..) => self.can_start(),
+            Action::BeginGame if phase != Phase::Countdown => Err("no countdown is going on"),
+            Action::MakeMove | Action::EndGame if phase != Phase::InGame => {
+                Err("game is not started yet")
+            }
+            Action::VoteRematch if phase != Phase::Finished => Err("there is no game to rematch"),
+            _ => Ok(()),
+        }
+    }
+
+    // moves the lobby to the phase the action leads to, returns every transition made on the way
+    // so the users can be told about each of them
+    pub fn apply(&mut self, action: Action) -> Result<Vec<PhaseChange>, &'static str> {
+        self.check(&action)?;
+
+        let mut changes = vec![];
+
+        // setting the lobby up for the next game leaves the results of the last one behind
+        if self.phase() == Phase::Finished && action.sets_up() {
+            *self = LobbyStatus::Waiting;
+            changes.push(PhaseChange {
+                from: Phase::Finished,
+                to: Phase::Waiting,
+            });
+        }
+
+        let from = self.phase();
+        *self = match (std::mem::replace(self, LobbyStatus::Waiting), action) {
+            (_, Action::StartGame(game, series)) => LobbyStatus::Countdown(*game, series),
+            (LobbyStatus::Countdown(mut game, series), Action::BeginGame) => {
+                game.start_clock();
+                LobbyStatus::InGame(game, series)
+            }
+            (LobbyStatus::InGame(game, series), Action::EndGame) => {
+                LobbyStatus::Finished(Rematch {
+                    angel: game.angel,
+                    devil: game.devil,
+                    votes: vec![],
+                    series,
+                })
+            }
+            (status, _) => status,
+        };
+
+        if self.phase() != from {
+            changes.push(PhaseChange {
+                from,
+                to: self.phase(),
+            });
+        }
+
+        Ok(changes)
+    }
+}
+
+impl Action {
+    fn sets_up(&self) -> bool {
+        matches!(
+            self,
+            Action::ChangeRoles | Action::ChangeSettings | Action::StartGame(..)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rules::GameSettings;
+
+    use super::*;
+
+    #[test]
+    fn transitions() {
+        let game = || Box::new(GameState::new(0, 1, &GameSettings::default()));
+        let change = |from, to| PhaseChange { from, to };
+        let mut status = LobbyStatus::Waiting;
+
+        assert_eq!(status.apply(Action::ChangeSettings), Ok(vec![]));
+        assert!(status.apply(Action::MakeMove).is_err());
+        assert!(status.apply(Action::BeginGame).is_err());
+        assert!(status.apply(Action::VoteRematch).is_err());
+
+        assert_eq!(
+            status.apply(Action::StartGame(game(), None)),
+            Ok(vec![change(Phase::Waiting, Phase::Countdown)])
+        );
+        assert!(status.game().is_none());
+        assert!(status.apply(Action::ChangeRoles).is_err());
+        assert!(status.apply(Action::StartGame(game(), None)).is_err());
+
+        assert_eq!(
+            status.apply(Action::BeginGame),
+            Ok(vec![change(Phase::Countdown, Phase::InGame)])
+        );
+        assert!(status.game().is_some());
+        assert_eq!(status.apply(Action::MakeMove), Ok(vec![]));
+        assert!(status.apply(Action::ChangeSettings).is_err());
+
+        assert_eq!(
+            status.apply(Action::EndGame),
+            Ok(vec![change(Phase::InGame, Phase::Finished)])
+        );
+        assert!(status.apply(Action::MakeMove).is_err());
+
+        // the players of the last game can vote for a rematch until the lobby is set up again
+        assert_eq!(
+            status.rematch(),
+            Some(&Rematch {
+                angel: 0,
+                devil: 1,
+                votes: vec![],
+                series: None,
+            })
+        );
+        assert_eq!(status.apply(Action::VoteRematch), Ok(vec![]));
+
+        // a match is carried from game to game
+        let series = MatchState::new(3, 1, 0, false);
+        status
+            .apply(Action::StartGame(game(), Some(series.clone())))
+            .unwrap();
+        assert_eq!(status.series(), Some(&series));
+        status.apply(Action::BeginGame).unwrap();
+        status.series_mut().unwrap().record(1, 0, 1, None);
+        status.apply(Action::EndGame).unwrap();
+        assert_eq!(status.rematch().unwrap().series.as_ref().unwrap().draws, 1);
+
+        // going on with the match keeps it, setting the lobby up otherwise leaves it unfinished
+        let series = status.series().cloned().unwrap();
+        let next = Action::StartGame(game(), Some(series.clone()));
+        assert!(status.abandons(&next).is_none());
+        assert!(status.abandons(&Action::VoteRematch).is_none());
+        let abandoned = status.abandons(&Action::ChangeSettings).unwrap();
+        assert!(abandoned.abandoned);
+        assert_eq!(abandoned.game_ids, series.game_ids);
+
+        // the next game goes back through waiting
+        assert_eq!(
+            status.apply(Action::StartGame(game(), None)),
+            Ok(vec![
+                change(Phase::Finished, Phase::Waiting),
+                change(Phase::Waiting, Phase::Countdown)
+            ])
+        );
+
+        let mut status = LobbyStatus::restore(Some(*game()), None);
+        assert_eq!(status.phase(), Phase::InGame);
+        assert!(status.game().unwrap().turn_started.is_none());
+        status.apply(Action::EndGame).unwrap();
+        assert_eq!(
+            status.apply(Action::ChangeRoles),
+            Ok(vec![change(Phase::Finished, Phase::Waiting)])
+        );
+        assert!(status.rematch().is_none());
+
+        // a lobby restored between the games of a match waits for the next one
+        let mut status = LobbyStatus::restore(None, Some(series.clone()));
+        assert_eq!(status.phase(), Phase::Finished);
+        assert_eq!(status.series(), Some(&series));
+        assert_eq!(
+            status
+                .rematch()
+                .map(|rematch| (rematch.angel, rematch.devil)),
+            Some((0, 1))
+        );
+        assert!(status
+            .apply(Action::StartGame(game(), Some(series.clone())))
+            .is_ok());
+        assert_eq!(status.series(), Some(&series));
+
+        let mut over = series.clone();
+        over.forfeit = Some(1);
+        assert_eq!(
+            LobbyStatus::restore(None, Some(over)).phase(),
+            Phase::Waiting
+        );
+    }
+}
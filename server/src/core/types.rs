@@ -1,13 +1,21 @@
 use std::{
     cell::RefCell,
+    collections::HashMap,
     net::SocketAddr,
     sync::{Arc, Condvar, Mutex},
     thread::JoinHandle,
+    time::Instant,
 };
 
+use rules::{GameSettings, GameState, MatchState};
 use serde_derive::{Deserialize, Serialize};
 
-use super::{game::GameState, request_handlers::Request};
+use super::{
+    db::{ChatMessage, ProfileStats},
+    registry::LobbyRegistry,
+    request_handlers::Request,
+    status::{LobbyStatus, Phase},
+};
 
 pub type BoolMutex = Arc<Mutex<bool>>;
 
@@ -55,6 +63,17 @@ pub type LobbyName = Arc<Mutex<String>>;
 pub type LobbyInfo = (u16, SocketAddr, BoolMutex, JoinHandle<()>);
 pub type LobbyVec = Arc<Mutex<Vec<LobbyInfo>>>;
 
+pub type Registry = Arc<Mutex<LobbyRegistry>>;
+
+// profile stats by user id, without the recent games
+pub type ProfileCache = Arc<Mutex<HashMap<u32, CachedProfile>>>;
+
+pub struct CachedProfile {
+    pub last_game: Option<u32>, // the game the stats were computed up to
+    pub stats: ProfileStats,
+    pub viewed: Instant,
+}
+
 #[derive(Serialize)]
 pub struct LobbyAddr {
     pub id: u16,
@@ -65,7 +84,11 @@ pub struct LobbyAddr {
 pub struct LobbyState {
     pub name: String,
     pub users: Vec<UserInfoShort>,
+    pub chat: Vec<ChatMessage>, // the latest messages, oldest first
+    pub settings: GameSettings,
     pub game: Option<GameState>,
+    pub series: Option<MatchState>, // the match being played, if any
+    pub phase: Phase,
 }
 
 #[derive(Serialize)]
@@ -73,6 +96,12 @@ pub struct LobbyStateShort {
     pub name: String,
     pub users: u32,
     pub game_going: bool,
+    pub chat: Vec<ChatMessage>,
+    pub settings: GameSettings,
+    pub phase: Phase,
 }
 
-pub type Game = Arc<Mutex<Option<GameState>>>;
+// a handler that needs both the status and the users of a lobby locks the status first, the
+// countdown and clock threads do the same
+pub type Status = Arc<Mutex<LobbyStatus>>;
+pub type Settings = Arc<Mutex<GameSettings>>;
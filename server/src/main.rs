@@ -1,11 +1,26 @@
+use std::env;
+use std::sync::Arc;
+
+use core::db::{Db, MemoryStorage, SqliteStorage, DB_NAME};
 use core::*;
 
 mod core;
 
 fn main() {
-    db::init_db().unwrap();
+    // --memory runs a throwaway server that keeps nothing once it stops
+    let db: Db = if env::args().any(|arg| arg == "--memory") {
+        Arc::new(MemoryStorage::default())
+    } else {
+        match SqliteStorage::open(DB_NAME) {
+            Ok(db) => Arc::new(db),
+            Err(e) => {
+                println!("couldn't initialize the database: {e:?}");
+                return;
+            }
+        }
+    };
 
-    let server = Server::new("127.0.0.1:20000").unwrap();
+    let server = Server::new("127.0.0.1:20000", db).unwrap();
 
     server.start().unwrap();
 }